@@ -0,0 +1,94 @@
+use std::fs;
+
+use crate::interpreter::ast_printer::AstPrinter;
+use crate::interpreter::parser::Parser;
+use crate::interpreter::scanner::Scanner;
+use crate::interpreter::stmt::Stmt;
+
+#[cfg(not(feature = "ast-serde"))]
+const NOT_BUILT: &str = "lox was built without the 'ast-serde' feature.";
+
+/// Serializes a program to pretty-printed JSON.
+pub fn to_json(statements: &[Stmt]) -> Result<String, String> {
+    #[cfg(feature = "ast-serde")]
+    {
+        serde_json::to_string_pretty(statements).map_err(|err| err.to_string())
+    }
+    #[cfg(not(feature = "ast-serde"))]
+    {
+        let _ = statements;
+        Err(NOT_BUILT.to_string())
+    }
+}
+
+/// Reads a program back from JSON written by `to_json`, or generated by
+/// other tools in the same shape. Expression ids are not part of the JSON;
+/// each loaded expression gets a fresh one, so programs whose positions
+/// are all made up still resolve every variable on its own.
+pub fn from_json(json: &str) -> Result<Vec<Stmt>, String> {
+    #[cfg(feature = "ast-serde")]
+    {
+        serde_json::from_str(json).map_err(|err| format!("Invalid AST: {}.", err))
+    }
+    #[cfg(not(feature = "ast-serde"))]
+    {
+        let _ = json;
+        Err(NOT_BUILT.to_string())
+    }
+}
+
+/// Parses a source file, or returns the first syntax error.
+fn parse_source(source: &str) -> Result<Vec<Stmt>, String> {
+    let mut scanner = Scanner::new(source.to_string());
+    let tokens = scanner.scan_tokens();
+    if let Some(error) = scanner.errors().first() {
+        return Err(format!("[line {}] {}", error.line, error.message));
+    }
+    Parser::new(tokens)
+        .parse()
+        .map_err(|error| format!("[line {}] {}", error.token.line, error.message))
+}
+
+/// Runs `lox ast [--json] <file>`, printing the parsed program as
+/// S-expressions or, with `--json`, as JSON that `lox --json-ast` runs.
+pub fn command(args: &[String]) -> i32 {
+    let (json, path) = match args {
+        [flag, path] if flag == "--json" => (true, path),
+        [path] if path != "--json" => (false, path),
+        _ => {
+            eprintln!("Usage: lox ast [--json] <file>");
+            return 64;
+        }
+    };
+
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("{}: {}", path, err);
+            return 74;
+        }
+    };
+    let statements = match parse_source(&source) {
+        Ok(statements) => statements,
+        Err(message) => {
+            eprintln!("{}: {}", path, message);
+            return 65;
+        }
+    };
+
+    if json {
+        match to_json(&statements) {
+            Ok(json) => println!("{}", json),
+            Err(message) => {
+                eprintln!("{}", message);
+                return 70;
+            }
+        }
+    } else {
+        let mut printer = AstPrinter::new();
+        for statement in &statements {
+            println!("{}", printer.print_stmt(statement));
+        }
+    }
+    0
+}
@@ -1,7 +1,9 @@
+use std::rc::Rc;
+
 use crate::interpreter::expr::{Expr, ExprVisitor};
 use crate::interpreter::stmt::{
     Stmt, StmtBlock, StmtClass, StmtExpression, StmtFunction, StmtIf,
-    StmtPrint, StmtReturn, StmtVar, StmtWhile, StmtVisitor,
+    StmtPrint, StmtReturn, StmtTest, StmtVar, StmtWhile, StmtVisitor,
 };
 pub struct AstPrinter;
 
@@ -10,6 +12,7 @@ impl AstPrinter {
         AstPrinter
     }
 
+    #[allow(dead_code)] // Handy when debugging the parser
     pub fn print_expr(&mut self, expr: &Expr) -> String {
         expr.accept(self)
     }
@@ -48,7 +51,7 @@ impl AstPrinter {
 // Implement the ExprVisitor trait for AstPrinter
 impl ExprVisitor<String> for AstPrinter {
     fn visit_assign_expr(&mut self, expr: &Expr) -> String {
-        if let Expr::Assign { name, value } = expr {
+        if let Expr::Assign { name, value, .. } = expr {
             self.parenthesize2("=", &[&name.lexeme, value])
         } else {
             String::new()
@@ -64,7 +67,7 @@ impl ExprVisitor<String> for AstPrinter {
     }
 
     fn visit_call_expr(&mut self, expr: &Expr) -> String {
-        if let Expr::Call { callee, arguments } = expr {
+        if let Expr::Call { callee, arguments, .. } = expr {
             let mut parts: Vec<&dyn ToString> = vec![callee];
             parts.extend(arguments.iter().map(|arg| arg as &dyn ToString));
             self.parenthesize2("call", &parts)
@@ -82,7 +85,7 @@ impl ExprVisitor<String> for AstPrinter {
     }
 
     fn visit_literal_expr(&mut self, expr: &Expr) -> String {
-        if let Expr::Literal { value } = expr {
+        if let Expr::Literal { value, .. } = expr {
             match value {
                 Some(v) => format!("{:?}", v),
                 None => "nil".to_string(),
@@ -109,7 +112,7 @@ impl ExprVisitor<String> for AstPrinter {
     }
 
     fn visit_variable_expr(&mut self, expr: &Expr) -> String {
-        if let Expr::Variable { name } = expr {
+        if let Expr::Variable { name, .. } = expr {
             name.lexeme.clone()
         } else {
             String::new()
@@ -132,17 +135,63 @@ impl ExprVisitor<String> for AstPrinter {
         }
     }
 
+    fn visit_index_expr(&mut self, expr: &Expr) -> String {
+        if let Expr::Index { object, index, .. } = expr {
+            self.parenthesize("[]", &[object, index])
+        } else {
+            String::new()
+        }
+    }
+
     fn visit_super_expr(&mut self, expr: &Expr) -> String {
-        if let Expr::Super { method } = expr {
+        if let Expr::Super { method, .. } = expr {
             self.parenthesize2("super", &[&method.lexeme])
         } else {
             String::new()
         }
     }
 
-    fn visit_this_expr(&mut self, expr: &Expr) -> String {
+    fn visit_this_expr(&mut self, _expr: &Expr) -> String {
         "this".to_string()
     }
+
+    fn visit_lambda_expr(&mut self, expr: &Expr) -> String {
+        if let Expr::Lambda { declaration } = expr {
+            let mut builder = String::new();
+            builder.push_str("(fun (");
+            for (i, param) in declaration.params.iter().enumerate() {
+                if i > 0 {
+                    builder.push(' ');
+                }
+                builder.push_str(&param.lexeme);
+            }
+            builder.push_str(") ");
+            for body_stmt in &declaration.body {
+                builder.push_str(&body_stmt.accept(self));
+            }
+            builder.push(')');
+            builder
+        } else {
+            String::new()
+        }
+    }
+
+    fn visit_interpolation_expr(&mut self, expr: &Expr) -> String {
+        if let Expr::Interpolation { segments, values, .. } = expr {
+            let mut builder = String::from("(interpolate");
+            for (i, segment) in segments.iter().enumerate() {
+                builder.push_str(&format!(" {:?}", segment));
+                if let Some(value) = values.get(i) {
+                    builder.push(' ');
+                    builder.push_str(&value.accept(self));
+                }
+            }
+            builder.push(')');
+            builder
+        } else {
+            String::new()
+        }
+    }
 }
 
 impl StmtVisitor<String> for AstPrinter {
@@ -172,15 +221,30 @@ impl StmtVisitor<String> for AstPrinter {
             builder.push_str(" < ");
             builder.push_str(&superclass.to_string());
         }
+        for method in &stmt.class_methods {
+            builder.push_str(" (class ");
+            builder.push_str(&method.accept(self));
+            builder.push(')');
+        }
         for method in &stmt.methods {
-            builder.push_str(" ");
+            builder.push(' ');
             builder.push_str(&method.accept(self));
         }
+        for getter in &stmt.getters {
+            builder.push_str(" (get ");
+            builder.push_str(&getter.accept(self));
+            builder.push(')');
+        }
+        for setter in &stmt.setters {
+            builder.push_str(" (set ");
+            builder.push_str(&setter.accept(self));
+            builder.push(')');
+        }
         builder.push(')');
         builder
     }
 
-    fn visit_function_stmt(&mut self, stmt: &StmtFunction) -> String {
+    fn visit_function_stmt(&mut self, stmt: &Rc<StmtFunction>) -> String {
         let mut builder = String::new();
         builder.push_str("(fun ");
         builder.push_str(&stmt.name.lexeme);
@@ -236,12 +300,26 @@ impl StmtVisitor<String> for AstPrinter {
         builder
     }
 
+    fn visit_test_stmt(&mut self, stmt: &StmtTest) -> String {
+        let mut builder = String::new();
+        builder.push_str(&format!("(test \"{}\" ", stmt.name));
+        for statement in &stmt.body {
+            builder.push_str(&statement.accept(self));
+        }
+        builder.push(')');
+        builder
+    }
+
     fn visit_while_stmt(&mut self, stmt: &StmtWhile) -> String {
         let mut builder = String::new();
         builder.push_str("(while ");
         builder.push_str(&stmt.condition.to_string());
         builder.push(' ');
         builder.push_str(&stmt.body.accept(self));
+        if let Some(increment) = &stmt.increment {
+            builder.push(' ');
+            builder.push_str(&increment.to_string());
+        }
         builder.push(')');
         builder
     }
@@ -0,0 +1,415 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// Limbs are stored in base 10^9 so parsing and printing stay simple.
+const BASE: u64 = 1_000_000_000;
+
+/// A signed arbitrary-precision integer, used when integer arithmetic
+/// overflows `i64`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    negative: bool,
+    limbs: Vec<u32>, // Little-endian, no trailing zero limbs; zero is empty
+}
+
+impl BigInt {
+    /// Creates a BigInt from a machine integer.
+    pub fn from_i64(value: i64) -> Self {
+        let mut magnitude = (value as i128).unsigned_abs();
+        let mut limbs = Vec::new();
+        while magnitude > 0 {
+            limbs.push((magnitude % BASE as u128) as u32);
+            magnitude /= BASE as u128;
+        }
+        Self::from_parts(value < 0, limbs)
+    }
+
+    /// Parses an optionally signed string of decimal digits.
+    pub fn parse(text: &str) -> Option<Self> {
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        let mut limbs = Vec::new();
+        let mut end = digits.len();
+        while end > 0 {
+            let start = end.saturating_sub(9);
+            limbs.push(digits[start..end].parse().ok()?);
+            end = start;
+        }
+        Some(Self::from_parts(negative, limbs))
+    }
+
+    /// Returns the value as an `i64` if it fits.
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.limbs.len() > 3 {
+            return None;
+        }
+        let mut magnitude: i128 = 0;
+        for limb in self.limbs.iter().rev() {
+            magnitude = magnitude * BASE as i128 + *limb as i128;
+        }
+        let value = if self.negative { -magnitude } else { magnitude };
+        i64::try_from(value).ok()
+    }
+
+    /// Converts to the nearest `f64`.
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self
+            .limbs
+            .iter()
+            .rev()
+            .fold(0.0, |acc, limb| acc * BASE as f64 + *limb as f64);
+        if self.negative {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// Floored division: the quotient rounds towards negative infinity and
+    /// the remainder takes the sign of the divisor. Returns `None` when
+    /// dividing by zero.
+    pub fn div_rem_floor(&self, divisor: &BigInt) -> Option<(BigInt, BigInt)> {
+        if divisor.is_zero() {
+            return None;
+        }
+
+        let (quotient, remainder) = div_rem_magnitude(&self.limbs, &divisor.limbs);
+        let mut quotient = Self::from_parts(self.negative != divisor.negative, quotient);
+        let mut remainder = Self::from_parts(self.negative, remainder);
+
+        if !remainder.is_zero() && remainder.negative != divisor.negative {
+            quotient = &quotient - &BigInt::from_i64(1);
+            remainder = &remainder + divisor;
+        }
+        Some((quotient, remainder))
+    }
+
+    /// Returns the bytes the digits take up on the heap.
+    pub fn heap_size(&self) -> usize {
+        self.limbs.len() * std::mem::size_of::<u32>()
+    }
+
+    /// Multiplies by `2^shift`. Callers bound `shift`, since the result
+    /// grows with it.
+    pub fn shl(&self, shift: u32) -> BigInt {
+        let words = to_words(&self.limbs);
+        let (skip, bits) = ((shift / 32) as usize, shift % 32);
+        let mut shifted = vec![0u32; skip];
+        let mut carry = 0u32;
+        for word in words {
+            shifted.push(if bits == 0 { word } else { word << bits | carry });
+            carry = if bits == 0 { 0 } else { word >> (32 - bits) };
+        }
+        shifted.push(carry);
+        Self::from_parts(self.negative, from_words(&shifted))
+    }
+
+    /// Divides by `2^shift`, rounding towards negative infinity like an
+    /// arithmetic shift.
+    pub fn shr(&self, shift: u64) -> BigInt {
+        let words = to_words(&self.limbs);
+        let len = words.len() + 1; // Room for the sign bit
+        let words = twos_complement(self.negative, words, len);
+        let fill = if self.negative { u32::MAX } else { 0 };
+        let word = |i: usize| words.get(i).copied().unwrap_or(fill);
+
+        let skip = usize::try_from(shift / 32).unwrap_or(usize::MAX);
+        let bits = (shift % 32) as u32;
+        let shifted = (0..len)
+            .map(|i| match i.checked_add(skip) {
+                Some(from) if bits == 0 => word(from),
+                Some(from) => word(from) >> bits | word(from.saturating_add(1)) << (32 - bits),
+                None => fill,
+            })
+            .collect();
+        Self::from_twos_complement(shifted)
+    }
+
+    /// Applies a bitwise operation word by word, treating both values as
+    /// two's complement with as many sign bits as needed.
+    pub fn bitwise(&self, other: &BigInt, op: impl Fn(u32, u32) -> u32) -> BigInt {
+        let (a, b) = (to_words(&self.limbs), to_words(&other.limbs));
+        let len = a.len().max(b.len()) + 1; // Room for the sign bit
+        let a = twos_complement(self.negative, a, len);
+        let b = twos_complement(other.negative, b, len);
+        Self::from_twos_complement(a.iter().zip(&b).map(|(a, b)| op(*a, *b)).collect())
+    }
+
+    fn from_twos_complement(words: Vec<u32>) -> Self {
+        let negative = words.last().is_some_and(|word| word >> 31 == 1);
+        let magnitude = if negative { negate_words(words) } else { words };
+        Self::from_parts(negative, from_words(&magnitude))
+    }
+
+    fn from_parts(negative: bool, mut limbs: Vec<u32>) -> Self {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        let negative = negative && !limbs.is_empty();
+        Self { negative, limbs }
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::from_parts(self.negative, add_magnitude(&self.limbs, &other.limbs));
+        }
+
+        match compare_magnitude(&self.limbs, &other.limbs) {
+            Ordering::Less => BigInt::from_parts(other.negative, sub_magnitude(&other.limbs, &self.limbs)),
+            _ => BigInt::from_parts(self.negative, sub_magnitude(&self.limbs, &other.limbs)),
+        }
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: &BigInt) -> BigInt {
+        self + &(-other)
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: &BigInt) -> BigInt {
+        let mut product = vec![0u64; self.limbs.len() + other.limbs.len()];
+        for (i, a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, b) in other.limbs.iter().enumerate() {
+                let current = product[i + j] + *a as u64 * *b as u64 + carry;
+                product[i + j] = current % BASE;
+                carry = current / BASE;
+            }
+            let mut k = i + other.limbs.len();
+            while carry > 0 {
+                let current = product[k] + carry;
+                product[k] = current % BASE;
+                carry = current / BASE;
+                k += 1;
+            }
+        }
+        let limbs = product.into_iter().map(|limb| limb as u32).collect();
+        BigInt::from_parts(self.negative != other.negative, limbs)
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::from_parts(!self.negative, self.limbs.clone())
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare_magnitude(&self.limbs, &other.limbs),
+            (true, true) => compare_magnitude(&other.limbs, &self.limbs),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.limbs.split_last() {
+            None => write!(f, "0"),
+            Some((most_significant, rest)) => {
+                if self.negative {
+                    write!(f, "-")?;
+                }
+                write!(f, "{}", most_significant)?;
+                for limb in rest.iter().rev() {
+                    write!(f, "{:09}", limb)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// Serialized as a decimal string, which keeps the limbs normalized.
+#[cfg(feature = "ast-serde")]
+impl serde::Serialize for BigInt {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "ast-serde")]
+impl<'de> serde::Deserialize<'de> for BigInt {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        BigInt::parse(&text).ok_or_else(|| serde::de::Error::custom(format!("invalid integer '{}'", text)))
+    }
+}
+
+// Magnitude helpers operating on little-endian base 10^9 limbs.
+
+fn compare_magnitude(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0u64;
+    for i in 0..a.len().max(b.len()) {
+        let sum = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        result.push((sum % BASE) as u32);
+        carry = sum / BASE;
+    }
+    if carry > 0 {
+        result.push(carry as u32);
+    }
+    result
+}
+
+/// Computes `a - b`, assuming `a >= b`.
+fn sub_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for (i, limb) in a.iter().enumerate() {
+        let mut difference = *limb as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = if difference < 0 {
+            difference += BASE as i64;
+            1
+        } else {
+            0
+        };
+        result.push(difference as u32);
+    }
+    result
+}
+
+fn mul_small(a: &[u32], factor: u64) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len() + 1);
+    let mut carry = 0u64;
+    for limb in a {
+        let product = *limb as u64 * factor + carry;
+        result.push((product % BASE) as u32);
+        carry = product / BASE;
+    }
+    while carry > 0 {
+        result.push((carry % BASE) as u32);
+        carry /= BASE;
+    }
+    while result.last() == Some(&0) {
+        result.pop();
+    }
+    result
+}
+
+/// Converts a magnitude to base 2^32 words, least significant first.
+fn to_words(limbs: &[u32]) -> Vec<u32> {
+    let mut words = Vec::new();
+    let mut rest = limbs.to_vec();
+    while !rest.is_empty() {
+        // Divide by 2^32 in place; the remainder is the next word.
+        let mut remainder = 0u64;
+        for limb in rest.iter_mut().rev() {
+            let current = remainder * BASE + *limb as u64;
+            *limb = (current >> 32) as u32;
+            remainder = current & u32::MAX as u64;
+        }
+        words.push(remainder as u32);
+        while rest.last() == Some(&0) {
+            rest.pop();
+        }
+    }
+    words
+}
+
+/// Converts base 2^32 words, least significant first, to a magnitude.
+fn from_words(words: &[u32]) -> Vec<u32> {
+    let mut limbs = Vec::new();
+    for word in words.iter().rev() {
+        let mut word = vec![(*word as u64 % BASE) as u32, (*word as u64 / BASE) as u32];
+        while word.last() == Some(&0) {
+            word.pop();
+        }
+        limbs = add_magnitude(&mul_small(&limbs, 1 << 32), &word);
+    }
+    limbs
+}
+
+/// Sign-extends a magnitude's words to `len` words of two's complement.
+fn twos_complement(negative: bool, mut words: Vec<u32>, len: usize) -> Vec<u32> {
+    words.resize(len, 0);
+    if negative {
+        negate_words(words)
+    } else {
+        words
+    }
+}
+
+/// Negates two's complement words: inverts them and adds one.
+fn negate_words(mut words: Vec<u32>) -> Vec<u32> {
+    let mut carry = true;
+    for word in &mut words {
+        let (negated, overflow) = (!*word).overflowing_add(carry as u32);
+        *word = negated;
+        carry = overflow;
+    }
+    words
+}
+
+/// Schoolbook long division of magnitudes, truncating towards zero.
+fn div_rem_magnitude(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    let mut quotient = vec![0u32; a.len()];
+    let mut remainder: Vec<u32> = Vec::new();
+
+    for i in (0..a.len()).rev() {
+        // remainder = remainder * BASE + a[i]
+        remainder.insert(0, a[i]);
+        while remainder.last() == Some(&0) {
+            remainder.pop();
+        }
+
+        // Binary search for the largest digit q with b * q <= remainder.
+        let (mut low, mut high) = (0u64, BASE - 1);
+        while low < high {
+            let mid = (low + high).div_ceil(2);
+            if compare_magnitude(&mul_small(b, mid), &remainder) != Ordering::Greater {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+
+        if low > 0 {
+            remainder = sub_magnitude(&remainder, &mul_small(b, low));
+            while remainder.last() == Some(&0) {
+                remainder.pop();
+            }
+        }
+        quotient[i] = low as u32;
+    }
+
+    (quotient, remainder)
+}
@@ -0,0 +1,41 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// A thread-safe handle for stopping a running script from another thread.
+/// Obtained from `Interpreter::cancel_handle`; clones share one flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancelHandle {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelHandle {
+    /// Asks the running script to stop at its next statement. If none is
+    /// running, the next one stops as soon as it starts.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns true if cancellation has been requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Clears any request; `Interpreter::interpret` does this once a
+    /// cancelled script has stopped.
+    pub fn reset(&self) {
+        self.cancelled.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Returned by `Interpreter::interpret` when a script was cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interrupted;
+
+impl fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Interrupted.")
+    }
+}
+
+impl std::error::Error for Interrupted {}
@@ -0,0 +1,244 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+
+use crate::interpreter::expr::Expr;
+use crate::interpreter::stmt::{Stmt, StmtFunction};
+use crate::interpreter::token::Token;
+
+/// A point where execution can go two ways: an `if` or loop condition, or
+/// an `and`/`or` that may skip its right operand.
+#[derive(Debug)]
+struct Branch {
+    line: usize,
+    taken: Option<[u64; 2]>, // `None` until it is first reached
+}
+
+/// Records which statements and branches of a program ran, for an LCOV
+/// report. Every executable line and branch is known up front, so the
+/// ones that never ran are reported too.
+#[derive(Debug)]
+pub struct Coverage {
+    lines: BTreeMap<usize, u64>,
+    branches: Vec<Branch>,
+    conditions: HashMap<Expr, usize>, // `if` and loop conditions, to their branch
+    operators: HashMap<Token, usize>, // `and` and `or` operators, to their branch
+}
+
+impl Coverage {
+    /// Finds the executable lines and branches of a program.
+    pub fn new(statements: &[Stmt]) -> Self {
+        let mut coverage = Self {
+            lines: BTreeMap::new(),
+            branches: Vec::new(),
+            conditions: HashMap::new(),
+            operators: HashMap::new(),
+        };
+        for statement in statements {
+            coverage.visit_stmt(statement);
+        }
+        coverage
+    }
+
+    /// Counts a statement about to run against its line.
+    pub fn statement(&mut self, stmt: &Stmt) {
+        // A block is counted through its statements instead.
+        if !matches!(stmt, Stmt::Block(_)) {
+            *self.lines.entry(stmt.line()).or_default() += 1;
+        }
+    }
+
+    /// Records the value an `if` or loop condition had.
+    pub fn condition(&mut self, condition: &Expr, value: bool) {
+        if let Some(&index) = self.conditions.get(condition) {
+            self.take(index, value);
+        }
+    }
+
+    /// Records whether an `and` or `or` ran its right operand.
+    pub fn logical(&mut self, operator: &Token, ran_right: bool) {
+        if let Some(&index) = self.operators.get(operator) {
+            self.take(index, ran_right);
+        }
+    }
+
+    fn take(&mut self, index: usize, first: bool) {
+        let taken = self.branches[index].taken.get_or_insert([0, 0]);
+        taken[if first { 0 } else { 1 }] += 1;
+    }
+
+    /// Formats the counts as an LCOV record for the source file at `path`.
+    pub fn lcov(&self, path: &str) -> String {
+        let mut lcov = String::new();
+        let _ = writeln!(lcov, "TN:");
+        let _ = writeln!(lcov, "SF:{}", path);
+
+        let mut hit = 0;
+        for (block, branch) in self.branches.iter().enumerate() {
+            for outcome in 0..2 {
+                match branch.taken {
+                    Some(taken) => {
+                        let _ = writeln!(
+                            lcov,
+                            "BRDA:{},{},{},{}",
+                            branch.line, block, outcome, taken[outcome]
+                        );
+                        if taken[outcome] > 0 {
+                            hit += 1;
+                        }
+                    }
+                    None => {
+                        let _ = writeln!(lcov, "BRDA:{},{},{},-", branch.line, block, outcome);
+                    }
+                }
+            }
+        }
+        let _ = writeln!(lcov, "BRF:{}", self.branches.len() * 2);
+        let _ = writeln!(lcov, "BRH:{}", hit);
+
+        for (line, hits) in &self.lines {
+            let _ = writeln!(lcov, "DA:{},{}", line, hits);
+        }
+        let _ = writeln!(lcov, "LF:{}", self.lines.len());
+        let _ = writeln!(
+            lcov,
+            "LH:{}",
+            self.lines.values().filter(|hits| **hits > 0).count()
+        );
+        let _ = writeln!(lcov, "end_of_record");
+        lcov
+    }
+
+    fn add_condition(&mut self, condition: &Expr) {
+        // A literal condition, like the one `for (;;)` implies, cannot go
+        // both ways.
+        if !matches!(condition, Expr::Literal { .. }) && !self.conditions.contains_key(condition) {
+            self.conditions
+                .insert(condition.clone(), self.branches.len());
+            self.branches.push(Branch {
+                line: condition.line(),
+                taken: None,
+            });
+        }
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        if !matches!(stmt, Stmt::Block(_)) {
+            self.lines.entry(stmt.line()).or_default();
+        }
+
+        match stmt {
+            Stmt::Block(stmt) => {
+                for statement in &stmt.statements {
+                    self.visit_stmt(statement);
+                }
+            }
+            Stmt::Class(stmt) => {
+                if let Some(superclass) = &stmt.superclass {
+                    self.visit_expr(superclass);
+                }
+                let members = [
+                    &stmt.methods,
+                    &stmt.class_methods,
+                    &stmt.getters,
+                    &stmt.setters,
+                ];
+                for method in members.into_iter().flatten() {
+                    self.visit_function(method);
+                }
+            }
+            Stmt::Expression(stmt) => self.visit_expr(&stmt.expression),
+            Stmt::Function(stmt) => self.visit_function(stmt),
+            Stmt::If(stmt) => {
+                self.add_condition(&stmt.condition);
+                self.visit_expr(&stmt.condition);
+                self.visit_stmt(&stmt.then_branch);
+                if let Some(else_branch) = &stmt.else_branch {
+                    self.visit_stmt(else_branch);
+                }
+            }
+            Stmt::Print(stmt) => self.visit_expr(&stmt.expression),
+            Stmt::Return(stmt) => {
+                if let Some(value) = &stmt.value {
+                    self.visit_expr(value);
+                }
+            }
+            Stmt::Test(stmt) => {
+                for statement in &stmt.body {
+                    self.visit_stmt(statement);
+                }
+            }
+            Stmt::Var(stmt) => {
+                if let Some(initializer) = &stmt.initializer {
+                    self.visit_expr(initializer);
+                }
+            }
+            Stmt::While(stmt) => {
+                self.add_condition(&stmt.condition);
+                self.visit_expr(&stmt.condition);
+                self.visit_stmt(&stmt.body);
+                if let Some(increment) = &stmt.increment {
+                    self.visit_expr(increment);
+                }
+            }
+        }
+    }
+
+    fn visit_function(&mut self, function: &StmtFunction) {
+        for statement in &function.body {
+            self.visit_stmt(statement);
+        }
+    }
+
+    /// Looks inside an expression for `and`/`or` and function bodies.
+    fn visit_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Logical {
+                left,
+                operator,
+                right,
+            } => {
+                self.operators.insert(operator.clone(), self.branches.len());
+                self.branches.push(Branch {
+                    line: operator.line,
+                    taken: None,
+                });
+                self.visit_expr(left);
+                self.visit_expr(right);
+            }
+            Expr::Lambda { declaration } => self.visit_function(declaration),
+            Expr::Assign { value, .. } => self.visit_expr(value),
+            Expr::Binary { left, right, .. } => {
+                self.visit_expr(left);
+                self.visit_expr(right);
+            }
+            Expr::Call {
+                callee, arguments, ..
+            } => {
+                self.visit_expr(callee);
+                for argument in arguments {
+                    self.visit_expr(argument);
+                }
+            }
+            Expr::Interpolation { values, .. } => {
+                for value in values {
+                    self.visit_expr(value);
+                }
+            }
+            Expr::Grouping { expression } => self.visit_expr(expression),
+            Expr::Unary { right, .. } => self.visit_expr(right),
+            Expr::Get { object, .. } => self.visit_expr(object),
+            Expr::Set { object, value, .. } => {
+                self.visit_expr(object);
+                self.visit_expr(value);
+            }
+            Expr::Index { object, index, .. } => {
+                self.visit_expr(object);
+                self.visit_expr(index);
+            }
+            Expr::Literal { .. }
+            | Expr::Variable { .. }
+            | Expr::Super { .. }
+            | Expr::This { .. } => {}
+        }
+    }
+}
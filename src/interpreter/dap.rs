@@ -0,0 +1,443 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::rc::Rc;
+
+use serde_json::{json, Value as Json};
+
+use crate::interpreter::debugger::{describe, DebugHook, Flow, Resume, StopReason, Stepper};
+use crate::interpreter::environment::Environment;
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::lsp::{read_message, write_message};
+use crate::interpreter::parser::Parser;
+use crate::interpreter::resolver::Resolver;
+use crate::interpreter::runtime_error::RuntimeError;
+use crate::interpreter::scanner::Scanner;
+use crate::interpreter::stmt::Stmt;
+
+// Lox runs on a single thread, which DAP needs an id for.
+const THREAD_ID: i64 = 1;
+
+/// A scope the client may ask the variables of, while stopped.
+#[derive(Debug, Clone, Copy)]
+enum Reference {
+    Locals(usize), // Frame id, counted from the innermost
+    Globals,
+}
+
+/// What the adapter does after handling a request.
+enum Action {
+    None,
+    Run,
+    Resume(Resume),
+    Disconnect,
+}
+
+/// The adapter's side of one debugging session. It is shared between the
+/// request loop and the hook that pauses the script, since requests are
+/// read from inside the hook while stopped.
+struct Session {
+    input: Box<dyn BufRead>,
+    seq: i64,
+    program: Option<String>,
+    source: String,
+    stepper: Stepper,
+    references: Vec<Reference>, // Handed out since the last stop
+    builtins: HashSet<String>,  // Globals hidden from the Globals scope
+    disconnected: bool,
+}
+
+impl Session {
+    fn new() -> Self {
+        let builtins = Interpreter::new()
+            .global_bindings()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+
+        Self {
+            input: Box::new(io::stdin().lock()),
+            seq: 0,
+            program: None,
+            source: String::new(),
+            stepper: Stepper::new(false),
+            references: Vec::new(),
+            builtins,
+            disconnected: false,
+        }
+    }
+
+    fn send(&mut self, mut message: Json) {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        if let Err(err) = write_message(&mut io::stdout().lock(), &message) {
+            eprintln!("lox dap: {}", err);
+        }
+    }
+
+    fn respond(&mut self, request: &Json, body: Option<Json>) {
+        let mut response = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "success": true,
+            "command": request["command"],
+        });
+        if let Some(body) = body {
+            response["body"] = body;
+        }
+        self.send(response);
+    }
+
+    fn fail(&mut self, request: &Json, message: &str) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "success": false,
+            "command": request["command"],
+            "message": message,
+        }));
+    }
+
+    fn event(&mut self, event: &str, body: Option<Json>) {
+        let mut message = json!({ "type": "event", "event": event });
+        if let Some(body) = body {
+            message["body"] = body;
+        }
+        self.send(message);
+    }
+
+    fn output(&mut self, category: &str, text: &str) {
+        self.event("output", Some(json!({ "category": category, "output": text })));
+    }
+
+    /// Handles one request. `interpreter` is set while the script is
+    /// stopped, which requests about its state need.
+    fn handle(&mut self, request: &Json, interpreter: Option<&Interpreter>) -> Action {
+        let command = request["command"].as_str().unwrap_or("");
+        let arguments = &request["arguments"];
+
+        match (command, interpreter) {
+            ("initialize", _) => {
+                self.respond(request, Some(json!({ "supportsConfigurationDoneRequest": true })));
+                self.event("initialized", None);
+            }
+            ("launch", _) => {
+                let program = match arguments["program"].as_str() {
+                    Some(program) => program.to_string(),
+                    None => {
+                        self.fail(request, "Launch needs a 'program' to run.");
+                        return Action::None;
+                    }
+                };
+                match fs::read_to_string(&program) {
+                    Ok(source) => {
+                        let breakpoints = std::mem::take(&mut self.stepper.breakpoints);
+                        self.stepper = Stepper::new(arguments["stopOnEntry"].as_bool().unwrap_or(false));
+                        self.stepper.breakpoints = breakpoints;
+                        self.source = source;
+                        self.program = Some(program);
+                        self.respond(request, None);
+                    }
+                    Err(err) => self.fail(request, &format!("{}: {}", program, err)),
+                }
+            }
+            ("setBreakpoints", _) => {
+                let lines: Vec<usize> = arguments["breakpoints"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|breakpoint| breakpoint["line"].as_u64())
+                    .map(|line| line as usize)
+                    .collect();
+                self.stepper.breakpoints = lines.iter().copied().collect();
+                let breakpoints: Vec<Json> =
+                    lines.iter().map(|line| json!({ "verified": true, "line": line })).collect();
+                self.respond(request, Some(json!({ "breakpoints": breakpoints })));
+            }
+            ("configurationDone", _) => {
+                self.respond(request, None);
+                if self.program.is_some() && interpreter.is_none() {
+                    return Action::Run;
+                }
+            }
+            ("threads", _) => {
+                self.respond(request, Some(json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] })));
+            }
+            ("disconnect", _) => {
+                self.respond(request, None);
+                return Action::Disconnect;
+            }
+            ("continue", Some(_)) => {
+                self.respond(request, Some(json!({ "allThreadsContinued": true })));
+                return Action::Resume(Resume::Continue);
+            }
+            ("next", Some(interpreter)) => {
+                self.respond(request, None);
+                return Action::Resume(Resume::Next(interpreter.frames().len()));
+            }
+            ("stepIn", Some(_)) => {
+                self.respond(request, None);
+                return Action::Resume(Resume::Step);
+            }
+            ("stepOut", Some(interpreter)) => {
+                self.respond(request, None);
+                return Action::Resume(Resume::Out(interpreter.frames().len()));
+            }
+            ("stackTrace", Some(interpreter)) => {
+                let body = self.stack_trace(interpreter);
+                self.respond(request, Some(body));
+            }
+            ("scopes", Some(interpreter)) => {
+                let frame = arguments["frameId"].as_u64().unwrap_or(0) as usize;
+                if frame >= interpreter.frames().len() {
+                    self.fail(request, &format!("No frame {}.", frame));
+                    return Action::None;
+                }
+                self.references.push(Reference::Locals(frame));
+                let locals = self.references.len();
+                self.references.push(Reference::Globals);
+                let globals = self.references.len();
+                self.respond(
+                    request,
+                    Some(json!({ "scopes": [
+                        { "name": "Locals", "variablesReference": locals, "expensive": false },
+                        { "name": "Globals", "variablesReference": globals, "expensive": false },
+                    ] })),
+                );
+            }
+            ("variables", Some(interpreter)) => {
+                let reference = arguments["variablesReference"]
+                    .as_u64()
+                    .and_then(|id| (id as usize).checked_sub(1))
+                    .and_then(|index| self.references.get(index).copied());
+                match reference {
+                    Some(reference) => {
+                        let variables = self.variables(interpreter, reference);
+                        self.respond(request, Some(json!({ "variables": variables })));
+                    }
+                    None => self.fail(request, "Unknown variables reference."),
+                }
+            }
+            ("continue" | "next" | "stepIn" | "stepOut" | "stackTrace" | "scopes" | "variables", None) => {
+                self.fail(request, "The program is not stopped.");
+            }
+            _ => self.fail(request, &format!("Unsupported request '{}'.", command)),
+        }
+        Action::None
+    }
+
+    fn stack_trace(&self, interpreter: &Interpreter) -> Json {
+        let program = self.program.as_deref().unwrap_or("");
+        let name = Path::new(program)
+            .file_name()
+            .map_or(program.to_string(), |name| name.to_string_lossy().into_owned());
+        let frames: Vec<Json> = interpreter
+            .frames()
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(id, frame)| {
+                json!({
+                    "id": id,
+                    "name": frame.function,
+                    "line": frame.line,
+                    "column": 1,
+                    "source": { "name": name, "path": program },
+                })
+            })
+            .collect();
+        json!({ "stackFrames": frames, "totalFrames": frames.len() })
+    }
+
+    fn variables(&self, interpreter: &Interpreter, reference: Reference) -> Vec<Json> {
+        let globals = interpreter.globals();
+        let bindings = match reference {
+            Reference::Globals => globals
+                .borrow()
+                .bindings()
+                .into_iter()
+                .filter(|(name, _)| !self.builtins.contains(name))
+                .collect(),
+            Reference::Locals(frame) => {
+                // Inner scopes come first and shadow outer ones.
+                let mut seen = HashSet::new();
+                let mut bindings = Vec::new();
+                let mut environment = Some(frame_environment(interpreter, frame));
+                while let Some(current) = environment {
+                    if Rc::ptr_eq(&current, &globals) {
+                        break;
+                    }
+                    for (name, value) in current.borrow().bindings() {
+                        if seen.insert(name.clone()) {
+                            bindings.push((name, value));
+                        }
+                    }
+                    environment = current.borrow().enclosing();
+                }
+                bindings
+            }
+        };
+
+        bindings
+            .into_iter()
+            .map(|(name, value)| json!({ "name": name, "value": describe(&value), "variablesReference": 0 }))
+            .collect()
+    }
+}
+
+/// Returns the environment a frame is running in, counting frames from the
+/// innermost. Callers are paused where they made their call.
+fn frame_environment(interpreter: &Interpreter, frame: usize) -> Rc<RefCell<Environment>> {
+    let frames = interpreter.frames();
+    if frame == 0 {
+        return interpreter.environment();
+    }
+    frames[frames.len() - 1 - frame]
+        .environment
+        .clone()
+        .unwrap_or_else(|| interpreter.globals())
+}
+
+/// Pauses the script where the client asked, and answers its requests
+/// until it resumes.
+struct Hook(Rc<RefCell<Session>>);
+
+impl DebugHook for Hook {
+    fn before_statement(&mut self, interpreter: &mut Interpreter, stmt: &Stmt) -> Flow {
+        let mut session = self.0.borrow_mut();
+        let reason = match session.stepper.check(stmt, interpreter.frames().len()) {
+            Some((_, reason)) => reason,
+            None => return Flow::Run,
+        };
+
+        let reason = match reason {
+            StopReason::Entry => "entry",
+            StopReason::Step => "step",
+            StopReason::Breakpoint => "breakpoint",
+        };
+        session.references.clear();
+        session.event(
+            "stopped",
+            Some(json!({ "reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true })),
+        );
+
+        while let Ok(Some(request)) = read_message(&mut session.input) {
+            match session.handle(&request, Some(&*interpreter)) {
+                Action::Resume(resume) => {
+                    session.stepper.resume(resume);
+                    return Flow::Run;
+                }
+                Action::Disconnect => break,
+                Action::None | Action::Run => {}
+            }
+        }
+
+        // The client is gone; stop the script where it is.
+        session.disconnected = true;
+        Flow::Stop
+    }
+}
+
+/// Sends what the script prints to the client as output events.
+struct Output(Rc<RefCell<Session>>);
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().output("stdout", &String::from_utf8_lossy(buf));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Runs the launched program under the hook. Returns its exit code.
+fn run(session: &Rc<RefCell<Session>>) -> i32 {
+    let source = session.borrow().source.clone();
+    let mut scanner = Scanner::new(source);
+    let tokens = scanner.scan_tokens();
+    if !scanner.errors().is_empty() {
+        for error in scanner.errors() {
+            let message = format!("[line {}] Error: {}\n", error.line, error.message);
+            session.borrow_mut().output("stderr", &message);
+        }
+        return 65;
+    }
+    let statements = match Parser::new(tokens).parse() {
+        Ok(statements) => statements,
+        Err(error) => {
+            let message = format!("[line {}] Error: {}\n", error.token.line, error.message);
+            session.borrow_mut().output("stderr", &message);
+            return 65;
+        }
+    };
+
+    let mut interpreter = Interpreter::new();
+    let mut resolver = Resolver::new(&mut interpreter);
+    resolver.resolve(&statements);
+    let errors: Vec<String> = resolver
+        .errors()
+        .iter()
+        .map(|(token, message)| format!("[line {}] Error at '{}': {}\n", token.line, token.lexeme, message))
+        .collect();
+    if !errors.is_empty() {
+        for message in errors {
+            session.borrow_mut().output("stderr", &message);
+        }
+        return 65;
+    }
+
+    interpreter.set_hook(Box::new(Hook(session.clone())));
+    interpreter.set_output(Box::new(Output(session.clone())));
+    match panic::catch_unwind(AssertUnwindSafe(|| interpreter.interpret(&statements))) {
+        Ok(_) => 0,
+        Err(payload) => {
+            let message = match payload.downcast::<RuntimeError>() {
+                Ok(error) => error.to_string(),
+                Err(payload) => panic::resume_unwind(payload),
+            };
+            session.borrow_mut().output("stderr", &format!("{}\n", message));
+            70
+        }
+    }
+}
+
+/// Runs `lox dap`, a debug adapter speaking the Debug Adapter Protocol
+/// over stdin and stdout. It debugs one launched program per session.
+pub fn command(args: &[String]) -> i32 {
+    if !args.is_empty() {
+        eprintln!("Usage: lox dap");
+        return 64;
+    }
+
+    let session = Rc::new(RefCell::new(Session::new()));
+    loop {
+        let request = match read_message(&mut session.borrow_mut().input) {
+            Ok(Some(request)) => request,
+            Ok(None) => return 0,
+            Err(err) => {
+                eprintln!("lox dap: {}", err);
+                return 74;
+            }
+        };
+
+        let action = session.borrow_mut().handle(&request, None);
+        match action {
+            Action::Run => {
+                let code = run(&session);
+                if session.borrow().disconnected {
+                    return 0;
+                }
+                let mut session = session.borrow_mut();
+                session.event("terminated", None);
+                session.event("exited", Some(json!({ "exitCode": code })));
+            }
+            Action::Disconnect => return 0,
+            Action::None | Action::Resume(_) => {}
+        }
+    }
+}
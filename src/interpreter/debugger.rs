@@ -0,0 +1,333 @@
+use std::collections::BTreeSet;
+use std::io::{self, BufRead, Write};
+use std::rc::Rc;
+
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::stmt::Stmt;
+use crate::interpreter::value::Value;
+
+/// Observes a running script. The interpreter calls the hook before each
+/// statement, with itself so the hook can inspect frames and variables.
+pub trait DebugHook {
+    fn before_statement(&mut self, interpreter: &mut Interpreter, stmt: &Stmt) -> Flow;
+}
+
+/// What a hook wants the interpreter to do next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    Run,
+    Stop, // Interrupt the script, as if it had been cancelled
+}
+
+/// A debugger command, entered at the `(debug)` prompt.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Break(usize),
+    Delete(usize),
+    Breakpoints,
+    Continue,
+    Step,
+    Next,
+    Out,
+    Backtrace,
+    Locals,
+    Print(String),
+    List,
+    Quit,
+    Help,
+}
+
+impl Command {
+    /// Parses a command line, or returns an error message for the user.
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let (name, argument) = match line.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (line, ""),
+        };
+        let line_number = || {
+            argument
+                .parse::<usize>()
+                .map_err(|_| format!("Usage: {} <line>", name))
+        };
+
+        match name {
+            "break" | "b" => Ok(Command::Break(line_number()?)),
+            "delete" | "d" => Ok(Command::Delete(line_number()?)),
+            "breakpoints" => Ok(Command::Breakpoints),
+            "continue" | "c" => Ok(Command::Continue),
+            "step" | "s" => Ok(Command::Step),
+            "next" | "n" => Ok(Command::Next),
+            "out" | "o" => Ok(Command::Out),
+            "backtrace" | "bt" => Ok(Command::Backtrace),
+            "locals" => Ok(Command::Locals),
+            "print" | "p" if !argument.is_empty() => Ok(Command::Print(argument.to_string())),
+            "print" | "p" => Err(format!("Usage: {} <name>", name)),
+            "list" | "l" => Ok(Command::List),
+            "quit" | "q" => Ok(Command::Quit),
+            "help" | "h" => Ok(Command::Help),
+            _ => Err(format!("Unknown command '{}'. Type help for a list.", name)),
+        }
+    }
+}
+
+pub const HELP: &str = "\
+break <line>   Stop before statements on a line (b)
+delete <line>  Remove a breakpoint (d)
+breakpoints    List breakpoints
+continue       Run to the next breakpoint (c)
+step           Run one statement, entering calls (s)
+next           Run one statement, stepping over calls (n)
+out            Run until the current function returns (o)
+backtrace      Show the calls in progress (bt)
+locals         Show variables in the current scopes
+print <name>   Show a variable (p)
+list           Show source around the current line (l)
+quit           Stop the script (q)";
+
+/// How far to run before stopping again.
+#[derive(Debug, Clone, Copy)]
+pub enum Resume {
+    Continue,
+    Step,
+    Next(usize), // Stop at a depth no deeper than this
+    Out(usize),  // Stop once shallower than this
+}
+
+/// Why execution stopped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopReason {
+    Entry,
+    Step,
+    Breakpoint,
+}
+
+/// Decides before which statements a debugger stops, from its breakpoints
+/// and how it was last resumed. Depth is the number of frames in progress.
+#[derive(Debug)]
+pub struct Stepper {
+    pub breakpoints: BTreeSet<usize>,
+    resume: Resume,
+    last: Option<(usize, usize)>, // Line and depth of the last statement seen
+}
+
+impl Stepper {
+    /// Stops before the first statement if `stop_on_entry` is set, and
+    /// otherwise at the first breakpoint.
+    pub fn new(stop_on_entry: bool) -> Self {
+        Self {
+            breakpoints: BTreeSet::new(),
+            resume: if stop_on_entry { Resume::Step } else { Resume::Continue },
+            last: None,
+        }
+    }
+
+    pub fn resume(&mut self, resume: Resume) {
+        self.resume = resume;
+    }
+
+    /// Returns the line to stop at before `stmt` and why, or `None` to keep
+    /// running.
+    pub fn check(&mut self, stmt: &Stmt, depth: usize) -> Option<(usize, StopReason)> {
+        // A block stops at its first statement instead.
+        if matches!(stmt, Stmt::Block(_)) {
+            return None;
+        }
+        let line = stmt.line();
+
+        // A breakpoint stops once per visit to its line, not before every
+        // statement on it.
+        let entry = self.last.is_none();
+        let arrived = self.last != Some((line, depth));
+        self.last = Some((line, depth));
+
+        let stepped = match self.resume {
+            Resume::Continue => false,
+            Resume::Step => true,
+            Resume::Next(limit) => depth <= limit,
+            Resume::Out(limit) => depth < limit,
+        };
+        if stepped {
+            Some((line, if entry { StopReason::Entry } else { StopReason::Step }))
+        } else if arrived && self.breakpoints.contains(&line) {
+            Some((line, StopReason::Breakpoint))
+        } else {
+            None
+        }
+    }
+}
+
+/// An interactive step debugger reading commands from stdin. It stops
+/// before the first statement.
+pub struct Debugger {
+    source: Vec<String>,
+    stepper: Stepper,
+}
+
+impl Debugger {
+    pub fn new(source: &str) -> Self {
+        Self {
+            source: source.lines().map(str::to_string).collect(),
+            stepper: Stepper::new(true),
+        }
+    }
+
+    /// Reads and runs commands until one resumes or stops the script.
+    fn prompt(&mut self, interpreter: &Interpreter, line: usize) -> Flow {
+        let stdin = io::stdin();
+        loop {
+            print!("(debug) ");
+            let _ = io::stdout().flush();
+
+            let mut input = String::new();
+            if stdin.lock().read_line(&mut input).unwrap_or(0) == 0 {
+                // Without input, run to completion.
+                println!();
+                self.stepper.breakpoints.clear();
+                self.stepper.resume(Resume::Continue);
+                return Flow::Run;
+            }
+            if input.trim().is_empty() {
+                continue;
+            }
+
+            let depth = interpreter.frames().len();
+            match Command::parse(&input) {
+                Ok(Command::Continue) => self.stepper.resume(Resume::Continue),
+                Ok(Command::Step) => self.stepper.resume(Resume::Step),
+                Ok(Command::Next) => self.stepper.resume(Resume::Next(depth)),
+                Ok(Command::Out) => self.stepper.resume(Resume::Out(depth)),
+                Ok(Command::Quit) => return Flow::Stop,
+                Ok(command) => {
+                    self.inspect(interpreter, command, line);
+                    continue;
+                }
+                Err(message) => {
+                    println!("{}", message);
+                    continue;
+                }
+            }
+            return Flow::Run;
+        }
+    }
+
+    /// Runs a command that looks at the script without resuming it.
+    fn inspect(&mut self, interpreter: &Interpreter, command: Command, line: usize) {
+        match command {
+            Command::Break(line) => {
+                self.stepper.breakpoints.insert(line);
+                println!("Breakpoint at line {}.", line);
+            }
+            Command::Delete(line) => {
+                if self.stepper.breakpoints.remove(&line) {
+                    println!("Removed breakpoint at line {}.", line);
+                } else {
+                    println!("No breakpoint at line {}.", line);
+                }
+            }
+            Command::Breakpoints => {
+                if self.stepper.breakpoints.is_empty() {
+                    println!("No breakpoints.");
+                }
+                for line in &self.stepper.breakpoints {
+                    println!("line {}: {}", line, self.line_text(*line));
+                }
+            }
+            Command::Backtrace => {
+                for (i, frame) in interpreter.frames().iter().rev().enumerate() {
+                    println!("#{} {} at line {}", i, frame.function, frame.line);
+                }
+            }
+            Command::Locals => {
+                for (depth, bindings) in scopes(interpreter).iter().enumerate() {
+                    println!("scope {}:", depth);
+                    for (name, value) in bindings {
+                        println!("  {} = {}", name, describe(value));
+                    }
+                }
+            }
+            Command::Print(name) => {
+                let value = scopes(interpreter)
+                    .into_iter()
+                    .flatten()
+                    .find(|(bound, _)| *bound == name)
+                    .map(|(_, value)| value)
+                    .or_else(|| {
+                        interpreter
+                            .global_bindings()
+                            .into_iter()
+                            .find(|(bound, _)| *bound == name)
+                            .map(|(_, value)| value)
+                    });
+                match value {
+                    Some(value) => println!("{} = {}", name, describe(&value)),
+                    None => println!("Undefined variable '{}'.", name),
+                }
+            }
+            Command::List => {
+                let first = line.saturating_sub(3).max(1);
+                for number in first..=(line + 3).min(self.source.len()) {
+                    let marker = if number == line { "->" } else { "  " };
+                    let breakpoint = if self.stepper.breakpoints.contains(&number) { "*" } else { " " };
+                    println!("{}{}{:>4}  {}", breakpoint, marker, number, self.line_text(number));
+                }
+            }
+            Command::Help => println!("{}", HELP),
+            Command::Continue | Command::Step | Command::Next | Command::Out | Command::Quit => {}
+        }
+    }
+
+    fn line_text(&self, line: usize) -> &str {
+        line.checked_sub(1)
+            .and_then(|index| self.source.get(index))
+            .map_or("", |text| text.trim())
+    }
+}
+
+impl DebugHook for Debugger {
+    fn before_statement(&mut self, interpreter: &mut Interpreter, stmt: &Stmt) -> Flow {
+        let line = match self.stepper.check(stmt, interpreter.frames().len()) {
+            Some((line, _)) => line,
+            None => return Flow::Run,
+        };
+
+        let function = interpreter.frames().last().map_or("<script>", |frame| frame.function.as_str());
+        println!("Stopped at line {} in {}: {}", line, function, self.line_text(line));
+        self.prompt(interpreter, line)
+    }
+}
+
+/// Returns the bindings of each scope from the innermost out, stopping
+/// before the globals.
+fn scopes(interpreter: &Interpreter) -> Vec<Vec<(String, Value)>> {
+    let globals = interpreter.globals();
+    let mut scopes = Vec::new();
+    let mut environment = Some(interpreter.environment());
+    while let Some(current) = environment {
+        if Rc::ptr_eq(&current, &globals) {
+            break;
+        }
+        scopes.push(current.borrow().bindings());
+        environment = current.borrow().enclosing();
+    }
+    scopes
+}
+
+/// Formats a value for display, quoting strings even inside lists and
+/// maps. Unlike `Interpreter::stringify`, this never runs Lox code, so
+/// inspecting cannot change the script's state.
+pub fn describe(value: &Value) -> String {
+    match value {
+        Value::String(s) => format!("{:?}", s),
+        Value::List(list) => {
+            let items: Vec<String> = list.borrow().iter().map(describe).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Value::Map(map) => {
+            let entries: Vec<String> =
+                map.borrow().iter().map(|(key, value)| format!("{}: {}", key, describe(value))).collect();
+            format!("{{{}}}", entries.join(", "))
+        }
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,232 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use crate::interpreter::expr::Expr;
+use crate::interpreter::parser::Parser;
+use crate::interpreter::resolver::signature;
+use crate::interpreter::scanner::Scanner;
+use crate::interpreter::stmt::{Stmt, StmtClass};
+
+/// How pages are written.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Markdown,
+    Html,
+}
+
+/// A documented declaration: a class with its members, or a function.
+struct Entry {
+    signature: String,
+    doc: Option<String>,
+    members: Vec<Entry>,
+}
+
+/// The reference page for one file.
+struct Page {
+    title: String,
+    classes: Vec<Entry>,
+    functions: Vec<Entry>,
+}
+
+/// Collects the top-level classes and functions of a source file, or
+/// returns the first syntax error.
+fn page(title: &str, source: &str) -> Result<Page, String> {
+    let mut scanner = Scanner::new(source.to_string());
+    let tokens = scanner.scan_tokens();
+    if let Some(error) = scanner.errors().first() {
+        return Err(format!("[line {}] {}", error.line, error.message));
+    }
+    let statements = Parser::with_comments(tokens, scanner.comments())
+        .parse()
+        .map_err(|error| format!("[line {}] {}", error.token.line, error.message))?;
+
+    let mut page = Page {
+        title: title.to_string(),
+        classes: Vec::new(),
+        functions: Vec::new(),
+    };
+    for statement in &statements {
+        match statement {
+            Stmt::Class(class) => page.classes.push(class_entry(class)),
+            Stmt::Function(function) => page.functions.push(Entry {
+                signature: signature("fun ", function),
+                doc: function.doc.clone(),
+                members: Vec::new(),
+            }),
+            _ => {}
+        }
+    }
+    Ok(page)
+}
+
+fn class_entry(class: &StmtClass) -> Entry {
+    let header = match &class.superclass {
+        Some(Expr::Variable { name, .. }) => format!("class {} < {}", class.name.lexeme, name.lexeme),
+        _ => format!("class {}", class.name.lexeme),
+    };
+
+    // Members are kept by kind, so put them back in source order.
+    let mut members: Vec<(usize, Entry)> = class.methods.iter().map(|method| ("", method))
+        .chain(class.class_methods.iter().map(|method| ("class ", method)))
+        .chain(class.getters.iter().map(|getter| ("get ", getter)))
+        .chain(class.setters.iter().map(|setter| ("set ", setter)))
+        .map(|(prefix, method)| {
+            // A getter takes no parameter list.
+            let signature = match prefix {
+                "get " => format!("get {}", method.name.lexeme),
+                _ => signature(prefix, method),
+            };
+            let entry = Entry {
+                signature,
+                doc: method.doc.clone(),
+                members: Vec::new(),
+            };
+            (method.name.line, entry)
+        })
+        .collect();
+    members.sort_by_key(|(line, _)| *line);
+
+    Entry {
+        signature: header,
+        doc: class.doc.clone(),
+        members: members.into_iter().map(|(_, entry)| entry).collect(),
+    }
+}
+
+fn markdown(page: &Page) -> String {
+    fn entry(out: &mut String, level: usize, entry: &Entry) {
+        let _ = writeln!(out, "{} `{}`\n", "#".repeat(level), entry.signature);
+        if let Some(doc) = &entry.doc {
+            let _ = writeln!(out, "{}\n", doc);
+        }
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(out, "# {}\n", page.title);
+    if !page.classes.is_empty() {
+        let _ = writeln!(out, "## Classes\n");
+        for class in &page.classes {
+            entry(&mut out, 3, class);
+            for member in &class.members {
+                entry(&mut out, 4, member);
+            }
+        }
+    }
+    if !page.functions.is_empty() {
+        let _ = writeln!(out, "## Functions\n");
+        for function in &page.functions {
+            entry(&mut out, 3, function);
+        }
+    }
+    out
+}
+
+fn html(page: &Page) -> String {
+    fn entry(out: &mut String, level: usize, entry: &Entry) {
+        let _ = writeln!(out, "<h{0}><code>{1}</code></h{0}>", level, escape(&entry.signature));
+        // Blank lines in a doc comment separate paragraphs.
+        for paragraph in entry.doc.iter().flat_map(|doc| doc.split("\n\n")) {
+            if !paragraph.trim().is_empty() {
+                let _ = writeln!(out, "<p>{}</p>", escape(paragraph.trim()));
+            }
+        }
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(out, "<!DOCTYPE html>");
+    let _ = writeln!(out, "<html>");
+    let _ = writeln!(out, "<head>");
+    let _ = writeln!(out, "<meta charset=\"utf-8\">");
+    let _ = writeln!(out, "<title>{}</title>", escape(&page.title));
+    let _ = writeln!(out, "</head>");
+    let _ = writeln!(out, "<body>");
+    let _ = writeln!(out, "<h1>{}</h1>", escape(&page.title));
+    if !page.classes.is_empty() {
+        let _ = writeln!(out, "<h2>Classes</h2>");
+        for class in &page.classes {
+            entry(&mut out, 3, class);
+            for member in &class.members {
+                entry(&mut out, 4, member);
+            }
+        }
+    }
+    if !page.functions.is_empty() {
+        let _ = writeln!(out, "<h2>Functions</h2>");
+        for function in &page.functions {
+            entry(&mut out, 3, function);
+        }
+    }
+    let _ = writeln!(out, "</body>");
+    let _ = writeln!(out, "</html>");
+    out
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Runs `lox doc [--html] [--out DIR] <files...>`, printing a reference
+/// page per file, or writing each to DIR named after the file.
+pub fn command(args: &[String]) -> i32 {
+    let mut args = args.to_vec();
+    let format = match args.iter().position(|arg| arg == "--html") {
+        Some(position) => {
+            args.remove(position);
+            Format::Html
+        }
+        None => Format::Markdown,
+    };
+    let out = match args.iter().position(|arg| arg == "--out") {
+        Some(position) if position + 1 < args.len() => {
+            let dir = args[position + 1].clone();
+            args.drain(position..position + 2);
+            Some(dir)
+        }
+        _ => None,
+    };
+    if args.is_empty() || args.iter().any(|arg| arg.starts_with("--")) {
+        eprintln!("Usage: lox doc [--html] [--out DIR] <files...>");
+        return 64;
+    }
+
+    let mut status = 0;
+    for path in &args {
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(err) => {
+                eprintln!("{}: {}", path, err);
+                status = 74;
+                continue;
+            }
+        };
+        let page = match page(path, &source) {
+            Ok(page) => page,
+            Err(message) => {
+                eprintln!("{}: {}", path, message);
+                status = status.max(65);
+                continue;
+            }
+        };
+        let (text, extension) = match format {
+            Format::Markdown => (markdown(&page), "md"),
+            Format::Html => (html(&page), "html"),
+        };
+
+        match &out {
+            Some(dir) => {
+                let stem = Path::new(path).file_stem().unwrap_or_default();
+                let target = Path::new(dir).join(stem).with_extension(extension);
+                if let Err(err) = fs::create_dir_all(dir).and_then(|_| fs::write(&target, text)) {
+                    eprintln!("{}: {}", target.display(), err);
+                    status = 74;
+                }
+            }
+            None => print!("{}", text),
+        }
+    }
+    status
+}
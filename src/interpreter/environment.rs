@@ -4,6 +4,7 @@ use std::cell::RefCell;
 
 use crate::interpreter::token::Token;
 use crate::interpreter::runtime_error::RuntimeError;
+use crate::interpreter::value::Value;
 
 #[derive(Debug, Clone)]
 pub struct Environment {
@@ -11,16 +12,6 @@ pub struct Environment {
     enclosing: Option<Rc<RefCell<Environment>>>, // Reference to the enclosing environment
 }
 
-#[derive(Debug, Clone)]
-pub enum Value {
-    Nil,
-    Number(f64),
-    String(String),
-    Bool(bool),
-    Callable, // You can extend this for Lox functions/objects.
-    // Add other types as needed
-}
-
 impl Environment {
     /// Creates a new global environment.
     pub fn new() -> Self {
@@ -48,7 +39,7 @@ impl Environment {
             return enclosing.borrow().get(name);
         }
 
-        panic!("{}", RuntimeError::new(name.clone(), format!("Undefined variable '{}'.", name.lexeme)));
+        RuntimeError::new(name.clone(), format!("Undefined variable '{}'.", name.lexeme)).raise();
     }
 
     /// Assigns a value to an existing variable.
@@ -63,7 +54,7 @@ impl Environment {
             return;
         }
 
-        panic!("{}", RuntimeError::new(name.clone(), format!("Undefined variable '{}'.", name.lexeme)));
+        RuntimeError::new(name.clone(), format!("Undefined variable '{}'.", name.lexeme)).raise();
     }
 
     /// Defines a new variable in the current environment.
@@ -71,31 +62,53 @@ impl Environment {
         self.values.insert(name.to_string(), value);
     }
 
-    /// Gets an ancestor environment at a specific distance.
-    pub fn ancestor(&self, distance: usize) -> Rc<RefCell<Environment>> {
-        let mut environment = Rc::new(RefCell::new(self.clone()));
+    /// Returns the variables defined directly in this environment, by name.
+    pub fn bindings(&self) -> Vec<(String, Value)> {
+        let mut bindings: Vec<(String, Value)> = self
+            .values
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        bindings.sort_by(|a, b| a.0.cmp(&b.0));
+        bindings
+    }
+
+    /// Returns the enclosing environment, if this is not the global one.
+    pub fn enclosing(&self) -> Option<Rc<RefCell<Environment>>> {
+        self.enclosing.clone()
+    }
+
+    /// Gets the environment `distance` hops out from `environment`.
+    pub fn ancestor(environment: &Rc<RefCell<Environment>>, distance: usize) -> Rc<RefCell<Environment>> {
+        let mut environment = environment.clone();
 
         for _ in 0..distance {
             let enclosing = environment
                 .borrow()
                 .enclosing
-                .as_ref()
-                .expect("Enclosing environment not found")
-                .clone();
+                .clone()
+                .expect("Enclosing environment not found");
             environment = enclosing;
         }
 
         environment
     }
 
-    /// Gets a variable value at a specific distance.
-    pub fn get_at(&self, distance: usize, name: &str) -> Value {
-        self.ancestor(distance).borrow().values.get(name).cloned().unwrap_or(Value::Nil)
+    /// Gets a variable value at a specific distance. The resolver only
+    /// records distances to variables it saw declared there, so a missing
+    /// one is a bug in the resolver rather than in the script.
+    pub fn get_at(environment: &Rc<RefCell<Environment>>, distance: usize, name: &str) -> Value {
+        Self::ancestor(environment, distance)
+            .borrow()
+            .values
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| panic!("Resolved variable '{}' not found {} scopes out", name, distance))
     }
 
     /// Assigns a value to a variable at a specific distance.
-    pub fn assign_at(&self, distance: usize, name: &Token, value: Value) {
-        self.ancestor(distance)
+    pub fn assign_at(environment: &Rc<RefCell<Environment>>, distance: usize, name: &Token, value: Value) {
+        Self::ancestor(environment, distance)
             .borrow_mut()
             .values
             .insert(name.lexeme.clone(), value);
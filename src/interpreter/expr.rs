@@ -1,21 +1,67 @@
 use std::fmt;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::interpreter::stmt::StmtFunction;
 use crate::interpreter::token::Token;
 use crate::interpreter::value::Object;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Expr {
-    Assign { name: Token, value: Box<Expr> },
+    Assign {
+        name: Token,
+        value: Box<Expr>,
+        #[cfg_attr(feature = "ast-serde", serde(skip))]
+        id: ExprId,
+    },
     Binary { left: Box<Expr>, operator: Token, right: Box<Expr> },
-    Call { callee: Box<Expr>, arguments: Vec<Expr> },
+    Call { callee: Box<Expr>, paren: Token, arguments: Vec<Expr> },
     Grouping { expression: Box<Expr> },
-    Literal { value: Option<Object> },
+    Literal { value: Option<Object>, line: usize },
     Logical { left: Box<Expr>, operator: Token, right: Box<Expr> },
     Unary { operator: Token, right: Box<Expr> },
-    Variable { name: Token },    
+    Variable {
+        name: Token,
+        #[cfg_attr(feature = "ast-serde", serde(skip))]
+        id: ExprId,
+    },
     Get { object: Box<Expr>, name: Token },
     Set { object: Box<Expr>, name: Token, value: Box<Expr> },
-    Super { method: Token },
-    This,
+    Index { object: Box<Expr>, bracket: Token, index: Box<Expr> },
+    Super {
+        method: Token,
+        #[cfg_attr(feature = "ast-serde", serde(skip))]
+        id: ExprId,
+    },
+    This {
+        keyword: Token,
+        #[cfg_attr(feature = "ast-serde", serde(skip))]
+        id: ExprId,
+    },
+    Lambda { declaration: Rc<StmtFunction> },
+    // `"a ${x} b"`: `segments` has one more entry than `values`, which go between them
+    Interpolation { start: Token, segments: Vec<String>, values: Vec<Expr> },
+}
+
+/// Tells apart the expressions the resolver records depths for, which
+/// would otherwise compare equal when their tokens do: the same line typed
+/// twice in the REPL, or a loaded AST without real positions. Each parsed
+/// or deserialized expression gets a fresh one; clones keep it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ExprId(usize);
+
+impl ExprId {
+    /// Returns an id no other expression has.
+    pub fn fresh() -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl Default for ExprId {
+    fn default() -> Self {
+        Self::fresh()
+    }
 }
 
 pub trait ExprVisitor<R> {
@@ -29,11 +75,31 @@ pub trait ExprVisitor<R> {
     fn visit_variable_expr(&mut self, expr: &Expr) -> R;
     fn visit_get_expr(&mut self, expr: &Expr) -> R;
     fn visit_set_expr(&mut self, expr: &Expr) -> R;
+    fn visit_index_expr(&mut self, expr: &Expr) -> R;
     fn visit_super_expr(&mut self, expr: &Expr) -> R;
     fn visit_this_expr(&mut self, expr: &Expr) -> R;
+    fn visit_lambda_expr(&mut self, expr: &Expr) -> R;
+    fn visit_interpolation_expr(&mut self, expr: &Expr) -> R;
 }
 
 impl Expr {
+    /// Returns the line of the expression's leftmost token.
+    pub fn line(&self) -> usize {
+        match self {
+            Expr::Assign { name, .. } | Expr::Variable { name, .. } => name.line,
+            Expr::Binary { left, .. } | Expr::Logical { left, .. } => left.line(),
+            Expr::Call { callee, .. } => callee.line(),
+            Expr::Grouping { expression } => expression.line(),
+            Expr::Unary { operator, .. } => operator.line,
+            Expr::Get { object, .. } | Expr::Set { object, .. } | Expr::Index { object, .. } => object.line(),
+            Expr::Super { method, .. } => method.line,
+            Expr::This { keyword, .. } => keyword.line,
+            Expr::Lambda { declaration } => declaration.name.line,
+            Expr::Interpolation { start, .. } => start.line,
+            Expr::Literal { line, .. } => *line,
+        }
+    }
+
     pub fn accept<R>(&self, visitor: &mut dyn ExprVisitor<R>) -> R {
         match self {
             Expr::Assign { .. } => visitor.visit_assign_expr(self),
@@ -46,8 +112,11 @@ impl Expr {
             Expr::Variable { .. } => visitor.visit_variable_expr(self),
             Expr::Get { .. } => visitor.visit_get_expr(self),
             Expr::Set { .. } => visitor.visit_set_expr(self),
+            Expr::Index { .. } => visitor.visit_index_expr(self),
             Expr::Super { .. } => visitor.visit_super_expr(self),
-            Expr::This => visitor.visit_this_expr(self),
+            Expr::This { .. } => visitor.visit_this_expr(self),
+            Expr::Lambda { .. } => visitor.visit_lambda_expr(self),
+            Expr::Interpolation { .. } => visitor.visit_interpolation_expr(self),
         }
     }
 }
@@ -55,18 +124,23 @@ impl Expr {
 impl fmt::Display for Expr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Expr::Assign { name, value } => write!(f, "Assign({}, {})", name.lexeme, value),
+            Expr::Assign { name, value, .. } => write!(f, "Assign({}, {})", name.lexeme, value),
             Expr::Binary { left, operator, right } => write!(f, "Binary({}, {}, {})", left, operator, right),
-            Expr::Call { callee, arguments } => write!(f, "Call({}, {:?})", callee, arguments),
+            Expr::Call { callee, arguments, .. } => write!(f, "Call({}, {:?})", callee, arguments),
             Expr::Grouping { expression } => write!(f, "Grouping({})", expression),
-            Expr::Literal { value } => write!(f, "Literal({:?})", value),
+            Expr::Literal { value, .. } => write!(f, "Literal({:?})", value),
             Expr::Logical { left, operator, right } => write!(f, "Logical({}, {}, {})", left, operator, right),
             Expr::Unary { operator, right } => write!(f, "Unary({}, {})", operator, right),
-            Expr::Variable { name } => write!(f, "Variable({})", name.lexeme),
+            Expr::Variable { name, .. } => write!(f, "Variable({})", name.lexeme),
             Expr::Get { object, name } => write!(f, "Get({}, {})", object, name.lexeme),
             Expr::Set { object, name, value } => write!(f, "Set({}, {}, {})", object, name.lexeme, value),
-            Expr::Super { method } => write!(f, "Super({})", method.lexeme),
-            Expr::This => write!(f, "This"),
+            Expr::Index { object, index, .. } => write!(f, "Index({}, {})", object, index),
+            Expr::Super { method, .. } => write!(f, "Super({})", method.lexeme),
+            Expr::This { .. } => write!(f, "This"),
+            Expr::Lambda { declaration } => write!(f, "Lambda({:?})", declaration.params),
+            Expr::Interpolation { segments, values, .. } => {
+                write!(f, "Interpolation({:?}, {:?})", segments, values)
+            }
         }
     }
 }
\ No newline at end of file
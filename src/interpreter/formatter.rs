@@ -0,0 +1,611 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, Read};
+use std::rc::Rc;
+
+use crate::interpreter::expr::{Expr, ExprVisitor};
+use crate::interpreter::parser::Parser;
+use crate::interpreter::scanner::{Comment, Scanner};
+use crate::interpreter::stmt::{
+    Stmt, StmtBlock, StmtClass, StmtExpression, StmtFunction, StmtIf, StmtPrint, StmtReturn,
+    StmtTest, StmtVar, StmtVisitor, StmtWhile,
+};
+use crate::interpreter::token::Token;
+use crate::interpreter::token_type::TokenType;
+use crate::interpreter::value::Object;
+
+const INDENT: &str = "    ";
+
+/// Regenerates canonical Lox source from the syntax tree: four-space
+/// indentation, opening braces on the header line and single spaces around
+/// binary operators. Comments are re-attached by line number.
+pub struct Formatter {
+    comments: VecDeque<Comment>,
+    braces: VecDeque<usize>, // Closing line of each `{ }`, in order of the opening brace
+    output: String,
+    indent: usize,
+}
+
+/// Formats a whole source file, or returns the first syntax error.
+pub fn format_source(source: &str) -> Result<String, String> {
+    let mut scanner = Scanner::new(source.to_string());
+    let tokens = scanner.scan_tokens();
+    if let Some(error) = scanner.errors().first() {
+        return Err(format!("[line {}] {}", error.line, error.message));
+    }
+
+    let comments = scanner.comments().to_vec();
+    let statements = Parser::new(tokens.clone())
+        .parse()
+        .map_err(|error| format!("[line {}] {}", error.token.line, error.message))?;
+
+    let mut formatter = Formatter::new(comments, &tokens);
+    Ok(formatter.format(&statements))
+}
+
+/// Runs `lox fmt [--check] [files...]`, returning the exit code. Without
+/// files, formats stdin to stdout. `--check` rewrites nothing and exits
+/// with 1 if any input is not formatted.
+pub fn command(args: &[String]) -> i32 {
+    let check = args.iter().any(|arg| arg == "--check");
+    let paths: Vec<&String> = args.iter().filter(|arg| *arg != "--check").collect();
+
+    if paths.is_empty() {
+        let mut source = String::new();
+        if let Err(err) = io::stdin().read_to_string(&mut source) {
+            eprintln!("Failed to read stdin: {}", err);
+            return 74;
+        }
+        return match format_source(&source) {
+            Ok(formatted) if check => (formatted != source) as i32,
+            Ok(formatted) => {
+                print!("{}", formatted);
+                0
+            }
+            Err(message) => {
+                eprintln!("<stdin>: {}", message);
+                65
+            }
+        };
+    }
+
+    let mut status = 0;
+    for path in paths {
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(err) => {
+                eprintln!("{}: {}", path, err);
+                status = 74;
+                continue;
+            }
+        };
+
+        match format_source(&source) {
+            Ok(formatted) if formatted == source => {}
+            Ok(_) if check => {
+                println!("{} is not formatted", path);
+                status = status.max(1);
+            }
+            Ok(formatted) => {
+                if let Err(err) = fs::write(path, formatted) {
+                    eprintln!("{}: {}", path, err);
+                    status = 74;
+                }
+            }
+            Err(message) => {
+                eprintln!("{}: {}", path, message);
+                status = status.max(65);
+            }
+        }
+    }
+    status
+}
+
+impl Formatter {
+    pub fn new(comments: Vec<Comment>, tokens: &[Token]) -> Self {
+        let mut braces = Vec::new();
+        let mut open = Vec::new();
+        for token in tokens {
+            match token.token_type {
+                TokenType::LeftBrace => {
+                    open.push(braces.len());
+                    braces.push(token.line);
+                }
+                TokenType::RightBrace => {
+                    if let Some(index) = open.pop() {
+                        braces[index] = token.line;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            comments: comments.into(),
+            braces: braces.into(),
+            output: String::new(),
+            indent: 0,
+        }
+    }
+
+    /// Formats top-level statements, followed by any trailing comments.
+    pub fn format(&mut self, statements: &[Stmt]) -> String {
+        self.statements(statements, true);
+        self.comments_before(usize::MAX);
+        std::mem::take(&mut self.output)
+    }
+
+    fn statements(&mut self, statements: &[Stmt], top_level: bool) {
+        for (i, stmt) in statements.iter().enumerate() {
+            // Declarations at the top level are set apart by a blank line.
+            if top_level && i > 0 && (is_declaration(&statements[i - 1]) || is_declaration(stmt)) {
+                self.output.push('\n');
+            }
+            self.statement(stmt);
+        }
+    }
+
+    fn statement(&mut self, stmt: &Stmt) {
+        self.comments_before(stmt.line());
+
+        // `for (init; ...)` is parsed as a block holding the initializer
+        // and a while loop whose keyword is `for`.
+        if let Stmt::Block(block) = stmt {
+            if let [initializer @ (Stmt::Var(_) | Stmt::Expression(_)), Stmt::While(loop_stmt)] =
+                block.statements.as_slice()
+            {
+                if loop_stmt.keyword.token_type == TokenType::For {
+                    self.for_loop(Some(initializer), loop_stmt);
+                    return;
+                }
+            }
+        }
+        stmt.accept(self);
+    }
+
+    /// Writes standalone comments that come before `line`.
+    fn comments_before(&mut self, line: usize) {
+        while self.comments.front().is_some_and(|comment| comment.line < line) {
+            let comment = self.comments.pop_front().unwrap();
+            self.write_indent();
+            self.output.push_str(&comment.text);
+            self.output.push('\n');
+        }
+    }
+
+    /// Writes a one-line statement, keeping a trailing comment on its line.
+    fn simple(&mut self, text: String, line: usize) {
+        self.write_indent();
+        self.output.push_str(&text);
+        if self
+            .comments
+            .front()
+            .is_some_and(|comment| comment.trailing && comment.line == line)
+        {
+            let comment = self.comments.pop_front().unwrap();
+            self.output.push(' ');
+            self.output.push_str(&comment.text);
+        }
+        self.output.push('\n');
+    }
+
+    fn write_indent(&mut self) {
+        for _ in 0..self.indent {
+            self.output.push_str(INDENT);
+        }
+    }
+
+    /// Writes `{`, the statements indented, and `}` without a newline.
+    /// Comments before the closing brace stay inside the block.
+    fn braced(&mut self, statements: &[Stmt]) {
+        let close = self.braces.pop_front();
+        self.output.push_str("{\n");
+        self.indent += 1;
+        self.statements(statements, false);
+        if let Some(close) = close {
+            self.comments_before(close);
+        }
+        self.indent -= 1;
+        self.write_indent();
+        self.output.push('}');
+    }
+
+    /// Writes the body of an `if`, `while` or `for` after its header,
+    /// leaving the last line unterminated.
+    fn body(&mut self, body: &Stmt) {
+        match body {
+            Stmt::Block(block) => {
+                self.output.push(' ');
+                self.braced(&block.statements);
+            }
+            _ => {
+                self.output.push('\n');
+                self.indent += 1;
+                self.statement(body);
+                self.indent -= 1;
+                self.output.pop();
+            }
+        }
+    }
+
+    fn function(&mut self, header: String, function: &StmtFunction) {
+        self.write_indent();
+        self.output.push_str(&header);
+        self.output.push_str(&format!("({}) ", parameters(&function.params)));
+        self.braced(&function.body);
+        self.output.push('\n');
+    }
+
+    fn for_loop(&mut self, initializer: Option<&Stmt>, stmt: &StmtWhile) {
+        let initializer = match initializer {
+            Some(Stmt::Var(var)) => self.var(var),
+            Some(Stmt::Expression(expression)) => format!("{};", self.expr(&expression.expression)),
+            _ => ";".to_string(),
+        };
+
+        let mut header = format!("for ({}", initializer);
+        if !matches!(stmt.condition, Expr::Literal { value: Some(Object::Boolean(true)), .. }) {
+            header.push(' ');
+            header.push_str(&self.expr(&stmt.condition));
+        }
+        header.push(';');
+        if let Some(increment) = &stmt.increment {
+            header.push(' ');
+            header.push_str(&self.expr(increment));
+        }
+        header.push(')');
+
+        self.write_indent();
+        self.output.push_str(&header);
+        self.body(&stmt.body);
+        self.output.push('\n');
+    }
+
+    fn if_chain(&mut self, stmt: &StmtIf) {
+        let condition = self.expr(&stmt.condition);
+        self.output.push_str(&format!("if ({})", condition));
+        self.body(&stmt.then_branch);
+
+        if let Some(else_branch) = &stmt.else_branch {
+            if let Stmt::Block(_) = *stmt.then_branch {
+                self.output.push_str(" else");
+            } else {
+                self.output.push('\n');
+                self.write_indent();
+                self.output.push_str("else");
+            }
+
+            match else_branch.as_ref() {
+                Stmt::If(else_if) => {
+                    self.output.push(' ');
+                    self.if_chain(else_if);
+                }
+                other => self.body(other),
+            }
+        }
+    }
+
+    fn var(&mut self, stmt: &StmtVar) -> String {
+        match &stmt.initializer {
+            Some(initializer) => format!("var {} = {};", stmt.name.lexeme, self.expr(initializer)),
+            None => format!("var {};", stmt.name.lexeme),
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) -> String {
+        expr.accept(self)
+    }
+}
+
+impl ExprVisitor<String> for Formatter {
+    fn visit_assign_expr(&mut self, expr: &Expr) -> String {
+        if let Expr::Assign { name, value, .. } = expr {
+            format!("{} = {}", name.lexeme, self.expr(value))
+        } else {
+            String::new()
+        }
+    }
+
+    fn visit_binary_expr(&mut self, expr: &Expr) -> String {
+        if let Expr::Binary { left, operator, right } = expr {
+            let left = self.expr(left);
+            let right = self.expr(right);
+            format!("{} {} {}", left, operator.lexeme, right)
+        } else {
+            String::new()
+        }
+    }
+
+    fn visit_call_expr(&mut self, expr: &Expr) -> String {
+        if let Expr::Call { callee, arguments, .. } = expr {
+            let callee = self.expr(callee);
+            let arguments: Vec<String> = arguments.iter().map(|argument| self.expr(argument)).collect();
+            format!("{}({})", callee, arguments.join(", "))
+        } else {
+            String::new()
+        }
+    }
+
+    fn visit_grouping_expr(&mut self, expr: &Expr) -> String {
+        if let Expr::Grouping { expression } = expr {
+            format!("({})", self.expr(expression))
+        } else {
+            String::new()
+        }
+    }
+
+    fn visit_literal_expr(&mut self, expr: &Expr) -> String {
+        match expr {
+            Expr::Literal { value: Some(value), .. } => literal(value),
+            _ => "nil".to_string(),
+        }
+    }
+
+    fn visit_logical_expr(&mut self, expr: &Expr) -> String {
+        if let Expr::Logical { left, operator, right } = expr {
+            let left = self.expr(left);
+            let right = self.expr(right);
+            format!("{} {} {}", left, operator.lexeme, right)
+        } else {
+            String::new()
+        }
+    }
+
+    fn visit_unary_expr(&mut self, expr: &Expr) -> String {
+        if let Expr::Unary { operator, right } = expr {
+            format!("{}{}", operator.lexeme, self.expr(right))
+        } else {
+            String::new()
+        }
+    }
+
+    fn visit_variable_expr(&mut self, expr: &Expr) -> String {
+        if let Expr::Variable { name, .. } = expr {
+            name.lexeme.clone()
+        } else {
+            String::new()
+        }
+    }
+
+    fn visit_get_expr(&mut self, expr: &Expr) -> String {
+        if let Expr::Get { object, name } = expr {
+            format!("{}.{}", self.expr(object), name.lexeme)
+        } else {
+            String::new()
+        }
+    }
+
+    fn visit_set_expr(&mut self, expr: &Expr) -> String {
+        if let Expr::Set { object, name, value } = expr {
+            let object = self.expr(object);
+            format!("{}.{} = {}", object, name.lexeme, self.expr(value))
+        } else {
+            String::new()
+        }
+    }
+
+    fn visit_index_expr(&mut self, expr: &Expr) -> String {
+        if let Expr::Index { object, index, .. } = expr {
+            let object = self.expr(object);
+            format!("{}[{}]", object, self.expr(index))
+        } else {
+            String::new()
+        }
+    }
+
+    fn visit_super_expr(&mut self, expr: &Expr) -> String {
+        if let Expr::Super { method, .. } = expr {
+            format!("super.{}", method.lexeme)
+        } else {
+            String::new()
+        }
+    }
+
+    fn visit_this_expr(&mut self, _expr: &Expr) -> String {
+        "this".to_string()
+    }
+
+    fn visit_interpolation_expr(&mut self, expr: &Expr) -> String {
+        let (segments, values) = match expr {
+            Expr::Interpolation { segments, values, .. } => (segments, values),
+            _ => return String::new(),
+        };
+        let mut rendered = String::from("\"");
+        for (i, segment) in segments.iter().enumerate() {
+            rendered.push_str(&escape(segment));
+            if let Some(value) = values.get(i) {
+                rendered.push_str("${");
+                rendered.push_str(&self.expr(value));
+                rendered.push('}');
+            }
+        }
+        rendered.push('"');
+        rendered
+    }
+
+    fn visit_lambda_expr(&mut self, expr: &Expr) -> String {
+        let declaration = match expr {
+            Expr::Lambda { declaration } => declaration,
+            _ => return String::new(),
+        };
+        let params = parameters(&declaration.params);
+
+        if declaration.name.token_type != TokenType::Arrow {
+            return self.capture(|formatter| {
+                formatter.output.push_str(&format!("fun ({}) ", params));
+                formatter.braced(&declaration.body);
+            });
+        }
+
+        // An arrow function with an expression body is parsed as a single
+        // return whose keyword is the arrow itself.
+        if let [Stmt::Return(StmtReturn { keyword, value: Some(value) })] = declaration.body.as_slice() {
+            if *keyword == declaration.name {
+                return format!("({}) => {}", params, self.expr(value));
+            }
+        }
+        self.capture(|formatter| {
+            formatter.output.push_str(&format!("({}) => ", params));
+            formatter.braced(&declaration.body);
+        })
+    }
+}
+
+impl Formatter {
+    /// Runs `write` against an empty output buffer and returns what it wrote,
+    /// so function bodies can be embedded in expressions.
+    fn capture(&mut self, write: impl FnOnce(&mut Self)) -> String {
+        let saved = std::mem::take(&mut self.output);
+        write(self);
+        std::mem::replace(&mut self.output, saved)
+    }
+}
+
+impl StmtVisitor<()> for Formatter {
+    fn visit_block_stmt(&mut self, stmt: &StmtBlock) {
+        self.write_indent();
+        self.braced(&stmt.statements);
+        self.output.push('\n');
+    }
+
+    fn visit_class_stmt(&mut self, stmt: &StmtClass) {
+        let mut header = format!("class {} ", stmt.name.lexeme);
+        if let Some(superclass) = &stmt.superclass {
+            header = format!("class {} < {} ", stmt.name.lexeme, self.expr(superclass));
+        }
+
+        // Members are stored by kind; put them back in source order.
+        let mut members: Vec<(&str, &Rc<StmtFunction>)> = Vec::new();
+        members.extend(stmt.methods.iter().map(|method| ("", method)));
+        members.extend(stmt.class_methods.iter().map(|method| ("class ", method)));
+        members.extend(stmt.getters.iter().map(|getter| ("getter", getter)));
+        members.extend(stmt.setters.iter().map(|setter| ("setter", setter)));
+        members.sort_by_key(|(_, function)| function.name.line);
+
+        let close = self.braces.pop_front();
+        self.write_indent();
+        self.output.push_str(&header);
+        self.output.push_str("{\n");
+        self.indent += 1;
+        for (i, (kind, function)) in members.into_iter().enumerate() {
+            if i > 0 {
+                self.output.push('\n');
+            }
+            self.comments_before(function.name.line);
+            let name = &function.name.lexeme;
+            match kind {
+                "getter" => {
+                    self.write_indent();
+                    self.output.push_str(&format!("{} ", name));
+                    self.braced(&function.body);
+                    self.output.push('\n');
+                }
+                "setter" => self.function(format!("{}=", name), function),
+                prefix => self.function(format!("{}{}", prefix, name), function),
+            }
+        }
+        if let Some(close) = close {
+            self.comments_before(close);
+        }
+        self.indent -= 1;
+        self.write_indent();
+        self.output.push_str("}\n");
+    }
+
+    fn visit_expression_stmt(&mut self, stmt: &StmtExpression) {
+        let text = format!("{};", self.expr(&stmt.expression));
+        self.simple(text, stmt.expression.line());
+    }
+
+    fn visit_function_stmt(&mut self, stmt: &Rc<StmtFunction>) {
+        self.function(format!("fun {}", stmt.name.lexeme), stmt);
+    }
+
+    fn visit_if_stmt(&mut self, stmt: &StmtIf) {
+        self.write_indent();
+        self.if_chain(stmt);
+        self.output.push('\n');
+    }
+
+    fn visit_print_stmt(&mut self, stmt: &StmtPrint) {
+        let text = format!("print {};", self.expr(&stmt.expression));
+        self.simple(text, stmt.keyword.line);
+    }
+
+    fn visit_return_stmt(&mut self, stmt: &StmtReturn) {
+        let text = match &stmt.value {
+            Some(value) => format!("return {};", self.expr(value)),
+            None => "return;".to_string(),
+        };
+        self.simple(text, stmt.keyword.line);
+    }
+
+    fn visit_test_stmt(&mut self, stmt: &StmtTest) {
+        self.write_indent();
+        self.output.push_str(&format!("test \"{}\" ", escape(&stmt.name)));
+        self.braced(&stmt.body);
+        self.output.push('\n');
+    }
+
+    fn visit_var_stmt(&mut self, stmt: &StmtVar) {
+        let text = self.var(stmt);
+        self.simple(text, stmt.name.line);
+    }
+
+    fn visit_while_stmt(&mut self, stmt: &StmtWhile) {
+        if stmt.keyword.token_type == TokenType::For {
+            self.for_loop(None, stmt);
+            return;
+        }
+
+        let condition = self.expr(&stmt.condition);
+        self.write_indent();
+        self.output.push_str(&format!("while ({})", condition));
+        self.body(&stmt.body);
+        self.output.push('\n');
+    }
+}
+
+fn is_declaration(stmt: &Stmt) -> bool {
+    matches!(stmt, Stmt::Function(_) | Stmt::Class(_) | Stmt::Test(_))
+}
+
+fn parameters(params: &[Token]) -> String {
+    params
+        .iter()
+        .map(|param| param.lexeme.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn literal(value: &Object) -> String {
+    match value {
+        // Keep the decimal point so the number scans back as a float.
+        Object::Number(n) if n.fract() == 0.0 => format!("{}.0", n),
+        Object::Number(n) => n.to_string(),
+        Object::Integer(i) => i.to_string(),
+        Object::BigInteger(b) => b.to_string(),
+        Object::String(s) => format!("\"{}\"", escape(s)),
+        Object::Boolean(b) => b.to_string(),
+        Object::Nil => "nil".to_string(),
+    }
+}
+
+/// Escapes string contents so they scan back to the same value.
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            '\0' => escaped.push_str("\\0"),
+            '$' if chars.peek() == Some(&'{') => escaped.push_str("\\$"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -1,49 +1,325 @@
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 use std::cell::RefCell;
+use std::io::{self, Write};
+use std::panic::{self, AssertUnwindSafe};
 
-use crate::interpreter::environment::{ Environment, Value};
-use crate::interpreter::stmt::{Stmt, StmtVisitor};
+use crate::interpreter::cancellation::{CancelHandle, Interrupted};
+use crate::interpreter::coverage::Coverage;
+use crate::interpreter::debugger::{DebugHook, Flow};
+use crate::interpreter::environment::Environment;
+use crate::interpreter::value::Value;
+use crate::interpreter::return_value::Return;
+use crate::interpreter::stmt::{
+    Stmt, StmtBlock, StmtClass, StmtExpression, StmtFunction, StmtIf, StmtPrint, StmtReturn,
+    StmtTest, StmtVar, StmtVisitor, StmtWhile,
+};
 use crate::interpreter::runtime_error::RuntimeError;
 use crate::interpreter::token::Token;
 use crate::interpreter::token_type::TokenType;
 use crate::interpreter::expr::{Expr, ExprVisitor};
-use crate::interpreter::lox_callable::LoxCallable;
+use crate::interpreter::io_natives::{self, Capability};
+use crate::interpreter::limits::{self, Limits, Usage};
+use crate::interpreter::lox_callable::{LoxCallable, NativeFunction};
+use crate::interpreter::lox_class::LoxClass;
+use crate::interpreter::lox_function::LoxFunction;
+use crate::interpreter::lox_instance::LoxInstance;
+use crate::interpreter::lox_string::LoxString;
+use crate::interpreter::json_module;
+use crate::interpreter::math_module;
+use crate::interpreter::number;
+use crate::interpreter::profiler::Profiler;
+use crate::interpreter::random_module::{self, Random};
+use crate::interpreter::time_module;
+use crate::interpreter::value::Object;
 
 pub struct Interpreter {
     globals: Rc<RefCell<Environment>>,
     environment: Rc<RefCell<Environment>>,
     locals: HashMap<Expr, usize>,
+    call_paren: Option<Token>, // Closing paren (or other token) of the call being made, for errors at the call
+    capabilities: HashSet<Capability>, // I/O the embedder allows; empty by default
+    rng: Random,                       // Generator behind the `random` module
+    limits: Limits,
+    usage: Usage, // Counted against `limits`; reset by each `interpret`
+    cancel: CancelHandle,
+    frames: Vec<Frame>,             // Calls in progress, the script itself first
+    hook: Option<Box<dyn DebugHook>>, // Consulted before each statement
+    output: Option<Box<dyn Write>>,   // Where `print` writes; stdout if unset
+    profiler: Option<Profiler>,
+    coverage: Option<Coverage>,
+}
+
+/// A call in progress, as shown in a backtrace.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub function: String,
+    pub line: usize, // Line of the statement being run; kept while a hook is set
+    pub environment: Option<Rc<RefCell<Environment>>>, // Where it made the call above it
+}
+
+impl Frame {
+    fn new(function: &str) -> Self {
+        Self {
+            function: function.to_string(),
+            line: 0,
+            environment: None,
+        }
+    }
 }
 
 impl Interpreter {
     pub fn new() -> Self {
+        let globals = Self::fresh_globals();
+        Self {
+            globals: globals.clone(),
+            environment: globals,
+            locals: HashMap::new(),
+            call_paren: None,
+            capabilities: HashSet::new(),
+            rng: Random::from_time(),
+            limits: Limits::default(),
+            usage: Usage::start(),
+            cancel: CancelHandle::default(),
+            frames: vec![Frame::new("<script>")],
+            hook: None,
+            output: None,
+            profiler: None,
+            coverage: None,
+        }
+    }
+
+    /// Builds the global environment scripts start with: the natives and
+    /// the built-in modules.
+    fn fresh_globals() -> Rc<RefCell<Environment>> {
         let globals = Rc::new(RefCell::new(Environment::new()));
 
         // Define a native function "clock"
         globals.borrow_mut().define(
             "clock",
-            Value::Callable(LoxCallable::Native {
+            Value::Callable(Box::new(NativeFunction {
                 arity: 0,
-                call: Box::new(|_, _| {
+                optional: 0,
+                function: |_, _| {
                     let current_time = chrono::Utc::now().timestamp_millis() as f64 / 1000.0;
                     Value::Number(current_time)
-                }),
-            }),
-        )
+                },
+            })),
+        );
 
-        Self {
-            globals: globals.clone(),
-            environment: globals,
-            locals: HashMap::new(),
+        // Define a native function "str", also used by string interpolation
+        globals.borrow_mut().define(
+            "str",
+            Value::Callable(Box::new(NativeFunction {
+                arity: 1,
+                optional: 0,
+                function: |interpreter, arguments| Value::String(interpreter.stringify(&arguments[0])),
+            })),
+        );
+
+        // Define the "math" module and the number conversion natives
+        globals.borrow_mut().define("math", math_module::module());
+        math_module::define_globals(&mut globals.borrow_mut());
+
+        // Define the "json" module
+        globals.borrow_mut().define("json", json_module::module());
+
+        // Define the "time" module
+        globals.borrow_mut().define("time", time_module::module());
+
+        // Define the "random" module
+        globals.borrow_mut().define("random", random_module::module());
+
+        // Define the I/O natives; each one checks `capabilities` when called
+        io_natives::define_globals(&mut globals.borrow_mut());
+        globals
+    }
+
+    /// Grants scripts run by this interpreter an I/O capability.
+    pub fn allow(&mut self, capability: Capability) {
+        self.capabilities.insert(capability);
+    }
+
+    /// Returns true if scripts may use `capability`.
+    pub fn has_capability(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+
+    /// Raises a runtime error unless scripts may use `capability`.
+    pub fn require(&self, capability: Capability, native: &str) {
+        if !self.has_capability(capability) {
+            self.native_error(&format!(
+                "'{}' requires the {} capability, which this interpreter does not allow.",
+                native,
+                capability.name()
+            ));
         }
     }
 
-    /// Interprets a list of statements.
-    pub fn interpret(&mut self, statements: &[Stmt]) {
-        for stmt in statements {
-            self.execute(stmt);
+    /// Reseeds the `random` module so runs are reproducible.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = Random::new(seed);
+    }
+
+    /// Returns the generator behind the `random` module.
+    pub fn rng(&mut self) -> &mut Random {
+        &mut self.rng
+    }
+
+    /// Sets the resource limits enforced on scripts.
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// Returns the global variables, sorted by name.
+    pub fn global_bindings(&self) -> Vec<(String, Value)> {
+        self.globals.borrow().bindings()
+    }
+
+    /// Forgets everything scripts have defined, keeping the embedder's
+    /// settings (capabilities, limits, seed and cancel handle).
+    pub fn reset(&mut self) {
+        self.globals = Self::fresh_globals();
+        self.environment = self.globals.clone();
+        self.locals.clear();
+        self.call_paren = None;
+    }
+
+    /// Installs a hook to be consulted before each statement runs.
+    pub fn set_hook(&mut self, hook: Box<dyn DebugHook>) {
+        self.hook = Some(hook);
+    }
+
+    /// Sends what `print` writes to `output` instead of stdout.
+    pub fn set_output(&mut self, output: Box<dyn Write>) {
+        self.output = Some(output);
+    }
+
+    /// Writes `text` where `print` does, flushing so a prompt without a
+    /// line ending shows at once.
+    pub fn write_output(&mut self, text: &str) {
+        match &mut self.output {
+            Some(output) => {
+                let _ = output.write_all(text.as_bytes());
+                let _ = output.flush();
+            }
+            None => {
+                print!("{}", text);
+                let _ = io::stdout().flush();
+            }
+        }
+    }
+
+    /// Starts recording calls, time and line hits for `take_profiler`.
+    pub fn start_profiling(&mut self) {
+        self.profiler = Some(Profiler::new());
+    }
+
+    /// Stops profiling and returns what was recorded.
+    pub fn take_profiler(&mut self) -> Option<Profiler> {
+        let mut profiler = self.profiler.take()?;
+        profiler.finish();
+        Some(profiler)
+    }
+
+    /// Starts recording which statements and branches of a program run.
+    pub fn set_coverage(&mut self, coverage: Coverage) {
+        self.coverage = Some(coverage);
+    }
+
+    /// Stops recording coverage and returns what was recorded.
+    pub fn take_coverage(&mut self) -> Option<Coverage> {
+        self.coverage.take()
+    }
+
+    /// Returns the calls in progress, innermost last.
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    /// Records entry into a Lox function, called by `LoxFunction::call`
+    /// for every call: from a call expression, or through a getter, setter,
+    /// initializer or special method. Raises `Stack overflow.` at the call
+    /// site once the call depth limit is reached.
+    pub fn enter_function(&mut self, function: &str) {
+        if self.limits.max_call_depth.is_some_and(|max| self.usage.call_depth >= max) {
+            RuntimeError::new(self.call_site(), "Stack overflow.".to_string()).raise();
+        }
+        self.usage.call_depth += 1;
+
+        if let Some(caller) = self.frames.last_mut() {
+            caller.environment = Some(self.environment.clone());
+        }
+        if let Some(profiler) = &mut self.profiler {
+            profiler.enter(function);
         }
+        self.frames.push(Frame::new(function));
+    }
+
+    /// Records that the innermost Lox function has returned.
+    pub fn exit_function(&mut self) {
+        self.usage.call_depth -= 1;
+        self.frames.pop();
+        if let Some(profiler) = &mut self.profiler {
+            profiler.exit();
+        }
+    }
+
+    /// Returns the environment statements are currently running in.
+    pub fn environment(&self) -> Rc<RefCell<Environment>> {
+        self.environment.clone()
+    }
+
+    /// Returns the global environment.
+    pub fn globals(&self) -> Rc<RefCell<Environment>> {
+        self.globals.clone()
+    }
+
+    /// Runs the body of a `test` declaration in a scope of its own, after
+    /// `interpret` has run the script's other top-level statements.
+    pub fn run_test(&mut self, test: &StmtTest) {
+        let environment = Rc::new(RefCell::new(Environment::with_enclosing(self.globals.clone())));
+        // The resolver rejects `return` outside functions.
+        let _ = self.execute_block(&test.body, environment);
+    }
+
+    /// Returns a handle that can cancel running scripts from another thread.
+    pub fn cancel_handle(&self) -> CancelHandle {
+        self.cancel.clone()
+    }
+
+    /// Interprets a list of statements. Returns `Err(Interrupted)` if the
+    /// script was cancelled, after which the request is cleared and the
+    /// interpreter is ready to run another script with its globals intact.
+    /// A cancellation requested before the script starts stops it at its
+    /// first statement.
+    pub fn interpret(&mut self, statements: &[Stmt]) -> Result<(), Interrupted> {
+        self.usage = Usage::start();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            for stmt in statements {
+                // The resolver rejects `return` outside functions.
+                let _ = self.execute(stmt);
+            }
+        }));
+
+        let payload = match result {
+            Ok(()) => return Ok(()),
+            Err(payload) => payload,
+        };
+
+        // Unwinding skipped every environment restore on the way out.
+        self.environment = self.globals.clone();
+        self.call_paren = None;
+        self.frames.truncate(1);
+        if let Some(profiler) = &mut self.profiler {
+            profiler.unwind();
+        }
+        if payload.is::<Interrupted>() {
+            self.cancel.reset();
+            return Err(Interrupted);
+        }
+        panic::resume_unwind(payload)
     }
 
     /// Resolves an expression's depth for variable lookups.
@@ -51,21 +327,33 @@ impl Interpreter {
         self.locals.insert(expr.clone(), depth);
     }
 
-    /// Executes a block of statements in a new environment.
-    pub fn execute_block(&mut self, statements: &[Stmt], new_env: Rc<RefCell<Environment>>) {
+    /// Gives an expression the optimizer rewrote the depth resolved for
+    /// the original.
+    pub fn re_resolve(&mut self, original: &Expr, rewritten: &Expr) {
+        if let Some(&depth) = self.locals.get(original) {
+            self.locals.insert(rewritten.clone(), depth);
+        }
+    }
+
+    /// Executes a block of statements in a new environment. Returns
+    /// `Err` with the value of a `return` statement that ends it early.
+    pub fn execute_block(
+        &mut self,
+        statements: &[Stmt],
+        new_env: Rc<RefCell<Environment>>,
+    ) -> Result<(), Return> {
         let previous_env = self.environment.clone();
         self.environment = new_env;
 
-        for stmt in statements {
-            self.execute(stmt);
-        }
+        let result = statements.iter().try_for_each(|stmt| self.execute(stmt));
 
         self.environment = previous_env; // Restore the previous environment
+        result
     }
 
     fn look_up_variable(&self, name: &Token, expr: &Expr) -> Value {
         if let Some(distance) = self.locals.get(expr) {
-            self.environment.borrow().get_at(*distance, &name.lexeme)
+            Environment::get_at(&self.environment, *distance, &name.lexeme)
         } else {
             self.globals.borrow().get(name)
         }
@@ -77,20 +365,48 @@ impl Interpreter {
     }
 
     /// Executes a statement.
-    fn execute(&mut self, stmt: &Stmt) {
-        stmt.accept(self);
+    fn execute(&mut self, stmt: &Stmt) -> Result<(), Return> {
+        if self.cancel.is_cancelled() {
+            // `resume_unwind` skips the panic hook, so nothing is printed.
+            panic::resume_unwind(Box::new(Interrupted));
+        }
+
+        // Exceeded limits are reported at the statement about to run.
+        if let Some(message) = self.usage.step(&self.limits) {
+            Self::limit_error(stmt.line(), message);
+        }
+
+        if let Some(profiler) = &mut self.profiler {
+            profiler.statement(stmt);
+        }
+        if let Some(coverage) = &mut self.coverage {
+            coverage.statement(stmt);
+        }
+
+        if let Some(mut hook) = self.hook.take() {
+            if let Some(frame) = self.frames.last_mut() {
+                frame.line = stmt.line();
+            }
+            let flow = hook.before_statement(self, stmt);
+            self.hook = Some(hook);
+            if flow == Flow::Stop {
+                panic::resume_unwind(Box::new(Interrupted));
+            }
+        }
+        stmt.accept(self)
     }
 }
 
 // Implement ExprVisitor for Interpreter
 impl ExprVisitor<Value> for Interpreter {
-    fn visit_assign_expr(&mut self, name: &Token, value: &Expr) -> Value {
+    fn visit_assign_expr(&mut self, expr: &Expr) -> Value {
+        let Expr::Assign { name, value, .. } = expr else {
+            return Value::Nil;
+        };
         let evaluated_value = self.evaluate(value);
 
-        if let Some(distance) = self.locals.get(&Expr::Variable(name.clone())) {
-            self.environment
-                .borrow_mut()
-                .assign_at(*distance, name, evaluated_value.clone());
+        if let Some(distance) = self.locals.get(expr) {
+            Environment::assign_at(&self.environment, *distance, name, evaluated_value.clone());
         } else {
             self.globals
                 .borrow_mut()
@@ -100,79 +416,682 @@ impl ExprVisitor<Value> for Interpreter {
         evaluated_value
     }
 
-    fn visit_binary_expr(&mut self, left: &Expr, operator: &Token, right: &Expr) -> Value {
+    fn visit_binary_expr(&mut self, expr: &Expr) -> Value {
+        let Expr::Binary { left, operator, right } = expr else {
+            return Value::Nil;
+        };
         let left = self.evaluate(left);
         let right = self.evaluate(right);
 
+        // Instances may overload operators through special methods.
+        if let Value::Instance(instance) = &left {
+            if let Some(result) = self.overloaded_binary(instance, operator, &right) {
+                return result;
+            }
+        }
+
+        // Strings and big integers grow with their operands.
+        if matches!(
+            operator.token_type,
+            TokenType::Plus
+                | TokenType::Minus
+                | TokenType::Slash
+                | TokenType::Star
+                | TokenType::TildeSlash
+                | TokenType::Percent
+                | TokenType::Ampersand
+                | TokenType::Pipe
+                | TokenType::Caret
+                | TokenType::LessLess
+                | TokenType::GreaterGreater
+        ) {
+            self.charge_at(operator, limits::heap_size(&left) + limits::heap_size(&right));
+        }
+
         match operator.token_type {
             TokenType::Plus => match (left, right) {
-                (Value::Number(a), Value::Number(b)) => Value::Number(a + b),
                 (Value::String(a), Value::String(b)) => Value::String(a + &b),
-                _ => panic!("{}", RuntimeError::new(
-                    operator,
-                    "Operands must be two numbers or two strings."
-                )),
+                (left, right) if number::is_number(&left) && number::is_number(&right) => {
+                    Self::numeric(operator, number::arithmetic(&operator.token_type, &left, &right))
+                }
+                _ => RuntimeError::new(
+                    operator.clone(),
+                    "Operands must be two numbers or two strings.".to_string(),
+                ).raise(),
             },
-            TokenType::Minus => {
-                let (a, b) = Self::number_operands(operator, &left, &right);
-                Value::Number(a - b)
+            TokenType::Minus
+            | TokenType::Slash
+            | TokenType::Star
+            | TokenType::TildeSlash
+            | TokenType::Percent => {
+                Self::numeric(operator, number::arithmetic(&operator.token_type, &left, &right))
             }
-            TokenType::Slash => {
-                let (a, b) = Self::number_operands(operator, &left, &right);
-                Value::Number(a / b)
+            TokenType::Ampersand
+            | TokenType::Pipe
+            | TokenType::Caret
+            | TokenType::LessLess
+            | TokenType::GreaterGreater => {
+                Self::numeric(operator, number::bitwise(&operator.token_type, &left, &right))
             }
-            TokenType::Star => {
-                let (a, b) = Self::number_operands(operator, &left, &right);
-                Value::Number(a * b)
+            TokenType::Greater => {
+                let ordering = Self::compare_operands(operator, &left, &right);
+                Value::Boolean(ordering == Some(Ordering::Greater))
             }
+            TokenType::GreaterEqual => {
+                let ordering = Self::compare_operands(operator, &left, &right);
+                Value::Boolean(matches!(ordering, Some(Ordering::Greater | Ordering::Equal)))
+            }
+            TokenType::Less => {
+                let ordering = Self::compare_operands(operator, &left, &right);
+                Value::Boolean(ordering == Some(Ordering::Less))
+            }
+            TokenType::LessEqual => {
+                let ordering = Self::compare_operands(operator, &left, &right);
+                Value::Boolean(matches!(ordering, Some(Ordering::Less | Ordering::Equal)))
+            }
+            TokenType::EqualEqual => Value::Boolean(Self::is_equal(&left, &right)),
+            TokenType::BangEqual => Value::Boolean(!Self::is_equal(&left, &right)),
             // Add other binary operations
             _ => panic!("Unsupported binary operator"),
         }
     }
 
-    // Implement other `ExprVisitor` methods...
+    fn visit_grouping_expr(&mut self, expr: &Expr) -> Value {
+        match expr {
+            Expr::Grouping { expression } => self.evaluate(expression),
+            _ => Value::Nil,
+        }
+    }
+
+    fn visit_variable_expr(&mut self, expr: &Expr) -> Value {
+        match expr {
+            Expr::Variable { name, .. } => self.look_up_variable(name, expr),
+            _ => Value::Nil,
+        }
+    }
+
+    fn visit_literal_expr(&mut self, expr: &Expr) -> Value {
+        match expr {
+            Expr::Literal { value: Some(value), .. } => match value {
+                Object::Number(n) => Value::Number(*n),
+                Object::Integer(i) => Value::Integer(*i),
+                Object::BigInteger(b) => Value::BigInteger(b.clone()),
+                Object::String(s) => Value::String(s.clone()),
+                Object::Boolean(b) => Value::Boolean(*b),
+                Object::Nil => Value::Nil,
+            },
+            _ => Value::Nil,
+        }
+    }
+
+    fn visit_unary_expr(&mut self, expr: &Expr) -> Value {
+        if let Expr::Unary { operator, right } = expr {
+            let right = self.evaluate(right);
+            match operator.token_type {
+                TokenType::Bang => Value::Boolean(!Self::is_truthy(&right)),
+                TokenType::Minus => Self::numeric(operator, number::negate(&right)),
+                TokenType::Tilde => Self::numeric(operator, number::bit_not(&right)),
+                _ => Value::Nil,
+            }
+        } else {
+            Value::Nil
+        }
+    }
+
+    fn visit_logical_expr(&mut self, expr: &Expr) -> Value {
+        if let Expr::Logical { left, operator, right } = expr {
+            let left = self.evaluate(left);
+            let short_circuit = if operator.token_type == TokenType::Or {
+                Self::is_truthy(&left)
+            } else {
+                !Self::is_truthy(&left)
+            };
+            if let Some(coverage) = &mut self.coverage {
+                coverage.logical(operator, !short_circuit);
+            }
+            if short_circuit {
+                left
+            } else {
+                self.evaluate(right)
+            }
+        } else {
+            Value::Nil
+        }
+    }
+
+    fn visit_call_expr(&mut self, expr: &Expr) -> Value {
+        if let Expr::Call { callee, paren, arguments } = expr {
+            let callee = self.evaluate(callee);
+            let mut arguments: Vec<Value> = arguments
+                .iter()
+                .map(|argument| self.evaluate(argument))
+                .collect();
+
+            let function: Box<dyn LoxCallable> = match callee {
+                Value::Callable(function) => function,
+                // Instances with `__call__` can be called like functions.
+                Value::Instance(instance) => match instance.special_method("__call__") {
+                    Some(method) => Box::new(method),
+                    None => RuntimeError::new(
+                        paren.clone(),
+                        "Can only call functions and classes.".to_string(),
+                    ).raise(),
+                },
+                _ => RuntimeError::new(
+                    paren.clone(),
+                    "Can only call functions and classes.".to_string(),
+                ).raise(),
+            };
+
+            let (arity, optional) = (function.arity(), function.optional());
+            if arguments.len() > arity || arguments.len() + optional < arity {
+                let expected = match optional {
+                    0 => arity.to_string(),
+                    _ => format!("{} to {}", arity - optional, arity),
+                };
+                RuntimeError::new(
+                    paren.clone(),
+                    format!("Expected {} arguments but got {}.", expected, arguments.len()),
+                ).raise();
+            }
+            arguments.resize(arity, Value::Nil);
+
+            if let Some(message) = self.usage.check(&self.limits) {
+                RuntimeError::new(paren.clone(), message.to_string()).raise();
+            }
+
+            self.call_paren = Some(paren.clone());
+            function.call(self, arguments)
+        } else {
+            Value::Nil
+        }
+    }
+
+    fn visit_index_expr(&mut self, expr: &Expr) -> Value {
+        if let Expr::Index { object, bracket, index } = expr {
+            let object = self.evaluate(object);
+            let index = self.evaluate(index);
+
+            match &object {
+                Value::String(s) => return LoxString::index(s, &index, bracket),
+                Value::List(list) => {
+                    let list = list.borrow();
+                    let position = match index {
+                        Value::Integer(i) if i >= 0 && (i as usize) < list.len() => i as usize,
+                        Value::Integer(_) => RuntimeError::new(
+                            bracket.clone(),
+                            "List index out of range.".to_string(),
+                        ).raise(),
+                        _ => RuntimeError::new(
+                            bracket.clone(),
+                            "List index must be an integer.".to_string(),
+                        ).raise(),
+                    };
+                    return list[position].clone();
+                }
+                Value::Map(map) => match &index {
+                    Value::String(key) => {
+                        return map.borrow().get(key).cloned().unwrap_or(Value::Nil);
+                    }
+                    _ => RuntimeError::new(
+                        bracket.clone(),
+                        "Map key must be a string.".to_string(),
+                    ).raise(),
+                },
+                Value::Instance(instance) => {
+                    if let Some(method) = instance.special_method("__index__") {
+                        return self.call_special(method, bracket, vec![index]);
+                    }
+                }
+                _ => {}
+            }
+
+            RuntimeError::new(
+                bracket.clone(),
+                "Only indexable values can be subscripted.".to_string(),
+            ).raise();
+        } else {
+            Value::Nil
+        }
+    }
+
+    fn visit_get_expr(&mut self, expr: &Expr) -> Value {
+        if let Expr::Get { object, name } = expr {
+            match self.evaluate(object) {
+                Value::Instance(instance) => instance.get(name, self),
+                Value::String(s) => LoxString::get(&s, name),
+                Value::Module(module) => module.get(name),
+                Value::Callable(callable) => {
+                    // Properties on a class are its class methods.
+                    let method = callable
+                        .as_class()
+                        .and_then(|klass| klass.find_class_method(&name.lexeme));
+                    match method {
+                        Some(method) => Value::Callable(Box::new((*method).clone())),
+                        None => RuntimeError::new(
+                            name.clone(),
+                            format!("Undefined property '{}'.", name.lexeme),
+                        ).raise(),
+                    }
+                }
+                _ => RuntimeError::new(
+                    name.clone(),
+                    "Only instances have properties.".to_string(),
+                ).raise(),
+            }
+        } else {
+            Value::Nil
+        }
+    }
+
+    fn visit_set_expr(&mut self, expr: &Expr) -> Value {
+        if let Expr::Set { object, name, value } = expr {
+            let object = self.evaluate(object);
+            if let Value::Instance(instance) = object {
+                let value = self.evaluate(value);
+                instance.set(name, value.clone(), self);
+                value
+            } else {
+                RuntimeError::new(
+                    name.clone(),
+                    "Only instances have fields.".to_string(),
+                ).raise();
+            }
+        } else {
+            Value::Nil
+        }
+    }
+
+    fn visit_super_expr(&mut self, expr: &Expr) -> Value {
+        let Expr::Super { method, .. } = expr else {
+            return Value::Nil;
+        };
+        // `super` is defined one scope out from the `this` of the method.
+        let distance = self.locals.get(expr).copied().unwrap_or(0);
+        let superclass = Environment::get_at(&self.environment, distance, "super");
+        let object = Environment::get_at(&self.environment, distance.saturating_sub(1), "this");
+
+        // As on instances, a getter shadows a method of the same name.
+        let klass = match &superclass {
+            Value::Callable(callable) => callable.as_class(),
+            _ => None,
+        };
+        if let (Some(klass), Value::Instance(instance)) = (klass, object) {
+            if let Some(getter) = klass.find_getter(&method.lexeme) {
+                return self.call_at(method, &getter.bind(instance), Vec::new());
+            }
+            if let Some(function) = klass.find_method(&method.lexeme) {
+                return Value::Callable(Box::new(function.bind(instance)));
+            }
+        }
+        RuntimeError::new(
+            method.clone(),
+            format!("Undefined property '{}'.", method.lexeme),
+        ).raise()
+    }
+
+    fn visit_this_expr(&mut self, expr: &Expr) -> Value {
+        match self.locals.get(expr) {
+            Some(distance) => Environment::get_at(&self.environment, *distance, "this"),
+            None => Value::Nil,
+        }
+    }
+
+    fn visit_interpolation_expr(&mut self, expr: &Expr) -> Value {
+        let Expr::Interpolation { start, segments, values } = expr else {
+            return Value::Nil;
+        };
+        let mut text = segments[0].clone();
+        for (value, segment) in values.iter().zip(&segments[1..]) {
+            let value = self.evaluate(value);
+            text.push_str(&self.stringify(&value));
+            text.push_str(segment);
+        }
+        self.charge_at(start, text.len());
+        Value::String(text)
+    }
+
+    fn visit_lambda_expr(&mut self, expr: &Expr) -> Value {
+        if let Expr::Lambda { declaration } = expr {
+            // Anonymous functions close over the environment they are evaluated in.
+            let function = LoxFunction::new(declaration.clone(), self.environment.clone(), false);
+            Value::Callable(Box::new(function))
+        } else {
+            Value::Nil
+        }
+    }
+
 }
 
 // Implement StmtVisitor for Interpreter
-impl StmtVisitor<()> for Interpreter {
-    fn visit_block_stmt(&mut self, statements: &[Stmt]) {
+impl StmtVisitor<Result<(), Return>> for Interpreter {
+    fn visit_block_stmt(&mut self, stmt: &StmtBlock) -> Result<(), Return> {
         let new_env = Rc::new(RefCell::new(Environment::with_enclosing(
             self.environment.clone(),
         )));
-        self.execute_block(statements, new_env);
+        self.execute_block(&stmt.statements, new_env)
+    }
+
+    fn visit_expression_stmt(&mut self, stmt: &StmtExpression) -> Result<(), Return> {
+        self.evaluate(&stmt.expression);
+        Ok(())
+    }
+
+    fn visit_function_stmt(&mut self, stmt: &Rc<StmtFunction>) -> Result<(), Return> {
+        let function = LoxFunction::new(stmt.clone(), self.environment.clone(), false);
+        self.environment
+            .borrow_mut()
+            .define(&stmt.name.lexeme, Value::Callable(Box::new(function)));
+        Ok(())
+    }
+
+    fn visit_print_stmt(&mut self, stmt: &StmtPrint) -> Result<(), Return> {
+        let value = self.evaluate(&stmt.expression);
+        let text = self.stringify(&value);
+        self.write_output(&format!("{}\n", text));
+        Ok(())
+    }
+
+    fn visit_return_stmt(&mut self, stmt: &StmtReturn) -> Result<(), Return> {
+        let value = match &stmt.value {
+            Some(value) => self.evaluate(value),
+            None => Value::Nil,
+        };
+        Err(Return::new(value))
+    }
+
+    fn visit_class_stmt(&mut self, stmt: &StmtClass) -> Result<(), Return> {
+        let superclass = stmt.superclass.as_ref().map(|superclass| {
+            let class = match self.evaluate(superclass) {
+                Value::Callable(callable) => callable.as_class().cloned(),
+                _ => None,
+            };
+            match class {
+                Some(class) => Rc::new(class),
+                None => RuntimeError::new(
+                    stmt.name.clone(),
+                    "Superclass must be a class.".to_string(),
+                ).raise(),
+            }
+        });
+
+        self.environment.borrow_mut().define(&stmt.name.lexeme, Value::Nil);
+
+        let closure = match &superclass {
+            Some(superclass) => {
+                let environment = Rc::new(RefCell::new(Environment::with_enclosing(
+                    self.environment.clone(),
+                )));
+                environment
+                    .borrow_mut()
+                    .define("super", Value::Callable(Box::new((**superclass).clone())));
+                environment
+            }
+            None => self.environment.clone(),
+        };
+
+        let mut klass = LoxClass::new(
+            stmt.name.lexeme.clone(),
+            superclass,
+            Self::class_functions(&stmt.name, &stmt.methods, &closure, true),
+        );
+        klass.class_methods = Self::class_functions(&stmt.name, &stmt.class_methods, &closure, false);
+        klass.getters = Self::class_functions(&stmt.name, &stmt.getters, &closure, false);
+        klass.setters = Self::class_functions(&stmt.name, &stmt.setters, &closure, false);
+
+        self.environment
+            .borrow_mut()
+            .assign(&stmt.name, Value::Callable(Box::new(klass)));
+        Ok(())
+    }
+
+    fn visit_if_stmt(&mut self, stmt: &StmtIf) -> Result<(), Return> {
+        let condition = Self::is_truthy(&self.evaluate(&stmt.condition));
+        if let Some(coverage) = &mut self.coverage {
+            coverage.condition(&stmt.condition, condition);
+        }
+        if condition {
+            self.execute(&stmt.then_branch)
+        } else if let Some(else_branch) = &stmt.else_branch {
+            self.execute(else_branch)
+        } else {
+            Ok(())
+        }
     }
 
-    fn visit_expression_stmt(&mut self, expression: &Expr) {
-        self.evaluate(expression);
+    fn visit_test_stmt(&mut self, _stmt: &StmtTest) -> Result<(), Return> {
+        // Tests run only under `lox test`, through `run_test`.
+        Ok(())
     }
 
-    fn visit_print_stmt(&mut self, expression: &Expr) {
-        let value = self.evaluate(expression);
-        println!("{}", value);
+    fn visit_while_stmt(&mut self, stmt: &StmtWhile) -> Result<(), Return> {
+        loop {
+            let condition = Self::is_truthy(&self.evaluate(&stmt.condition));
+            if let Some(coverage) = &mut self.coverage {
+                coverage.condition(&stmt.condition, condition);
+            }
+            if !condition {
+                break;
+            }
+            if let Some(message) = self.usage.check(&self.limits) {
+                Self::limit_error(stmt.condition.line(), message);
+            }
+            self.execute(&stmt.body)?;
+            if let Some(increment) = &stmt.increment {
+                self.evaluate(increment);
+            }
+        }
+        Ok(())
     }
 
-    fn visit_var_stmt(&mut self, name: &Token, initializer: &Option<Expr>) {
-        let value = if let Some(expr) = initializer {
+    fn visit_var_stmt(&mut self, stmt: &StmtVar) -> Result<(), Return> {
+        let value = if let Some(expr) = &stmt.initializer {
             self.evaluate(expr)
         } else {
             Value::Nil
         };
-        self.environment.borrow_mut().define(name.lexeme.clone(), value);
+        self.environment.borrow_mut().define(&stmt.name.lexeme, value);
+        Ok(())
     }
-
-    // Implement other `StmtVisitor` methods...
 }
 
 // Utility functions
 impl Interpreter {
-    fn number_operands(operator: &Token, left: &Value, right: &Value) -> (f64, f64) {
-        if let (Value::Number(a), Value::Number(b)) = (left, right) {
-            (*a, *b)
+    /// Raises a runtime error from inside a native function, reported at
+    /// the call that invoked it. Natives must raise before calling back
+    /// into Lox code, which would move the recorded call site.
+    pub fn native_error(&self, message: &str) -> ! {
+        RuntimeError::new(self.call_site(), message.to_string()).raise();
+    }
+
+    /// Charges `bytes` a native is about to allocate against the allocation
+    /// budget, raising `Allocation budget exceeded.` at the call if it is over.
+    pub fn charge(&mut self, bytes: usize) {
+        if let Some(message) = self.usage.charge(bytes, &self.limits) {
+            self.native_error(message);
+        }
+    }
+
+    /// Raises the error for an exceeded limit at `line`.
+    fn limit_error(line: usize, message: &str) -> ! {
+        RuntimeError::new(Token::new(TokenType::Eof, String::new(), None, line), message.to_string()).raise();
+    }
+
+    /// Like `charge`, for allocations made by the expression at `token`.
+    fn charge_at(&mut self, token: &Token, bytes: usize) {
+        if let Some(message) = self.usage.charge(bytes, &self.limits) {
+            RuntimeError::new(token.clone(), message.to_string()).raise();
+        }
+    }
+
+    /// Returns the closing paren of the call being made, where natives
+    /// report their errors.
+    pub fn call_site(&self) -> Token {
+        self.call_paren
+            .clone()
+            .unwrap_or_else(|| Token::new(TokenType::Eof, String::new(), None, 0))
+    }
+
+    /// Runs `f`, returning a runtime error it raises instead of unwinding,
+    /// with the calls it was in abandoned. Cancellation still unwinds.
+    pub fn catch_error<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> Result<T, RuntimeError> {
+        let environment = self.environment.clone();
+        let call_paren = self.call_paren.clone();
+        let frames = self.frames.len();
+        let call_depth = self.usage.call_depth;
+
+        let payload = match panic::catch_unwind(AssertUnwindSafe(|| f(self))) {
+            Ok(value) => return Ok(value),
+            Err(payload) => payload,
+        };
+        self.environment = environment;
+        self.call_paren = call_paren;
+        self.frames.truncate(frames);
+        self.usage.call_depth = call_depth;
+        match payload.downcast::<RuntimeError>() {
+            Ok(error) => Err(*error),
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    }
+
+    /// Converts a value to its printed form, calling `__str__` on instances
+    /// whose class defines it, including those inside lists and maps.
+    /// `Value`'s `Display` cannot run Lox code, so `print` and the `str`
+    /// native go through here instead.
+    pub fn stringify(&mut self, value: &Value) -> String {
+        match value {
+            Value::Instance(instance) => match instance.special_method("__str__") {
+                Some(method) => {
+                    let name = method.name().clone();
+                    self.call_special(method, &name, Vec::new()).to_string()
+                }
+                None => value.to_string(),
+            },
+            // `__str__` may change the collection, so work on a copy.
+            Value::List(list) => {
+                let items = list.borrow().clone();
+                let items: Vec<String> = items.iter().map(|item| self.stringify(item)).collect();
+                format!("[{}]", items.join(", "))
+            }
+            Value::Map(map) => {
+                let entries = map.borrow().clone();
+                let entries: Vec<String> = entries
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key, self.stringify(value)))
+                    .collect();
+                format!("{{{}}}", entries.join(", "))
+            }
+            _ => value.to_string(),
+        }
+    }
+
+    /// Calls a special method such as `__add__`, reporting a runtime error
+    /// at `token` unless it takes exactly the arguments given. A plain call
+    /// would drop extra arguments and leave missing parameters undefined.
+    pub fn call_special(&mut self, method: LoxFunction, token: &Token, arguments: Vec<Value>) -> Value {
+        if method.arity() != arguments.len() {
+            let message = format!(
+                "Special method '{}' must take {} parameter{} but takes {}.",
+                method.name().lexeme,
+                arguments.len(),
+                if arguments.len() == 1 { "" } else { "s" },
+                method.arity()
+            );
+            RuntimeError::new(token.clone(), message).raise();
+        }
+        self.call_at(token, &method, arguments)
+    }
+
+    /// Calls `function` on behalf of `token`, such as the property a getter
+    /// runs for, so errors raised at the call site are reported there.
+    pub fn call_at(&mut self, token: &Token, function: &LoxFunction, arguments: Vec<Value>) -> Value {
+        self.call_paren = Some(token.clone());
+        function.call(self, arguments)
+    }
+
+    /// Dispatches a binary operator to the left operand's special method,
+    /// returning `None` when its class does not overload the operator.
+    fn overloaded_binary(
+        &mut self,
+        instance: &Rc<LoxInstance>,
+        operator: &Token,
+        right: &Value,
+    ) -> Option<Value> {
+        let name = match operator.token_type {
+            TokenType::Plus => "__add__",
+            TokenType::Minus => "__sub__",
+            TokenType::Star => "__mul__",
+            TokenType::Slash => "__div__",
+            TokenType::EqualEqual | TokenType::BangEqual => "__eq__",
+            TokenType::Less => "__lt__",
+            TokenType::LessEqual => "__le__",
+            TokenType::Greater => "__gt__",
+            TokenType::GreaterEqual => "__ge__",
+            _ => return None,
+        };
+
+        let method = instance.special_method(name)?;
+        let result = self.call_special(method, operator, vec![right.clone()]);
+        if operator.token_type == TokenType::BangEqual {
+            Some(Value::Boolean(!Self::is_truthy(&result)))
         } else {
-            panic!("{}", RuntimeError::new(
-                operator,
-                "Operands must be numbers."
-            ));
+            Some(result)
+        }
+    }
+
+    pub fn is_truthy(value: &Value) -> bool {
+        match value {
+            Value::Nil => false,
+            Value::Boolean(b) => *b,
+            _ => true,
+        }
+    }
+
+    pub fn is_equal(left: &Value, right: &Value) -> bool {
+        if let Some(equal) = number::numbers_equal(left, right) {
+            return equal;
+        }
+        match (left, right) {
+            (Value::Nil, Value::Nil) => true,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Boolean(a), Value::Boolean(b)) => a == b,
+            (Value::Instance(a), Value::Instance(b)) => Rc::ptr_eq(a, b),
+            (Value::List(a), Value::List(b)) => Rc::ptr_eq(a, b),
+            (Value::Map(a), Value::Map(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+
+    /// Builds the name-to-function table for one kind of class member.
+    fn class_functions(
+        class: &Token,
+        declarations: &[Rc<StmtFunction>],
+        closure: &Rc<RefCell<Environment>>,
+        are_methods: bool,
+    ) -> HashMap<String, Rc<LoxFunction>> {
+        declarations
+            .iter()
+            .map(|declaration| {
+                let is_initializer = are_methods && declaration.name.lexeme == "init";
+                let function = LoxFunction::new(declaration.clone(), closure.clone(), is_initializer).in_class(&class.lexeme);
+                (declaration.name.lexeme.clone(), Rc::new(function))
+            })
+            .collect()
+    }
+
+    /// Unwraps the result of a numeric operation, raising its error as a
+    /// runtime error at the operator.
+    fn numeric(operator: &Token, result: Result<Value, String>) -> Value {
+        match result {
+            Ok(value) => value,
+            Err(message) => RuntimeError::new(operator.clone(), message).raise(),
+        }
+    }
+
+    fn compare_operands(operator: &Token, left: &Value, right: &Value) -> Option<Ordering> {
+        match number::compare(left, right) {
+            Ok(ordering) => ordering,
+            Err(message) => RuntimeError::new(operator.clone(), message).raise(),
         }
     }
 }
\ No newline at end of file
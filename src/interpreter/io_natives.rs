@@ -0,0 +1,141 @@
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::rc::Rc;
+
+use crate::interpreter::environment::Environment;
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::limits;
+use crate::interpreter::lox_callable::NativeFunction;
+use crate::interpreter::value::Value;
+
+/// A permission an embedder grants to scripts. Every I/O native checks for
+/// its capability, so a fresh interpreter can only `print`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    FileRead,  // readFile, listDir, exists
+    FileWrite, // writeFile, appendFile
+    Stdin,     // readLine, input
+}
+
+impl Capability {
+    /// Parses a command-line flag such as `--allow-read`.
+    pub fn from_flag(flag: &str) -> Option<Self> {
+        match flag {
+            "--allow-read" => Some(Capability::FileRead),
+            "--allow-write" => Some(Capability::FileWrite),
+            "--allow-stdin" => Some(Capability::Stdin),
+            _ => None,
+        }
+    }
+
+    /// Returns the name used in error messages.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Capability::FileRead => "file-read",
+            Capability::FileWrite => "file-write",
+            Capability::Stdin => "stdin",
+        }
+    }
+}
+
+/// Defines the file and stdin natives.
+pub fn define_globals(globals: &mut Environment) {
+    define(globals, "readFile", 1, |interpreter, args| {
+        interpreter.require(Capability::FileRead, "readFile");
+        let path = path_arg(interpreter, &args[0]);
+        match fs::read_to_string(path) {
+            Ok(content) => {
+                interpreter.charge(content.len());
+                Value::String(content)
+            }
+            Err(err) => interpreter.native_error(&format!("Could not read '{}': {}.", path, err)),
+        }
+    });
+
+    define(globals, "writeFile", 2, |interpreter, args| {
+        interpreter.require(Capability::FileWrite, "writeFile");
+        let path = path_arg(interpreter, &args[0]);
+        let text = interpreter.stringify(&args[1]);
+        if let Err(err) = fs::write(path, text) {
+            interpreter.native_error(&format!("Could not write '{}': {}.", path, err));
+        }
+        Value::Nil
+    });
+
+    define(globals, "appendFile", 2, |interpreter, args| {
+        interpreter.require(Capability::FileWrite, "appendFile");
+        let path = path_arg(interpreter, &args[0]);
+        let text = interpreter.stringify(&args[1]);
+        let result = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(text.as_bytes()));
+        if let Err(err) = result {
+            interpreter.native_error(&format!("Could not append to '{}': {}.", path, err));
+        }
+        Value::Nil
+    });
+
+    define(globals, "listDir", 1, |interpreter, args| {
+        interpreter.require(Capability::FileRead, "listDir");
+        let path = path_arg(interpreter, &args[0]);
+        let entries = match fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(err) => interpreter.native_error(&format!("Could not list '{}': {}.", path, err)),
+        };
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        let names = Value::List(Rc::new(RefCell::new(names.into_iter().map(Value::String).collect())));
+        interpreter.charge(limits::heap_size(&names));
+        names
+    });
+
+    define(globals, "exists", 1, |interpreter, args| {
+        interpreter.require(Capability::FileRead, "exists");
+        let path = path_arg(interpreter, &args[0]);
+        Value::Boolean(std::path::Path::new(path).exists())
+    });
+
+    define(globals, "readLine", 0, |interpreter, _| {
+        interpreter.require(Capability::Stdin, "readLine");
+        read_line(interpreter)
+    });
+
+    define(globals, "input", 1, |interpreter, args| {
+        interpreter.require(Capability::Stdin, "input");
+        let prompt = interpreter.stringify(&args[0]);
+        interpreter.write_output(&prompt);
+        read_line(interpreter)
+    });
+}
+
+fn define(globals: &mut Environment, name: &str, arity: usize, function: fn(&mut Interpreter, Vec<Value>) -> Value) {
+    globals.define(name, Value::Callable(Box::new(NativeFunction { arity, optional: 0, function })));
+}
+
+fn path_arg<'a>(interpreter: &Interpreter, value: &'a Value) -> &'a str {
+    match value {
+        Value::String(path) => path,
+        _ => interpreter.native_error("Path must be a string."),
+    }
+}
+
+/// Reads one line from stdin without its line ending, or nil at end of input.
+fn read_line(interpreter: &mut Interpreter) -> Value {
+    let mut line = String::new();
+    match io::stdin().lock().read_line(&mut line) {
+        Ok(0) => Value::Nil,
+        Ok(_) => {
+            let trimmed = line.trim_end_matches(['\n', '\r']).len();
+            line.truncate(trimmed);
+            interpreter.charge(line.len());
+            Value::String(line)
+        }
+        Err(err) => interpreter.native_error(&format!("Could not read from stdin: {}.", err)),
+    }
+}
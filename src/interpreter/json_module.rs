@@ -0,0 +1,187 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use crate::interpreter::bigint::BigInt;
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::limits;
+use crate::interpreter::native_module::NativeModule;
+use crate::interpreter::value::Value;
+
+/// Builds the `json` module. Objects parse into maps, which keep their keys
+/// sorted, so `stringify` writes keys in sorted order rather than the order
+/// they were parsed in.
+pub fn module() -> Value {
+    let mut json = NativeModule::new("json");
+    json.function("parse", 1, |interpreter, args| {
+        let text = match &args[0] {
+            Value::String(text) => text,
+            _ => interpreter.native_error("json.parse expects a string."),
+        };
+        match serde_json::from_str::<serde_json::Value>(text) {
+            Ok(value) => {
+                let value = from_json(value);
+                interpreter.charge(limits::heap_size(&value));
+                value
+            }
+            Err(err) => interpreter.native_error(&format!("Invalid JSON: {}.", err)),
+        }
+    })
+    // The indent may be left out for compact output.
+    .function_with_optional("stringify", 2, 1, |interpreter, args| {
+        let indent = match &args[1] {
+            Value::Nil => None,
+            Value::Integer(n) if (0..=16).contains(n) => Some(*n as usize),
+            _ => interpreter.native_error("Indent must be nil or an integer between 0 and 16."),
+        };
+        let json = match to_json(interpreter, &args[0], &mut Vec::new()) {
+            Ok(json) => json,
+            Err(message) => interpreter.native_error(&message),
+        };
+        let text = render(&json, indent);
+        interpreter.charge(text.len());
+        Value::String(text)
+    });
+    Value::Module(Rc::new(json))
+}
+
+/// Converts parsed JSON into Lox values: objects become maps and arrays
+/// become lists. Integers too large for `i64` become big integers.
+pub fn from_json(value: serde_json::Value) -> Value {
+    match value {
+        serde_json::Value::Null => Value::Nil,
+        serde_json::Value::Bool(b) => Value::Boolean(b),
+        serde_json::Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(i), _) => Value::Integer(i),
+            (None, Some(u)) => Value::BigInteger(BigInt::parse(&u.to_string()).expect("digits parse")),
+            _ => Value::Number(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        serde_json::Value::String(s) => Value::String(s),
+        serde_json::Value::Array(items) => {
+            let items = items.into_iter().map(from_json).collect();
+            Value::List(Rc::new(RefCell::new(items)))
+        }
+        serde_json::Value::Object(fields) => {
+            let fields: BTreeMap<String, Value> = fields
+                .into_iter()
+                .map(|(key, value)| (key, from_json(value)))
+                .collect();
+            Value::Map(Rc::new(RefCell::new(fields)))
+        }
+    }
+}
+
+/// Converts a Lox value to JSON. `visiting` holds the lists and maps on the
+/// current path so self-containing values are reported instead of looping.
+pub fn to_json(
+    interpreter: &mut Interpreter,
+    value: &Value,
+    visiting: &mut Vec<*const ()>,
+) -> Result<serde_json::Value, String> {
+    match value {
+        Value::Nil => Ok(serde_json::Value::Null),
+        Value::Boolean(b) => Ok(serde_json::Value::Bool(*b)),
+        Value::Integer(i) => Ok(serde_json::Value::from(*i)),
+        Value::BigInteger(b) => b
+            .to_string()
+            .parse::<u64>()
+            .map(serde_json::Value::from)
+            .map_err(|_| "Cannot convert an integer this large to JSON.".to_string()),
+        Value::Number(n) => serde_json::Number::from_f64(*n)
+            .map(serde_json::Value::Number)
+            .ok_or_else(|| "Cannot convert NaN or infinity to JSON.".to_string()),
+        Value::String(s) => Ok(serde_json::Value::String(s.clone())),
+        Value::List(list) => {
+            let id = Rc::as_ptr(list) as *const ();
+            enter(visiting, id)?;
+            let items = list.borrow().clone();
+            let json = items
+                .iter()
+                .map(|item| to_json(interpreter, item, visiting))
+                .collect::<Result<Vec<_>, _>>()?;
+            visiting.pop();
+            Ok(serde_json::Value::Array(json))
+        }
+        Value::Map(map) => {
+            let id = Rc::as_ptr(map) as *const ();
+            enter(visiting, id)?;
+            let fields = map.borrow().clone();
+            let mut json = serde_json::Map::new();
+            for (key, field) in &fields {
+                json.insert(key.clone(), to_json(interpreter, field, visiting)?);
+            }
+            visiting.pop();
+            Ok(serde_json::Value::Object(json))
+        }
+        Value::Instance(instance) => {
+            let id = Rc::as_ptr(instance) as *const ();
+            match instance.special_method("toJson") {
+                Some(method) => {
+                    enter(visiting, id)?;
+                    let name = method.name().clone();
+                    let converted = interpreter.call_special(method, &name, Vec::new());
+                    let json = to_json(interpreter, &converted, visiting)?;
+                    visiting.pop();
+                    Ok(json)
+                }
+                None => Err(format!("Cannot convert {} to JSON without a toJson method.", instance)),
+            }
+        }
+        Value::Callable(callable) => Err(format!("Cannot convert {} to JSON.", callable)),
+        Value::Module(module) => Err(format!("Cannot convert {} to JSON.", module)),
+    }
+}
+
+fn enter(visiting: &mut Vec<*const ()>, id: *const ()) -> Result<(), String> {
+    if visiting.contains(&id) {
+        return Err("Cannot convert a value that contains itself to JSON.".to_string());
+    }
+    visiting.push(id);
+    Ok(())
+}
+
+/// Renders JSON compactly, or pretty-printed with `indent` spaces.
+fn render(json: &serde_json::Value, indent: Option<usize>) -> String {
+    match indent {
+        None => json.to_string(),
+        Some(width) => {
+            let mut output = String::new();
+            render_pretty(&mut output, json, width, 0);
+            output
+        }
+    }
+}
+
+/// Writes `json` with one array item or object field per line, nested
+/// `depth` levels deep. Empty arrays and objects stay on one line.
+fn render_pretty(output: &mut String, json: &serde_json::Value, width: usize, depth: usize) {
+    let newline = |output: &mut String, depth: usize| {
+        output.push('\n');
+        output.push_str(&" ".repeat(width * depth));
+    };
+    match json {
+        serde_json::Value::Array(items) if !items.is_empty() => {
+            output.push('[');
+            for (i, item) in items.iter().enumerate() {
+                output.push_str(if i > 0 { "," } else { "" });
+                newline(output, depth + 1);
+                render_pretty(output, item, width, depth + 1);
+            }
+            newline(output, depth);
+            output.push(']');
+        }
+        serde_json::Value::Object(fields) if !fields.is_empty() => {
+            output.push('{');
+            for (i, (key, value)) in fields.iter().enumerate() {
+                output.push_str(if i > 0 { "," } else { "" });
+                newline(output, depth + 1);
+                output.push_str(&serde_json::Value::from(key.as_str()).to_string());
+                output.push_str(": ");
+                render_pretty(output, value, width, depth + 1);
+            }
+            newline(output, depth);
+            output.push('}');
+        }
+        other => output.push_str(&other.to_string()),
+    }
+}
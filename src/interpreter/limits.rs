@@ -0,0 +1,100 @@
+use std::mem;
+use std::time::{Duration, Instant};
+
+use crate::interpreter::value::Value;
+
+/// Resource limits for running untrusted scripts. `None` disables a limit.
+/// The allocation budget bounds the bytes a script allocates over a whole
+/// run, charged as values are created, rather than what is live at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub max_call_depth: Option<usize>,
+    pub max_statements: Option<u64>,
+    pub max_alloc_bytes: Option<usize>,
+    pub timeout: Option<Duration>,
+}
+
+impl Default for Limits {
+    /// Only the call depth is capped by default, low enough that deep
+    /// recursion reports `Stack overflow.` before the Rust stack runs out.
+    fn default() -> Self {
+        Self {
+            max_call_depth: Some(256),
+            max_statements: None,
+            max_alloc_bytes: None,
+            timeout: None,
+        }
+    }
+}
+
+/// Usage counted against `Limits` during one call to `interpret`.
+#[derive(Debug)]
+pub struct Usage {
+    pub call_depth: usize,
+    statements: u64,
+    started: Instant,
+    allocated: usize, // Bytes charged when strings, lists, maps and instances are made; never credited back
+}
+
+impl Usage {
+    /// Starts counting from zero, as of now.
+    pub fn start() -> Self {
+        Self {
+            call_depth: 0,
+            statements: 0,
+            started: Instant::now(),
+            allocated: 0,
+        }
+    }
+
+    /// Counts one executed statement and returns the message of the first
+    /// limit now exceeded, if any.
+    pub fn step(&mut self, limits: &Limits) -> Option<&'static str> {
+        self.statements += 1;
+        if limits.max_statements.is_some_and(|max| self.statements > max) {
+            return Some("Statement budget exceeded.");
+        }
+        self.check(limits)
+    }
+
+    /// Checks the limits that grow without statements running, for loop
+    /// iterations and calls.
+    pub fn check(&self, limits: &Limits) -> Option<&'static str> {
+        if limits.timeout.is_some_and(|timeout| self.started.elapsed() > timeout) {
+            return Some("Execution timed out.");
+        }
+        None
+    }
+
+    /// Charges `bytes` the script is about to allocate, returning an error
+    /// message instead if that would exceed the allocation budget.
+    pub fn charge(&mut self, bytes: usize, limits: &Limits) -> Option<&'static str> {
+        let total = self.allocated.saturating_add(bytes);
+        if limits.max_alloc_bytes.is_some_and(|max| total > max) {
+            return Some("Allocation budget exceeded.");
+        }
+        self.allocated = total;
+        None
+    }
+}
+
+/// Estimates the heap bytes held by a value:
+/// string contents, big integer digits and list and map slots, counted
+/// recursively.
+pub fn heap_size(value: &Value) -> usize {
+    match value {
+        Value::String(s) => s.len(),
+        Value::BigInteger(b) => b.heap_size(),
+        Value::List(items) => items
+            .borrow()
+            .iter()
+            .map(|item| mem::size_of::<Value>() + heap_size(item))
+            .sum(),
+        Value::Map(fields) => fields
+            .borrow()
+            .iter()
+            .map(|(key, value)| key.len() + mem::size_of::<Value>() + heap_size(value))
+            .sum(),
+        _ => 0,
+    }
+}
@@ -0,0 +1,359 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+
+use crate::interpreter::expr::Expr;
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::parser::Parser;
+use crate::interpreter::resolver::Resolver;
+use crate::interpreter::scanner::{Comment, Scanner};
+use crate::interpreter::stmt::Stmt;
+use crate::interpreter::token::Token;
+use crate::interpreter::token_type::TokenType;
+use crate::interpreter::value::{Object, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Every lint rule: id, severity and what it reports.
+pub const RULES: &[(&str, Severity, &str)] = &[
+    ("unused-variable", Severity::Warning, "a local variable, function or class is never read"),
+    ("unused-parameter", Severity::Warning, "a parameter is never read"),
+    ("shadowed-name", Severity::Warning, "a declaration hides one in an enclosing scope"),
+    ("undeclared-global", Severity::Error, "an assignment targets a global that is never declared"),
+    ("wrong-arity", Severity::Error, "a call to a known function passes the wrong number of arguments"),
+    ("constant-comparison", Severity::Warning, "an equality test whose result is known statically"),
+    ("unreachable-code", Severity::Warning, "a statement follows a return in the same block"),
+    ("empty-block", Severity::Warning, "a block has no statements"),
+    ("resolve-error", Severity::Error, "a name is used where the language forbids it"),
+];
+
+/// A problem found by the linter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}[{}]: {}", self.line, self.severity, self.rule, self.message)
+    }
+}
+
+/// A top-level name, checked once the whole program has been resolved
+/// because globals may be used before they are declared.
+#[derive(Debug, Default)]
+struct Global {
+    declarations: usize,
+    arity: Option<usize>,
+    assignments: Vec<Token>,
+    calls: Vec<(Token, usize)>,
+}
+
+/// Collects diagnostics while the `Resolver` walks the program. The
+/// resolver reports scope events; rules that need no scopes run here.
+#[derive(Debug)]
+pub struct Linter {
+    diagnostics: Vec<Diagnostic>,
+    globals: HashMap<String, Global>,
+    builtins: HashMap<String, Option<(usize, usize)>>, // Natives and modules, with arity and optional count if callable
+}
+
+impl Linter {
+    /// Creates a linter that knows the globals `interpreter` predefines.
+    pub fn new(interpreter: &Interpreter) -> Self {
+        let builtins = interpreter
+            .global_bindings()
+            .into_iter()
+            .map(|(name, value)| {
+                let arity = match value {
+                    Value::Callable(callable) => Some((callable.arity(), callable.optional())),
+                    _ => None,
+                };
+                (name, arity)
+            })
+            .collect();
+
+        Self {
+            diagnostics: Vec::new(),
+            globals: HashMap::new(),
+            builtins,
+        }
+    }
+
+    pub fn report(&mut self, rule: &'static str, line: usize, message: String) {
+        let severity = RULES
+            .iter()
+            .find(|(id, _, _)| *id == rule)
+            .map_or(Severity::Warning, |(_, severity, _)| *severity);
+        self.diagnostics.push(Diagnostic { rule, severity, line, message });
+    }
+
+    /// Records a top-level `var`, `fun` or `class`.
+    pub fn declare_global(&mut self, name: &Token, arity: Option<usize>) {
+        let global = self.globals.entry(name.lexeme.clone()).or_default();
+        global.declarations += 1;
+        global.arity = arity;
+    }
+
+    /// Returns true if `name` is a global the program has declared so far.
+    pub fn is_global(&self, name: &str) -> bool {
+        self.globals.get(name).is_some_and(|global| global.declarations > 0)
+    }
+
+    /// Records an assignment to a name that is not a local.
+    pub fn assign_global(&mut self, name: &Token) {
+        self.globals.entry(name.lexeme.clone()).or_default().assignments.push(name.clone());
+    }
+
+    /// Records a call through a name that is not a local.
+    pub fn call_global(&mut self, name: Token, arguments: usize) {
+        self.globals
+            .entry(name.lexeme.clone())
+            .or_default()
+            .calls
+            .push((name, arguments));
+    }
+
+    /// Reports a call passing `arguments` to a function taking `arity`,
+    /// the last `optional` of which may be left out.
+    pub fn check_arity(&mut self, name: &Token, arity: usize, optional: usize, arguments: usize) {
+        if arguments > arity || arguments + optional < arity {
+            let expected = match optional {
+                0 => arity.to_string(),
+                _ => format!("{} to {}", arity - optional, arity),
+            };
+            self.report(
+                "wrong-arity",
+                name.line,
+                format!("'{}' expects {} arguments but is called with {}.", name.lexeme, expected, arguments),
+            );
+        }
+    }
+
+    /// Reports `==` or `!=` whose outcome is fixed, such as a literal or a
+    /// function compared with `nil`.
+    pub fn check_comparison(&mut self, left: &Expr, operator: &Token, right: &Expr) {
+        let equal = match operator.token_type {
+            TokenType::EqualEqual => true,
+            TokenType::BangEqual => false,
+            _ => return,
+        };
+
+        let outcome = match (static_kind(left), static_kind(right)) {
+            (Some(StaticKind::Nil), Some(StaticKind::Nil)) => equal,
+            (Some(StaticKind::Nil), Some(StaticKind::NonNil))
+            | (Some(StaticKind::NonNil), Some(StaticKind::Nil)) => !equal,
+            _ => return,
+        };
+        self.report(
+            "constant-comparison",
+            operator.line,
+            format!("This comparison is always {}.", outcome),
+        );
+    }
+
+    /// Reports statements that follow a `return` in the same list.
+    pub fn check_unreachable(&mut self, statements: &[Stmt]) {
+        let returned = statements.iter().position(|stmt| matches!(stmt, Stmt::Return(_)));
+        if let Some(stmt) = returned.and_then(|index| statements.get(index + 1)) {
+            self.report("unreachable-code", stmt.line(), "Unreachable code after 'return'.".to_string());
+        }
+    }
+
+    /// Reports empty statement blocks: `{}` standing alone or as the body
+    /// of `if`, `else`, `while` or `for`. Empty function and class bodies
+    /// are common stubs and are allowed. Blocks carry no line, so this
+    /// works on tokens.
+    pub fn check_empty_blocks(&mut self, tokens: &[Token]) {
+        for (i, pair) in tokens.windows(2).enumerate() {
+            if pair[0].token_type != TokenType::LeftBrace || pair[1].token_type != TokenType::RightBrace {
+                continue;
+            }
+
+            let is_statement = match i.checked_sub(1).map(|before| &tokens[before]) {
+                None => true,
+                Some(before) => match before.token_type {
+                    TokenType::Semicolon | TokenType::LeftBrace | TokenType::RightBrace | TokenType::Else => true,
+                    TokenType::RightParen => matches!(
+                        keyword_before_parens(tokens, i - 1),
+                        Some(TokenType::If | TokenType::While | TokenType::For)
+                    ),
+                    _ => false,
+                },
+            };
+            if is_statement {
+                self.report("empty-block", pair[0].line, "Empty block.".to_string());
+            }
+        }
+    }
+
+    /// Runs the whole-program checks and returns every diagnostic that is
+    /// not suppressed by a `// lox-allow(rule)` comment, ordered by line.
+    pub fn finish(mut self, comments: &[Comment]) -> Vec<Diagnostic> {
+        let globals = std::mem::take(&mut self.globals);
+        for (name, global) in &globals {
+            let builtin = self.builtins.get(name);
+            if global.declarations == 0 && builtin.is_none() {
+                for assignment in &global.assignments {
+                    self.report(
+                        "undeclared-global",
+                        assignment.line,
+                        format!("Assignment to undeclared variable '{}'.", name),
+                    );
+                }
+                continue;
+            }
+
+            // Only names bound exactly once are statically known.
+            let arity = match (global.declarations, builtin) {
+                (1, None) => global.arity.map(|arity| (arity, 0)),
+                (0, Some(arity)) => *arity,
+                _ => None,
+            };
+            if let (Some((arity, optional)), true) = (arity, global.assignments.is_empty()) {
+                for (call, arguments) in &global.calls {
+                    self.check_arity(call, arity, optional, *arguments);
+                }
+            }
+        }
+
+        let allowed = suppressions(comments);
+        let mut diagnostics: Vec<Diagnostic> = self
+            .diagnostics
+            .into_iter()
+            .filter(|diagnostic| !allowed.contains(&(diagnostic.line, diagnostic.rule.to_string())))
+            .collect();
+        diagnostics.sort_by(|a, b| (a.line, &a.message).cmp(&(b.line, &b.message)));
+        diagnostics
+    }
+}
+
+/// Returns the type of the token before the `(` matching the `)` at
+/// `close`.
+fn keyword_before_parens(tokens: &[Token], close: usize) -> Option<TokenType> {
+    let mut depth = 0;
+    for i in (0..=close).rev() {
+        match tokens[i].token_type {
+            TokenType::RightParen => depth += 1,
+            TokenType::LeftParen => {
+                depth -= 1;
+                if depth == 0 {
+                    return i.checked_sub(1).map(|before| tokens[before].token_type.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+enum StaticKind {
+    Nil,
+    NonNil,
+}
+
+/// Whether an expression is certainly nil or certainly not, if known.
+fn static_kind(expr: &Expr) -> Option<StaticKind> {
+    match expr {
+        Expr::Literal { value: None, .. } | Expr::Literal { value: Some(Object::Nil), .. } => Some(StaticKind::Nil),
+        Expr::Literal { .. } | Expr::Lambda { .. } | Expr::This { .. } => Some(StaticKind::NonNil),
+        Expr::Grouping { expression } => static_kind(expression),
+        _ => None,
+    }
+}
+
+/// Collects `(line, rule)` pairs silenced by `// lox-allow(rule, ...)`. A
+/// trailing comment covers its own line; a comment on its own line covers
+/// the next one.
+fn suppressions(comments: &[Comment]) -> HashSet<(usize, String)> {
+    let mut allowed = HashSet::new();
+    for comment in comments {
+        let rules = comment
+            .text
+            .split_once("lox-allow(")
+            .and_then(|(_, rest)| rest.split_once(')'))
+            .map(|(rules, _)| rules);
+        let line = if comment.trailing { comment.line } else { comment.line + 1 };
+        for rule in rules.into_iter().flat_map(|rules| rules.split(',')) {
+            allowed.insert((line, rule.trim().to_string()));
+        }
+    }
+    allowed
+}
+
+/// Lints a source file, or returns the first syntax error.
+pub fn lint_source(source: &str) -> Result<Vec<Diagnostic>, String> {
+    let mut scanner = Scanner::new(source.to_string());
+    let tokens = scanner.scan_tokens();
+    if let Some(error) = scanner.errors().first() {
+        return Err(format!("[line {}] {}", error.line, error.message));
+    }
+    let statements = Parser::new(tokens.clone())
+        .parse()
+        .map_err(|error| format!("[line {}] {}", error.token.line, error.message))?;
+
+    let mut interpreter = Interpreter::new();
+    let mut linter = Linter::new(&interpreter);
+    linter.check_empty_blocks(&tokens);
+    let mut resolver = Resolver::with_linter(&mut interpreter, linter);
+    resolver.resolve(&statements);
+    let errors = resolver.errors().to_vec();
+    let mut linter = resolver.into_linter().expect("resolver was created with a linter");
+    for (token, message) in errors {
+        linter.report("resolve-error", token.line, message);
+    }
+    Ok(linter.finish(scanner.comments()))
+}
+
+/// Runs `lox lint [files...]`, returning 1 if any error-level diagnostic
+/// was reported and 65 on a syntax error.
+pub fn command(args: &[String]) -> i32 {
+    if args.is_empty() {
+        eprintln!("Usage: lox lint <files...>");
+        return 64;
+    }
+
+    let mut status = 0;
+    for path in args {
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(err) => {
+                eprintln!("{}: {}", path, err);
+                status = 74;
+                continue;
+            }
+        };
+
+        match lint_source(&source) {
+            Ok(diagnostics) => {
+                for diagnostic in &diagnostics {
+                    println!("{}:{}", path, diagnostic);
+                }
+                if diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error) {
+                    status = status.max(1);
+                }
+            }
+            Err(message) => {
+                eprintln!("{}: {}", path, message);
+                status = status.max(65);
+            }
+        }
+    }
+    status
+}
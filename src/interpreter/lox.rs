@@ -1,18 +1,44 @@
 use std::fs;
-use std::io::{self, Write, BufRead};
+use std::panic::{self, AssertUnwindSafe};
 use std::process;
+use std::time::Duration;
 
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
 
+
+use crate::interpreter::ast_json;
+use crate::interpreter::ast_printer::AstPrinter;
+use crate::interpreter::coverage::Coverage;
+use crate::interpreter::dap;
+use crate::interpreter::debugger::Debugger;
+use crate::interpreter::doc;
+use crate::interpreter::formatter;
 use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::io_natives::Capability;
+use crate::interpreter::limits::Limits;
+use crate::interpreter::lint;
+use crate::interpreter::lsp;
+use crate::interpreter::optimizer::Optimizer;
+use crate::interpreter::repl::{self, Command};
 use crate::interpreter::runtime_error::RuntimeError;
 use crate::interpreter::resolver::Resolver;
 use crate::interpreter::parser::Parser;
 use crate::interpreter::scanner::Scanner;
+use crate::interpreter::stmt::Stmt;
+use crate::interpreter::test_runner;
+use crate::interpreter::token::Token;
+use crate::interpreter::token_type::TokenType;
 
 pub struct Lox {
     had_error: bool,
     had_runtime_error: bool,
     interpreter: Interpreter,
+    profile: Option<String>, // Where `--profile` writes folded stacks
+    coverage: Option<String>, // Where `--coverage` writes the LCOV report
+    optimize: bool,
+    dump_optimized: bool, // Print the syntax tree before and after optimizing
+    debugging: bool,      // Under `lox debug`, where an interrupted script was quit
 }
 
 impl Lox {
@@ -22,17 +48,142 @@ impl Lox {
             had_error: false,
             had_runtime_error: false,
             interpreter: Interpreter::new(),
+            profile: None,
+            coverage: None,
+            optimize: false,
+            dump_optimized: false,
+            debugging: false,
         }
     }
 
     /// Entry point for the Lox interpreter.
     pub fn main() {
-        let args: Vec<String> = std::env::args().collect();
+        let mut args: Vec<String> = std::env::args().collect();
+
+        // Tooling subcommands take the rest of the arguments.
+        match args.get(1).map(String::as_str) {
+            Some("ast") => process::exit(ast_json::command(&args[2..])),
+            Some("dap") => process::exit(dap::command(&args[2..])),
+            Some("doc") => process::exit(doc::command(&args[2..])),
+            Some("fmt") => process::exit(formatter::command(&args[2..])),
+            Some("lint") => process::exit(lint::command(&args[2..])),
+            Some("lsp") => process::exit(lsp::command(&args[2..])),
+            Some("test") => process::exit(test_runner::command(&args[2..])),
+            _ => {}
+        }
+
+        // `lox debug` runs a script under the step debugger.
+        let debug = args.get(1).is_some_and(|arg| arg == "debug");
+        if debug {
+            args.remove(1);
+        }
+
         let mut lox = Lox::new();
 
-        if args.len() > 2 {
-            eprintln!("Usage: lox [script]");
+        // `--allow-read`, `--allow-write` and `--allow-stdin` grant I/O natives.
+        args.retain(|arg| match Capability::from_flag(arg) {
+            Some(capability) => {
+                lox.interpreter.allow(capability);
+                false
+            }
+            None => true,
+        });
+
+        // `--seed N` fixes the `random` module's output.
+        if let Some(position) = args.iter().position(|arg| arg == "--seed") {
+            match args.get(position + 1).and_then(|seed| seed.parse::<u64>().ok()) {
+                Some(seed) => lox.interpreter.set_seed(seed),
+                None => {
+                    eprintln!("--seed expects a non-negative integer.");
+                    process::exit(64);
+                }
+            }
+            args.drain(position..position + 2);
+        }
+
+        // `--max-call-depth N`, `--max-statements N`, `--max-alloc BYTES` and
+        // `--timeout MS` limit what a script may use; `0` lifts a limit.
+        let mut limits = Limits::default();
+        if let Some(depth) = Self::limit_flag(&mut args, "--max-call-depth") {
+            limits.max_call_depth = (depth > 0).then_some(depth as usize);
+        }
+        if let Some(statements) = Self::limit_flag(&mut args, "--max-statements") {
+            limits.max_statements = (statements > 0).then_some(statements);
+        }
+        if let Some(bytes) = Self::limit_flag(&mut args, "--max-alloc") {
+            limits.max_alloc_bytes = (bytes > 0).then_some(bytes as usize);
+        }
+        if let Some(millis) = Self::limit_flag(&mut args, "--timeout") {
+            limits.timeout = (millis > 0).then_some(Duration::from_millis(millis));
+        }
+        lox.interpreter.set_limits(limits);
+
+        // `--profile FILE` reports where the script spent its time.
+        if let Some(position) = args.iter().position(|arg| arg == "--profile") {
+            match args.get(position + 1) {
+                Some(path) => lox.profile = Some(path.clone()),
+                None => {
+                    eprintln!("--profile expects a file for the folded stacks.");
+                    process::exit(64);
+                }
+            }
+            lox.interpreter.start_profiling();
+            args.drain(position..position + 2);
+        }
+
+        // `--coverage FILE` writes which lines and branches ran as LCOV.
+        if let Some(position) = args.iter().position(|arg| arg == "--coverage") {
+            match args.get(position + 1) {
+                Some(path) => lox.coverage = Some(path.clone()),
+                None => {
+                    eprintln!("--coverage expects a file for the LCOV report.");
+                    process::exit(64);
+                }
+            }
+            args.drain(position..position + 2);
+        }
+
+        // `--optimize` simplifies the program before running it.
+        // `--dump-optimized` does too, printing the tree before and after.
+        if let Some(position) = args.iter().position(|arg| arg == "--dump-optimized") {
+            lox.optimize = true;
+            lox.dump_optimized = true;
+            args.remove(position);
+        }
+        if let Some(position) = args.iter().position(|arg| arg == "--optimize") {
+            lox.optimize = true;
+            args.remove(position);
+        }
+
+        // `--json-ast` runs a program saved by `lox ast --json`.
+        let json_ast = match args.iter().position(|arg| arg == "--json-ast") {
+            Some(position) => {
+                args.remove(position);
+                true
+            }
+            None => false,
+        };
+
+        if args.len() > 2 || ((json_ast || debug || lox.profile.is_some() || lox.coverage.is_some()) && args.len() != 2) {
+            eprintln!("Usage: lox [--allow-read] [--allow-write] [--allow-stdin] [--seed N] [script]");
+            eprintln!("       lox [flags] [--max-call-depth N] [--max-statements N] [--max-alloc BYTES] [--timeout MS] [script]");
+            eprintln!("       lox [flags] --profile <stacks.folded> <script>");
+            eprintln!("       lox [flags] --coverage <report.lcov> <script>");
+            eprintln!("       lox [flags] --optimize | --dump-optimized [script]");
+            eprintln!("       lox [flags] --json-ast <program.json>");
+            eprintln!("       lox debug [flags] <script>");
+            eprintln!("       lox ast [--json] <file>");
+            eprintln!("       lox fmt [--check] [files...]");
+            eprintln!("       lox lint <files...>");
+            eprintln!("       lox lsp");
+            eprintln!("       lox dap");
+            eprintln!("       lox test [--tap | --junit] <files...>");
+            eprintln!("       lox doc [--html] [--out DIR] <files...>");
             process::exit(64);
+        } else if debug {
+            lox.debug_file(&args[1]);
+        } else if json_ast {
+            lox.run_ast_file(&args[1]);
         } else if args.len() == 2 {
             lox.run_file(&args[1]);
         } else {
@@ -40,6 +191,22 @@ impl Lox {
         }
     }
 
+    /// Removes `flag` and its value from `args`, returning the value.
+    /// Exits with a usage error if the value is not a non-negative integer.
+    fn limit_flag(args: &mut Vec<String>, flag: &str) -> Option<u64> {
+        let position = args.iter().position(|arg| arg == flag)?;
+        match args.get(position + 1).and_then(|value| value.parse::<u64>().ok()) {
+            Some(value) => {
+                args.drain(position..position + 2);
+                Some(value)
+            }
+            None => {
+                eprintln!("{} expects a non-negative integer.", flag);
+                process::exit(64);
+            }
+        }
+    }
+
     /// Runs a Lox script from a file.
     pub fn run_file(&mut self, path: &str) {
         let content = match fs::read_to_string(path) {
@@ -50,6 +217,68 @@ impl Lox {
             }
         };
         self.run(&content);
+        self.write_profile();
+        self.write_coverage(path);
+
+        if self.had_error {
+            process::exit(65);
+        }
+        if self.had_runtime_error {
+            process::exit(70);
+        }
+    }
+
+    /// Prints the profile to stderr and writes its folded stacks, if
+    /// `--profile` was given.
+    fn write_profile(&mut self) {
+        let (path, profiler) = match (&self.profile, self.interpreter.take_profiler()) {
+            (Some(path), Some(profiler)) => (path, profiler),
+            _ => return,
+        };
+        eprint!("{}", profiler.report());
+        if let Err(err) = fs::write(path, profiler.folded()) {
+            eprintln!("{}: {}", path, err);
+        }
+    }
+
+    /// Writes the LCOV report for the script at `path`, if `--coverage`
+    /// was given.
+    fn write_coverage(&mut self, path: &str) {
+        let (report, coverage) = match (&self.coverage, self.interpreter.take_coverage()) {
+            (Some(report), Some(coverage)) => (report, coverage),
+            _ => return,
+        };
+        if let Err(err) = fs::write(report, coverage.lcov(path)) {
+            eprintln!("{}: {}", report, err);
+        }
+    }
+
+    /// Runs a Lox script under the step debugger, which stops before its
+    /// first statement.
+    pub fn debug_file(&mut self, path: &str) {
+        if let Ok(source) = fs::read_to_string(path) {
+            self.interpreter.set_hook(Box::new(Debugger::new(&source)));
+            self.debugging = true;
+        }
+        self.run_file(path); // Reports a read error
+    }
+
+    /// Runs a program serialized as JSON by `lox ast --json`.
+    pub fn run_ast_file(&mut self, path: &str) {
+        let statements = match fs::read_to_string(path) {
+            Ok(content) => ast_json::from_json(&content),
+            Err(err) => {
+                eprintln!("Failed to read file: {}", err);
+                process::exit(74);
+            }
+        };
+        match statements {
+            Ok(statements) => self.execute(statements),
+            Err(message) => {
+                eprintln!("{}", message);
+                process::exit(65);
+            }
+        }
 
         if self.had_error {
             process::exit(65);
@@ -59,71 +288,211 @@ impl Lox {
         }
     }
 
-    /// Runs the Lox REPL (interactive prompt).
+    /// Runs the Lox REPL (interactive prompt). Input continues over several
+    /// lines while brackets are open, bare expressions echo their value, and
+    /// history persists in `~/.lox_history`. Ctrl-C interrupts the input
+    /// being run and returns to the prompt.
     pub fn run_prompt(&mut self) {
-        let stdin = io::stdin();
-        let mut stdout = io::stdout();
+        let cancel = self.interpreter.cancel_handle();
+        let handler = cancel.clone();
+        if let Err(err) = ctrlc::set_handler(move || handler.cancel()) {
+            eprintln!("Failed to handle Ctrl-C: {}", err);
+        }
+
+        let mut editor = match DefaultEditor::new() {
+            Ok(editor) => editor,
+            Err(err) => {
+                eprintln!("Failed to start the REPL: {}", err);
+                process::exit(74);
+            }
+        };
+        let history = repl::history_path();
+        if let Some(path) = &history {
+            let _ = editor.load_history(path); // Missing on first run
+        }
 
+        let mut input = String::new();
         loop {
-            print!("> ");
-            stdout.flush().unwrap();
+            let prompt = if input.is_empty() { "> " } else { "... " };
+            let line = match editor.readline(prompt) {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => {
+                    input.clear(); // Ctrl-C abandons the current entry
+                    continue;
+                }
+                Err(ReadlineError::Eof) => break,
+                Err(err) => {
+                    eprintln!("Failed to read input: {}", err);
+                    break;
+                }
+            };
+            // A Ctrl-C pressed at the prompt must not stop what runs next.
+            cancel.reset();
+
+            if input.is_empty() && line.trim_start().starts_with(':') {
+                let _ = editor.add_history_entry(line.as_str());
+                match Command::parse(&line) {
+                    Ok(Command::Quit) => break,
+                    Ok(command) => self.run_command(command),
+                    Err(message) => eprintln!("{}", message),
+                }
+                continue;
+            }
 
-            let mut input = String::new();
-            if stdin.lock().read_line(&mut input).unwrap() == 0 {
-                break;
+            input.push_str(&line);
+            input.push('\n');
+            if repl::is_incomplete(&input) {
+                continue;
             }
 
-            self.run(&input);
+            let _ = editor.add_history_entry(input.trim_end());
+            let source = repl::complete_statement(&input);
+            input.clear();
+
+            // `execute` reports runtime errors itself; anything else that
+            // unwinds is a bug, which the default panic hook prints before
+            // the session carries on.
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+                if let Some(statements) = self.parse(&source) {
+                    self.execute(repl::echo_expression(statements));
+                }
+            }));
             self.had_error = false; // Reset the error state for the next input
+            self.had_runtime_error = false;
+        }
+
+        if let Some(path) = &history {
+            if let Err(err) = editor.save_history(path) {
+                eprintln!("Failed to save history: {}", err);
+            }
+        }
+    }
+
+    /// Runs a REPL meta-command.
+    fn run_command(&mut self, command: Command) {
+        match command {
+            Command::Load(path) => match fs::read_to_string(&path) {
+                Ok(content) => {
+                    let _ = panic::catch_unwind(AssertUnwindSafe(|| self.run(&content)));
+                }
+                Err(err) => eprintln!("Failed to read file: {}", err),
+            },
+            Command::Ast(source) => {
+                if let Some(statements) = self.parse(&repl::complete_statement(&source)) {
+                    let mut printer = AstPrinter::new();
+                    for statement in &statements {
+                        println!("{}", printer.print_stmt(statement));
+                    }
+                }
+            }
+            Command::Env => {
+                for (name, value) in self.interpreter.global_bindings() {
+                    println!("{} = {}", name, value);
+                }
+            }
+            Command::Reset => self.interpreter.reset(),
+            Command::Help => println!("{}", repl::HELP),
+            Command::Quit => {}
         }
+        self.had_error = false;
+        self.had_runtime_error = false;
     }
 
     /// Executes the given Lox source code.
     pub fn run(&mut self, source: &str) {
+        if let Some(statements) = self.parse(source) {
+            self.execute(statements);
+        }
+    }
+
+    /// Scans and parses source code, returning `None` on a syntax error.
+    fn parse(&mut self, source: &str) -> Option<Vec<Stmt>> {
         // Tokenize the source code
         let mut scanner = Scanner::new(source.to_string());
-        let tokens = match scanner.scan_tokens() {
-            Ok(tokens) => tokens,
-            Err(err) => {
-                eprintln!("Error scanning tokens: {}", err);
-                return;
+        let tokens = scanner.scan_tokens();
+        if !scanner.errors().is_empty() {
+            for error in scanner.errors() {
+                self.report(error.line, "", &error.message);
             }
-        };
+            return None;
+        }
 
         // Parse the tokens into statements
         let mut parser = Parser::new(tokens);
-        let statements = match parser.parse() {
-            Ok(statements) => statements,
-            Err(_) => {
-                // Errors are already reported in the parser
-                return;
+        match parser.parse() {
+            Ok(statements) => Some(statements),
+            Err(error) => {
+                self.error_at(&error.token, &error.message);
+                None
             }
-        };
-
-        if self.had_error {
-            return;
         }
+    }
 
+    /// Resolves and interprets parsed statements.
+    fn execute(&mut self, statements: Vec<Stmt>) {
         // Resolve the statements
         let mut resolver = Resolver::new(&mut self.interpreter);
-        if let Err(err) = resolver.resolve(&statements) {
-            eprintln!("Error resolving statements: {}", err);
-            return;
+        resolver.resolve(&statements);
+        let errors = resolver.errors().to_vec();
+        for (token, message) in &errors {
+            self.error_at(token, message);
         }
-
         if self.had_error {
             return;
         }
 
+        let statements = if self.optimize {
+            self.run_optimizer(statements)
+        } else {
+            statements
+        };
+
+        if self.coverage.is_some() {
+            self.interpreter.set_coverage(Coverage::new(&statements));
+        }
+
         // Interpret the statements
-        if let Err(runtime_error) = self.interpreter.interpret(&statements) {
-            self.runtime_error(runtime_error);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| self.interpreter.interpret(&statements)));
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(_)) if self.debugging => {} // The user quit
+            Ok(Err(interrupted)) => {
+                eprintln!("{}", interrupted);
+                self.had_runtime_error = true;
+            }
+            Err(payload) => match payload.downcast::<RuntimeError>() {
+                Ok(error) => self.runtime_error(*error),
+                Err(payload) => panic::resume_unwind(payload),
+            },
         }
     }
 
-    /// Reports a compile-time error.
-    pub fn error(&mut self, line: usize, message: &str) {
-        self.report(line, "", message);
+    /// Runs the optimizer over resolved statements, printing the syntax
+    /// tree before and after to stderr if `--dump-optimized` was given.
+    fn run_optimizer(&mut self, statements: Vec<Stmt>) -> Vec<Stmt> {
+        let before = self.dump_optimized.then(|| Self::dump(&statements));
+        let optimized = Optimizer::new(&mut self.interpreter).optimize(statements);
+        if let Some(before) = before {
+            eprint!("-- before --\n{}-- after --\n{}", before, Self::dump(&optimized));
+        }
+        optimized
+    }
+
+    fn dump(statements: &[Stmt]) -> String {
+        let mut printer = AstPrinter::new();
+        statements
+            .iter()
+            .map(|statement| format!("{}\n", printer.print_stmt(statement)))
+            .collect()
+    }
+
+    /// Reports a compile-time error at a token.
+    fn error_at(&mut self, token: &Token, message: &str) {
+        if token.token_type == TokenType::Eof {
+            self.report(token.line, " at end", message);
+        } else {
+            self.report(token.line, &format!(" at '{}'", token.lexeme), message);
+        }
     }
 
     /// Helper method to format and display error messages.
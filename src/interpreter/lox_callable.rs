@@ -1,5 +1,6 @@
 use crate::interpreter::value::Value;
 use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::lox_class::LoxClass;
 use std::fmt;
 
 /// Represents a callable function or class in the Lox language.
@@ -7,6 +8,12 @@ pub trait LoxCallable: dyn_clone::DynClone + std::fmt::Debug {
     /// Returns the number of arguments the callable expects.
     fn arity(&self) -> usize;
 
+    /// Returns how many trailing parameters may be left out. Calls pass
+    /// nil for them.
+    fn optional(&self) -> usize {
+        0
+    }
+
     /// Calls the function or class.
     fn call(&self, interpreter: &mut Interpreter, arguments: Vec<Value>) -> Value;
 
@@ -14,6 +21,11 @@ pub trait LoxCallable: dyn_clone::DynClone + std::fmt::Debug {
     fn to_string(&self) -> String {
         "<native fn>".to_string()
     }
+
+    /// Returns the class behind this callable, if it is one.
+    fn as_class(&self) -> Option<&LoxClass> {
+        None
+    }
 }
 
 dyn_clone::clone_trait_object!(LoxCallable);
@@ -22,6 +34,7 @@ dyn_clone::clone_trait_object!(LoxCallable);
 #[derive(Debug, Clone)]
 pub struct NativeFunction {
     pub arity: usize,
+    pub optional: usize, // Trailing parameters that may be left out
     pub function: fn(&mut Interpreter, Vec<Value>) -> Value,
 }
 
@@ -30,6 +43,10 @@ impl LoxCallable for NativeFunction {
         self.arity
     }
 
+    fn optional(&self) -> usize {
+        self.optional
+    }
+
     fn call(&self, interpreter: &mut Interpreter, arguments: Vec<Value>) -> Value {
         (self.function)(interpreter, arguments)
     }
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::mem;
 use std::rc::Rc;
 use std::fmt;
 
@@ -8,12 +9,20 @@ use crate::interpreter::lox_instance::LoxInstance;
 use crate::interpreter::interpreter::Interpreter;
 use crate::interpreter::value::Value; // Assuming `Value` is defined elsewhere.
 
+/// Fills in a new instance of a native class from the arguments it was
+/// called with.
+pub type NativeInitializer = fn(&mut Interpreter, &LoxInstance, Vec<Value>);
+
 /// Represents a Lox class.
 #[derive(Debug, Clone)]
 pub struct LoxClass {
     pub name: String,
     pub superclass: Option<Rc<LoxClass>>,
     pub methods: HashMap<String, Rc<LoxFunction>>,
+    pub class_methods: HashMap<String, Rc<LoxFunction>>,
+    pub getters: HashMap<String, Rc<LoxFunction>>,
+    pub setters: HashMap<String, Rc<LoxFunction>>,
+    pub native_init: Option<(usize, NativeInitializer)>, // Arity and initializer of a native class
 }
 
 impl LoxClass {
@@ -27,6 +36,18 @@ impl LoxClass {
             name,
             superclass,
             methods,
+            class_methods: HashMap::new(),
+            getters: HashMap::new(),
+            setters: HashMap::new(),
+            native_init: None,
+        }
+    }
+
+    /// Creates a class defined in Rust, whose instances `init` fills in.
+    pub fn native(name: &str, arity: usize, init: NativeInitializer) -> Self {
+        Self {
+            native_init: Some((arity, init)),
+            ..Self::new(name.to_string(), None, HashMap::new())
         }
     }
 
@@ -42,12 +63,43 @@ impl LoxClass {
 
         None
     }
+
+    /// Finds a class (static) method by name in the class or its superclass.
+    pub fn find_class_method(&self, name: &str) -> Option<Rc<LoxFunction>> {
+        self.find_member(name, |klass| &klass.class_methods)
+    }
+
+    /// Finds a property getter by name in the class or its superclass.
+    pub fn find_getter(&self, name: &str) -> Option<Rc<LoxFunction>> {
+        self.find_member(name, |klass| &klass.getters)
+    }
+
+    /// Finds a property setter by name in the class or its superclass.
+    pub fn find_setter(&self, name: &str) -> Option<Rc<LoxFunction>> {
+        self.find_member(name, |klass| &klass.setters)
+    }
+
+    fn find_member(
+        &self,
+        name: &str,
+        table: fn(&LoxClass) -> &HashMap<String, Rc<LoxFunction>>,
+    ) -> Option<Rc<LoxFunction>> {
+        if let Some(member) = table(self).get(name) {
+            return Some(member.clone());
+        }
+
+        self.superclass
+            .as_ref()
+            .and_then(|superclass| superclass.find_member(name, table))
+    }
 }
 
 impl LoxCallable for LoxClass {
     /// Returns the number of arguments expected by the initializer (if it exists).
     fn arity(&self) -> usize {
-        if let Some(initializer) = self.find_method("init") {
+        if let Some((arity, _)) = self.native_init {
+            arity
+        } else if let Some(initializer) = self.find_method("init") {
             initializer.arity()
         } else {
             0
@@ -56,9 +108,12 @@ impl LoxCallable for LoxClass {
 
     /// Calls the class, creating a new instance and initializing it if an initializer is defined.
     fn call(&self, interpreter: &mut Interpreter, arguments: Vec<Value>) -> Value {
+        interpreter.charge(mem::size_of::<LoxInstance>());
         let instance = Rc::new(LoxInstance::new(Rc::new(self.clone())));
 
-        if let Some(initializer) = self.find_method("init") {
+        if let Some((_, init)) = self.native_init {
+            init(interpreter, &instance, arguments);
+        } else if let Some(initializer) = self.find_method("init") {
             initializer.bind(instance.clone()).call(interpreter, arguments);
         }
 
@@ -69,6 +124,10 @@ impl LoxCallable for LoxClass {
     fn to_string(&self) -> String {
         self.name.clone()
     }
+
+    fn as_class(&self) -> Option<&LoxClass> {
+        Some(self)
+    }
 }
 
 impl fmt::Display for LoxClass {
@@ -3,26 +3,54 @@ use std::cell::RefCell;
 use std::fmt;
 
 use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::lox_callable::LoxCallable;
 use crate::interpreter::value::Value;
 use crate::interpreter::environment::Environment;
 use crate::interpreter::lox_instance::LoxInstance;
-use crate::interpreter::stmt::Stmt;
+use crate::interpreter::return_value::Return;
+use crate::interpreter::stmt::StmtFunction;
+use crate::interpreter::token::Token;
+use crate::interpreter::token_type::TokenType;
 
 /// Represents a function in Lox.
 #[derive(Clone)]
 pub struct LoxFunction {
-    declaration: Rc<Stmt>,           // Function declaration
+    declaration: Rc<StmtFunction>,   // Function declaration (named or anonymous)
     closure: Rc<RefCell<Environment>>, // Closure environment
     is_initializer: bool,           // Indicates if this is an initializer
+    class: Option<Rc<str>>,         // Name of the class declaring it, if a class member
 }
 
 impl LoxFunction {
     /// Creates a new LoxFunction.
-    pub fn new(declaration: Rc<Stmt>, closure: Rc<RefCell<Environment>>, is_initializer: bool) -> Self {
+    pub fn new(declaration: Rc<StmtFunction>, closure: Rc<RefCell<Environment>>, is_initializer: bool) -> Self {
         Self {
             declaration,
             closure,
             is_initializer,
+            class: None,
+        }
+    }
+
+    /// Marks the function as a member of the class named `class`.
+    pub fn in_class(mut self, class: &str) -> Self {
+        self.class = Some(class.into());
+        self
+    }
+
+    /// The function's name token, or its `fun` or `=>` token if anonymous.
+    pub fn name(&self) -> &Token {
+        &self.declaration.name
+    }
+
+    /// The name profiles and stack traces show: `Class.method` for class
+    /// members and `<fn line N>` for anonymous functions.
+    pub fn qualified_name(&self) -> String {
+        let name = &self.declaration.name;
+        match (&self.class, name.token_type == TokenType::Identifier) {
+            (_, false) => format!("<fn line {}>", name.line),
+            (Some(class), true) => format!("{}.{}", class, name.lexeme),
+            (None, true) => name.lexeme.clone(),
         }
     }
 
@@ -30,61 +58,58 @@ impl LoxFunction {
     pub fn bind(&self, instance: Rc<LoxInstance>) -> Self {
         let environment = Rc::new(RefCell::new(Environment::with_enclosing(self.closure.clone())));
         environment.borrow_mut().define("this", Value::Instance(instance));
-        Self::new(self.declaration.clone(), environment, self.is_initializer)
+        Self {
+            closure: environment,
+            ..self.clone()
+        }
     }
 }
 
-impl crate::interpreter::lox_callable::LoxCallable for LoxFunction {
+impl LoxCallable for LoxFunction {
     /// Returns the number of parameters the function expects.
     fn arity(&self) -> usize {
-        if let Stmt::Function { params, .. } = &*self.declaration {
-            params.len()
-        } else {
-            0
-        }
+        self.declaration.params.len()
     }
 
     /// Executes the function in its closure environment.
     fn call(&self, interpreter: &mut Interpreter, arguments: Vec<Value>) -> Value {
         let environment = Rc::new(RefCell::new(Environment::with_enclosing(self.closure.clone())));
 
-        if let Stmt::Function { params, body, .. } = &*self.declaration {
-            for (i, param) in params.iter().enumerate() {
-                if let Some(argument) = arguments.get(i) {
-                    environment.borrow_mut().define(&param.lexeme, argument.clone());
-                }
-            }
-
-            // Execute the function body
-            if let Err(return_value) = interpreter.execute_block(body, environment) {
-                // Handle return statement or initializer
-                if self.is_initializer {
-                    return self.closure.borrow().get_at(0, "this").unwrap();
-                }
-                return return_value;
+        for (i, param) in self.declaration.params.iter().enumerate() {
+            if let Some(argument) = arguments.get(i) {
+                environment.borrow_mut().define(&param.lexeme, argument.clone());
             }
         }
 
-        // Return the instance if this is an initializer, or nil otherwise
+        // Execute the function body
+        interpreter.enter_function(&self.qualified_name());
+        let result = interpreter.execute_block(&self.declaration.body, environment);
+        interpreter.exit_function();
+
+        // An initializer always returns its instance, even from a bare `return`.
         if self.is_initializer {
-            self.closure.borrow().get_at(0, "this").unwrap()
-        } else {
-            Value::Nil
+            return Environment::get_at(&self.closure, 0, "this");
+        }
+        match result {
+            Ok(()) => Value::Nil,
+            Err(Return { value }) => value,
         }
     }
 
     /// String representation of the function.
     fn to_string(&self) -> String {
-        if let Stmt::Function { name, .. } = &*self.declaration {
+        // Anonymous functions are named after their `fun` or `=>` token.
+        let name = &self.declaration.name;
+        if name.token_type == TokenType::Identifier {
             format!("<fn {}>", name.lexeme)
         } else {
-            "<fn unknown>".to_string()
+            "<fn>".to_string()
         }
     }
 }
 
 impl fmt::Debug for LoxFunction {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.to_string())
+        write!(f, "{}", LoxCallable::to_string(self))
     }
 }
\ No newline at end of file
@@ -1,7 +1,10 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
+use crate::interpreter::interpreter::Interpreter;
 use crate::interpreter::lox_class::LoxClass;
+use crate::interpreter::lox_function::LoxFunction;
 use crate::interpreter::runtime_error::RuntimeError;
 use crate::interpreter::token::Token;
 use crate::interpreter::value::Value;
@@ -9,8 +12,9 @@ use crate::interpreter::value::Value;
 /// Represents an instance of a Lox class.
 #[derive(Debug, Clone)]
 pub struct LoxInstance {
-    klass: Rc<LoxClass>,           // The class this instance belongs to
-    fields: HashMap<String, Value>, // Instance fields
+    klass: Rc<LoxClass>,                     // The class this instance belongs to
+    fields: RefCell<HashMap<String, Value>>, // Instance fields
+    setting: RefCell<HashSet<String>>,       // Properties whose setter is running
 }
 
 impl LoxInstance {
@@ -18,32 +22,76 @@ impl LoxInstance {
     pub fn new(klass: Rc<LoxClass>) -> Self {
         Self {
             klass,
-            fields: HashMap::new(),
+            fields: RefCell::new(HashMap::new()),
+            setting: RefCell::new(HashSet::new()),
         }
     }
 
-    /// Gets a property or method from the instance.
-    pub fn get(&self, name: &Token) -> Value {
+    /// Gets a property or method from the instance. Fields shadow getters,
+    /// which in turn shadow methods.
+    pub fn get(self: &Rc<Self>, name: &Token, interpreter: &mut Interpreter) -> Value {
         // Check for instance fields first
-        if let Some(value) = self.fields.get(&name.lexeme) {
+        if let Some(value) = self.fields.borrow().get(&name.lexeme) {
             return value.clone();
         }
 
+        // Run a getter if the class declares one
+        if let Some(getter) = self.klass.find_getter(&name.lexeme) {
+            return interpreter.call_at(name, &getter.bind(self.clone()), Vec::new());
+        }
+
         // Check for methods in the class
         if let Some(method) = self.klass.find_method(&name.lexeme) {
-            return Value::Callable(Box::new(method.bind(Rc::new(self.clone()))));
+            return Value::Callable(Box::new(method.bind(self.clone())));
         }
 
         // If not found, throw a runtime error
-        panic!(
-            "{}",
-            RuntimeError::new(name.clone(), format!("Undefined property '{}'.", name.lexeme))
-        );
+        RuntimeError::new(name.clone(), format!("Undefined property '{}'.", name.lexeme)).raise();
+    }
+
+    /// Looks up a special method such as `__add__` or `__str__` on the
+    /// instance's class and binds it to the instance.
+    pub fn special_method(self: &Rc<Self>, name: &str) -> Option<LoxFunction> {
+        self.klass.find_method(name).map(|method| method.bind(self.clone()))
+    }
+
+    /// Reads a field directly, without consulting getters or methods.
+    pub fn field(&self, name: &str) -> Option<Value> {
+        self.fields.borrow().get(name).cloned()
+    }
+
+    /// Stores a field directly, bypassing setters. Natives use this to
+    /// build instances.
+    pub fn define_field(&self, name: &str, value: Value) {
+        self.fields.borrow_mut().insert(name.to_string(), value);
     }
 
-    /// Sets a property on the instance.
-    pub fn set(&mut self, name: &Token, value: Value) {
-        self.fields.insert(name.lexeme.clone(), value);
+    /// Sets a property on the instance, going through the class's setter if
+    /// it declares one. While that setter runs, assigning the same property
+    /// stores the field directly instead of calling the setter again.
+    pub fn set(self: &Rc<Self>, name: &Token, value: Value, interpreter: &mut Interpreter) {
+        if let Some(setter) = self.klass.find_setter(&name.lexeme) {
+            if self.setting.borrow_mut().insert(name.lexeme.clone()) {
+                let _setting = Setting { instance: self, name: &name.lexeme };
+                interpreter.call_at(name, &setter.bind(self.clone()), vec![value]);
+                return;
+            }
+        }
+
+        self.fields.borrow_mut().insert(name.lexeme.clone(), value);
+    }
+}
+
+/// Marks a property's setter as finished when dropped, even if the setter
+/// raised a runtime error.
+struct Setting<'a> {
+    instance: &'a LoxInstance,
+    name: &'a str,
+}
+
+impl Drop for Setting<'_> {
+    fn drop(&mut self) {
+        self.instance.setting.borrow_mut().remove(self.name);
     }
 }
 
@@ -0,0 +1,142 @@
+use std::cell::RefCell;
+use std::mem;
+use std::rc::Rc;
+
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::lox_callable::LoxCallable;
+use crate::interpreter::runtime_error::RuntimeError;
+use crate::interpreter::token::Token;
+use crate::interpreter::value::Value;
+
+/// Signature of a built-in String method: interpreter, receiver and
+/// arguments in, result or error message out. Methods charge the strings
+/// they build to the interpreter's allocation budget before building them.
+type StringMethodFn = fn(&mut Interpreter, &str, &[Value]) -> Result<Value, String>;
+
+/// The built-in String class. Strings have a `length` property and the
+/// methods below; all positions count chars, not bytes.
+pub struct LoxString;
+
+impl LoxString {
+    /// Looks up a property or method on a string value.
+    pub fn get(receiver: &str, name: &Token) -> Value {
+        if name.lexeme == "length" {
+            return Value::Integer(receiver.chars().count() as i64);
+        }
+
+        match Self::method(&name.lexeme) {
+            Some((arity, method)) => Value::Callable(Box::new(StringMethod {
+                receiver: receiver.to_string(),
+                name: name.clone(),
+                arity,
+                method,
+            })),
+            None => RuntimeError::new(name.clone(), format!("Undefined property '{}' on String.", name.lexeme)).raise(),
+        }
+    }
+
+    /// Evaluates `s[i]`, returning the char at index `i` as a string.
+    pub fn index(receiver: &str, index: &Value, bracket: &Token) -> Value {
+        let position = match index {
+            Value::Integer(i) if *i >= 0 => receiver.chars().nth(*i as usize),
+            Value::Integer(_) => None,
+            _ => RuntimeError::new(bracket.clone(), "String index must be an integer.".to_string()).raise(),
+        };
+
+        match position {
+            Some(c) => Value::String(c.to_string()),
+            None => RuntimeError::new(bracket.clone(), "String index out of range.".to_string()).raise(),
+        }
+    }
+
+    fn method(name: &str) -> Option<(usize, StringMethodFn)> {
+        let method: (usize, StringMethodFn) = match name {
+            "upper" => (0, |interpreter, s, _| {
+                interpreter.charge(s.len());
+                Ok(Value::String(s.to_uppercase()))
+            }),
+            "lower" => (0, |interpreter, s, _| {
+                interpreter.charge(s.len());
+                Ok(Value::String(s.to_lowercase()))
+            }),
+            "trim" => (0, |interpreter, s, _| {
+                let trimmed = s.trim();
+                interpreter.charge(trimmed.len());
+                Ok(Value::String(trimmed.to_string()))
+            }),
+            "contains" => (1, |_, s, args| Ok(Value::Boolean(s.contains(string_arg(&args[0])?)))),
+            "startsWith" => (1, |_, s, args| Ok(Value::Boolean(s.starts_with(string_arg(&args[0])?)))),
+            "endsWith" => (1, |_, s, args| Ok(Value::Boolean(s.ends_with(string_arg(&args[0])?)))),
+            "replace" => (2, |interpreter, s, args| {
+                let (from, to) = (string_arg(&args[0])?, string_arg(&args[1])?);
+                // Replacing an empty string inserts `to` around every char.
+                let matches = if from.is_empty() { s.chars().count() + 1 } else { s.matches(from).count() };
+                interpreter.charge(s.len().saturating_add(matches.saturating_mul(to.len())));
+                Ok(Value::String(s.replace(from, to)))
+            }),
+            "split" => (1, |interpreter, s, args| {
+                let separator = string_arg(&args[0])?;
+                let count = if separator.is_empty() { s.chars().count() } else { s.matches(separator).count() + 1 };
+                interpreter.charge(s.len().saturating_add(count.saturating_mul(mem::size_of::<Value>())));
+                let parts: Vec<Value> = if separator.is_empty() {
+                    s.chars().map(|c| Value::String(c.to_string())).collect()
+                } else {
+                    s.split(separator).map(|part| Value::String(part.to_string())).collect()
+                };
+                Ok(Value::List(Rc::new(RefCell::new(parts))))
+            }),
+            "substr" => (2, |interpreter, s, args| {
+                let start = integer_arg(&args[0])?;
+                let length = integer_arg(&args[1])?;
+                if start > s.chars().count() {
+                    return Err("Substring start out of range.".to_string());
+                }
+                let substring: String = s.chars().skip(start).take(length).collect();
+                interpreter.charge(substring.len());
+                Ok(Value::String(substring))
+            }),
+            _ => return None,
+        };
+        Some(method)
+    }
+}
+
+fn string_arg(value: &Value) -> Result<&str, String> {
+    match value {
+        Value::String(s) => Ok(s),
+        _ => Err("Argument must be a string.".to_string()),
+    }
+}
+
+fn integer_arg(value: &Value) -> Result<usize, String> {
+    match value {
+        Value::Integer(i) if *i >= 0 => Ok(*i as usize),
+        _ => Err("Argument must be a non-negative integer.".to_string()),
+    }
+}
+
+/// A String method bound to its receiver, e.g. the value of `s.upper`.
+#[derive(Debug, Clone)]
+pub struct StringMethod {
+    receiver: String,
+    name: Token,
+    arity: usize,
+    method: StringMethodFn,
+}
+
+impl LoxCallable for StringMethod {
+    fn arity(&self) -> usize {
+        self.arity
+    }
+
+    fn call(&self, interpreter: &mut Interpreter, arguments: Vec<Value>) -> Value {
+        match (self.method)(interpreter, &self.receiver, &arguments) {
+            Ok(value) => value,
+            Err(message) => RuntimeError::new(self.name.clone(), message).raise(),
+        }
+    }
+
+    fn to_string(&self) -> String {
+        format!("<native method String.{}>", self.name.lexeme)
+    }
+}
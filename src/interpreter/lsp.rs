@@ -0,0 +1,503 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+use serde_json::{json, Value as Json};
+
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::lint::{Linter, Severity};
+use crate::interpreter::parser::Parser;
+use crate::interpreter::resolver::Resolver;
+use crate::interpreter::scanner::{Scanner, KEYWORDS};
+use crate::interpreter::symbols::{SymbolIndex, SymbolKind};
+use crate::interpreter::token::Token;
+use crate::interpreter::value::Value;
+
+// JSON-RPC error code for requests the server does not handle.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+// LSP enumerations used below.
+const SEVERITY_ERROR: u8 = 1;
+const SEVERITY_WARNING: u8 = 2;
+const SYNC_FULL: u8 = 1;
+
+/// An open document and what was last learned from it.
+struct Document {
+    text: String,
+    index: Option<SymbolIndex>, // From the last version that parsed
+}
+
+/// A language server for one client, speaking LSP over JSON-RPC.
+pub struct Server {
+    documents: HashMap<String, Document>,
+    builtins: Vec<(String, Option<usize>, bool)>, // Name, arity if callable, whether a module
+    shutdown: bool,
+    exit: Option<i32>,
+}
+
+impl Server {
+    pub fn new() -> Self {
+        let builtins = Interpreter::new()
+            .global_bindings()
+            .into_iter()
+            .map(|(name, value)| match value {
+                Value::Callable(callable) => (name, Some(callable.arity()), false),
+                Value::Module(_) => (name, None, true),
+                _ => (name, None, false),
+            })
+            .collect();
+
+        Self {
+            documents: HashMap::new(),
+            builtins,
+            shutdown: false,
+            exit: None,
+        }
+    }
+
+    /// Handles one message from the client and returns the messages to
+    /// send back: a response for a request, plus any notifications.
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let method = message["method"].as_str().unwrap_or("");
+        let params = &message["params"];
+        let id = message.get("id").cloned();
+
+        let mut notifications = Vec::new();
+        let result = match method {
+            "initialize" => Some(capabilities()),
+            "shutdown" => {
+                self.shutdown = true;
+                Some(Json::Null)
+            }
+            "exit" => {
+                self.exit = Some(if self.shutdown { 0 } else { 1 });
+                None
+            }
+            "textDocument/didOpen" => {
+                let document = &params["textDocument"];
+                let uri = document["uri"].as_str().unwrap_or("");
+                notifications.push(self.update(uri, document["text"].as_str().unwrap_or("")));
+                None
+            }
+            "textDocument/didChange" => {
+                // Only full-text sync is advertised, so the last change holds
+                // the whole document.
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+                if let Some(text) = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str())
+                {
+                    notifications.push(self.update(uri, text));
+                }
+                None
+            }
+            "textDocument/didClose" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+                self.documents.remove(uri);
+                notifications.push(publish_diagnostics(uri, Vec::new()));
+                None
+            }
+            "textDocument/definition" => Some(self.definition(params)),
+            "textDocument/references" => Some(self.references(params)),
+            "textDocument/hover" => Some(self.hover(params)),
+            "textDocument/documentSymbol" => Some(self.document_symbols(params)),
+            "textDocument/completion" => Some(self.completion(params)),
+            _ => {
+                if let Some(id) = id {
+                    notifications.push(json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": METHOD_NOT_FOUND, "message": format!("Unknown method '{}'.", method) },
+                    }));
+                }
+                return notifications;
+            }
+        };
+
+        // Notifications get no response.
+        if let (Some(id), Some(result)) = (id, result) {
+            notifications.insert(0, json!({ "jsonrpc": "2.0", "id": id, "result": result }));
+        }
+        notifications
+    }
+
+    /// Returns the exit code once the client has sent `exit`.
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit
+    }
+
+    /// Re-analyzes a document and returns its diagnostics notification.
+    fn update(&mut self, uri: &str, text: &str) -> Json {
+        let (diagnostics, index) = analyze(text);
+        let document = self.documents.entry(uri.to_string()).or_insert(Document {
+            text: String::new(),
+            index: None,
+        });
+        document.text = text.to_string();
+        if index.is_some() {
+            document.index = index;
+        }
+        publish_diagnostics(uri, diagnostics)
+    }
+
+    /// Finds the document, its text and index, and the symbol under the
+    /// cursor in `params`.
+    fn symbol_at<'a>(&'a self, params: &'a Json) -> Option<(&'a str, &'a str, &'a SymbolIndex, usize)> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let document = self.documents.get(uri)?;
+        let (line, column) = position(&document.text, params)?;
+        let index = document.index.as_ref()?;
+        Some((uri, &document.text, index, index.symbol_at(line + 1, column)?))
+    }
+
+    fn definition(&self, params: &Json) -> Json {
+        match self.symbol_at(params) {
+            Some((uri, text, index, id)) => location(uri, text, &index.symbol(id).name),
+            None => Json::Null,
+        }
+    }
+
+    fn references(&self, params: &Json) -> Json {
+        let declaration = params["context"]["includeDeclaration"].as_bool().unwrap_or(true);
+        match self.symbol_at(params) {
+            Some((uri, text, index, id)) => index
+                .occurrences(id, declaration)
+                .into_iter()
+                .map(|token| location(uri, text, token))
+                .collect(),
+            None => Json::Null,
+        }
+    }
+
+    /// Shows how a name was declared and, for callables, how many
+    /// arguments it takes.
+    fn hover(&self, params: &Json) -> Json {
+        let (detail, arity) = match self.symbol_at(params) {
+            Some((_, _, index, id)) => {
+                let symbol = index.symbol(id);
+                (symbol.detail.clone(), symbol.arity)
+            }
+            None => {
+                let builtin = self.word_at(params).and_then(|word| {
+                    self.builtins.iter().find(|(name, _, _)| *name == word)
+                });
+                match builtin {
+                    Some((name, Some(arity), _)) => (format!("native fun {}", name), Some(*arity)),
+                    Some((name, None, true)) => (format!("module {}", name), None),
+                    _ => return Json::Null,
+                }
+            }
+        };
+
+        let mut value = format!("```lox\n{}\n```", detail);
+        if let Some(arity) = arity {
+            let plural = if arity == 1 { "" } else { "s" };
+            value.push_str(&format!("\nTakes {} argument{}.", arity, plural));
+        }
+        json!({ "contents": { "kind": "markdown", "value": value } })
+    }
+
+    /// Lists classes with their methods, and top-level functions.
+    fn document_symbols(&self, params: &Json) -> Json {
+        let (text, index) = match params["textDocument"]["uri"]
+            .as_str()
+            .and_then(|uri| self.documents.get(uri))
+            .and_then(|document| Some((&document.text, document.index.as_ref()?)))
+        {
+            Some(found) => found,
+            None => return json!([]),
+        };
+
+        let symbols = index.symbols();
+        let outline: Vec<Json> = symbols
+            .iter()
+            .enumerate()
+            .filter(|(_, symbol)| {
+                symbol.kind == SymbolKind::Class || (symbol.kind == SymbolKind::Function && symbol.global)
+            })
+            .map(|(id, symbol)| {
+                let mut entry = document_symbol(text, &symbol.name, &symbol.detail, symbol.kind);
+                if symbol.kind == SymbolKind::Class {
+                    entry["children"] = symbols
+                        .iter()
+                        .filter(|method| method.container == Some(id))
+                        .map(|method| document_symbol(text, &method.name, &method.detail, method.kind))
+                        .collect();
+                }
+                entry
+            })
+            .collect();
+        Json::Array(outline)
+    }
+
+    /// Offers names in scope at the cursor, then natives and keywords,
+    /// that start with the identifier being typed.
+    fn completion(&self, params: &Json) -> Json {
+        let document = match params["textDocument"]["uri"].as_str().and_then(|uri| self.documents.get(uri)) {
+            Some(document) => document,
+            None => return json!([]),
+        };
+        let (line, column) = match position(&document.text, params) {
+            Some(position) => position,
+            None => return json!([]),
+        };
+        let prefix: String = {
+            let before: Vec<char> = document.text.lines().nth(line).unwrap_or("").chars().take(column).collect();
+            let start = before.iter().rposition(|c| !is_identifier(*c)).map_or(0, |i| i + 1);
+            before[start..].iter().collect()
+        };
+
+        let mut items = Vec::new();
+        if let Some(index) = &document.index {
+            for symbol in index.visible_at(line + 1) {
+                items.push(completion_item(&symbol.name.lexeme, symbol.kind, &symbol.detail));
+            }
+        }
+        for (name, arity, module) in &self.builtins {
+            let (kind, detail) = match (arity, module) {
+                (Some(arity), _) => (3, format!("native fun {}/{}", name, arity)),
+                (None, true) => (9, format!("module {}", name)),
+                (None, false) => (6, format!("var {}", name)),
+            };
+            items.push(json!({ "label": name, "kind": kind, "detail": detail }));
+        }
+        let mut keywords: Vec<&&str> = KEYWORDS.keys().collect();
+        keywords.sort();
+        for keyword in keywords {
+            items.push(json!({ "label": keyword, "kind": 14 }));
+        }
+
+        let mut seen = Vec::new();
+        items.retain(|item| {
+            let label = item["label"].as_str().unwrap_or("").to_string();
+            let keep = label.starts_with(&prefix) && !seen.contains(&label);
+            seen.push(label);
+            keep
+        });
+        Json::Array(items)
+    }
+
+    /// Returns the identifier under the cursor, read from the text.
+    fn word_at(&self, params: &Json) -> Option<String> {
+        let document = self.documents.get(params["textDocument"]["uri"].as_str()?)?;
+        let (line, column) = position(&document.text, params)?;
+        let chars: Vec<char> = document.text.lines().nth(line)?.chars().collect();
+        if !chars.get(column).is_some_and(|c| is_identifier(*c)) {
+            return None;
+        }
+        let start = chars[..column].iter().rposition(|c| !is_identifier(*c)).map_or(0, |i| i + 1);
+        let end = chars[column..].iter().position(|c| !is_identifier(*c)).map_or(chars.len(), |i| column + i);
+        Some(chars[start..end].iter().collect())
+    }
+}
+
+fn capabilities() -> Json {
+    json!({
+        "capabilities": {
+            "textDocumentSync": SYNC_FULL,
+            "definitionProvider": true,
+            "referencesProvider": true,
+            "hoverProvider": true,
+            "documentSymbolProvider": true,
+            "completionProvider": {},
+        },
+        "serverInfo": { "name": "lox" },
+    })
+}
+
+/// Scans, parses and resolves a document. Returns its diagnostics, and its
+/// symbols if it parsed.
+fn analyze(text: &str) -> (Vec<Json>, Option<SymbolIndex>) {
+    let lines: Vec<&str> = text.lines().collect();
+    let whole_line = |line: usize| {
+        let length = lines.get(line.saturating_sub(1)).map_or(0, |text| text.encode_utf16().count());
+        json!({
+            "start": { "line": line.saturating_sub(1), "character": 0 },
+            "end": { "line": line.saturating_sub(1), "character": length },
+        })
+    };
+    let diagnostic = |range: Json, severity: u8, code: Option<&str>, message: &str| {
+        let mut diagnostic = json!({ "range": range, "severity": severity, "source": "lox", "message": message });
+        if let Some(code) = code {
+            diagnostic["code"] = json!(code);
+        }
+        diagnostic
+    };
+
+    let mut scanner = Scanner::new(text.to_string());
+    let tokens = scanner.scan_tokens();
+    let mut diagnostics: Vec<Json> = scanner
+        .errors()
+        .iter()
+        .map(|error| diagnostic(whole_line(error.line), SEVERITY_ERROR, None, &error.message))
+        .collect();
+
+    let statements = match Parser::new(tokens.clone()).parse() {
+        Ok(statements) => statements,
+        Err(error) => {
+            diagnostics.push(diagnostic(range(text, &error.token), SEVERITY_ERROR, None, &error.message));
+            return (diagnostics, None);
+        }
+    };
+
+    let mut interpreter = Interpreter::new();
+    let mut linter = Linter::new(&interpreter);
+    linter.check_empty_blocks(&tokens);
+    let mut resolver = Resolver::with_linter(&mut interpreter, linter);
+    resolver.index_symbols();
+    resolver.resolve(&statements);
+
+    for (token, message) in resolver.errors() {
+        diagnostics.push(diagnostic(range(text, token), SEVERITY_ERROR, None, message));
+    }
+    let index = resolver.take_index();
+    let linter = resolver.into_linter().expect("resolver was created with a linter");
+    for lint in linter.finish(scanner.comments()) {
+        let severity = match lint.severity {
+            Severity::Error => SEVERITY_ERROR,
+            Severity::Warning => SEVERITY_WARNING,
+        };
+        diagnostics.push(diagnostic(whole_line(lint.line), severity, Some(lint.rule), &lint.message));
+    }
+    (diagnostics, index)
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Json>) -> Json {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
+
+/// The 0-based line and column of `params.position` in `text`. LSP counts
+/// characters in UTF-16 code units; the column counts chars, like tokens.
+fn position(text: &str, params: &Json) -> Option<(usize, usize)> {
+    let position = &params["position"];
+    let line = position["line"].as_u64()? as usize;
+    let character = position["character"].as_u64()? as usize;
+
+    let mut units = 0;
+    let column = text
+        .lines()
+        .nth(line)
+        .unwrap_or("")
+        .chars()
+        .take_while(|c| {
+            units += c.len_utf16();
+            units <= character
+        })
+        .count();
+    Some((line, column))
+}
+
+/// The LSP range of a token in `text`. Lines are 1-based in tokens and
+/// 0-based in LSP, and columns count chars in tokens but UTF-16 code units
+/// in LSP.
+fn range(text: &str, token: &Token) -> Json {
+    let line = token.line.saturating_sub(1);
+    let before = text.lines().nth(line).unwrap_or("").chars().take(token.column);
+    let start: usize = before.map(char::len_utf16).sum();
+    let end = start + token.lexeme.encode_utf16().count();
+    json!({
+        "start": { "line": line, "character": start },
+        "end": { "line": line, "character": end },
+    })
+}
+
+fn location(uri: &str, text: &str, token: &Token) -> Json {
+    json!({ "uri": uri, "range": range(text, token) })
+}
+
+fn document_symbol(text: &str, name: &Token, detail: &str, kind: SymbolKind) -> Json {
+    json!({
+        "name": name.lexeme,
+        "detail": detail,
+        "kind": match kind {
+            SymbolKind::Class => 5,
+            SymbolKind::Method => 6,
+            SymbolKind::Function => 12,
+            SymbolKind::Variable | SymbolKind::Parameter => 13,
+        },
+        "range": range(text, name),
+        "selectionRange": range(text, name),
+    })
+}
+
+fn completion_item(label: &str, kind: SymbolKind, detail: &str) -> Json {
+    let kind = match kind {
+        SymbolKind::Method => 2,
+        SymbolKind::Function => 3,
+        SymbolKind::Variable | SymbolKind::Parameter => 6,
+        SymbolKind::Class => 7,
+    };
+    json!({ "label": label, "kind": kind, "detail": detail })
+}
+
+fn is_identifier(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Reads one `Content-Length` framed message, or `None` at end of input.
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<Json>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let length = length.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length header"))?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body).map(Some).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+pub fn write_message(output: &mut impl Write, message: &Json) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+/// Runs `lox lsp`, serving one client over stdin and stdout until it
+/// sends `exit`.
+pub fn command(args: &[String]) -> i32 {
+    if !args.is_empty() {
+        eprintln!("Usage: lox lsp");
+        return 64;
+    }
+
+    let mut input = io::stdin().lock();
+    let mut output = io::stdout().lock();
+    let mut server = Server::new();
+    loop {
+        let message = match read_message(&mut input) {
+            Ok(Some(message)) => message,
+            Ok(None) => return if server.shutdown { 0 } else { 1 },
+            Err(err) => {
+                eprintln!("lox lsp: {}", err);
+                return 74;
+            }
+        };
+
+        for reply in server.handle(&message) {
+            if let Err(err) = write_message(&mut output, &reply) {
+                eprintln!("lox lsp: {}", err);
+                return 74;
+            }
+        }
+        if let Some(code) = server.exit_code() {
+            return code;
+        }
+    }
+}
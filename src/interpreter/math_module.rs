@@ -0,0 +1,130 @@
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+use crate::interpreter::bigint::BigInt;
+use crate::interpreter::environment::Environment;
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::lox_callable::NativeFunction;
+use crate::interpreter::native_module::NativeModule;
+use crate::interpreter::number;
+use crate::interpreter::value::Value;
+
+/// Builds the `math` module.
+pub fn module() -> Value {
+    let mut math = NativeModule::new("math");
+    math.constant("pi", Value::Number(std::f64::consts::PI))
+        .constant("e", Value::Number(std::f64::consts::E))
+        .constant("inf", Value::Number(f64::INFINITY))
+        .constant("nan", Value::Number(f64::NAN))
+        .function("floor", 1, |interpreter, args| rounded(interpreter, &args[0], f64::floor))
+        .function("ceil", 1, |interpreter, args| rounded(interpreter, &args[0], f64::ceil))
+        .function("round", 1, |interpreter, args| rounded(interpreter, &args[0], f64::round))
+        .function("abs", 1, |interpreter, args| match &args[0] {
+            // Integers stay exact; only negative ones need negating.
+            Value::Integer(_) | Value::BigInteger(_) => match number::compare(&args[0], &Value::Integer(0)) {
+                Ok(Some(Ordering::Less)) => number::negate(&args[0]).unwrap_or(Value::Nil),
+                _ => args[0].clone(),
+            },
+            other => Value::Number(float(interpreter, other).abs()),
+        })
+        .function("sqrt", 1, |interpreter, args| Value::Number(float(interpreter, &args[0]).sqrt()))
+        .function("pow", 2, |interpreter, args| {
+            Value::Number(float(interpreter, &args[0]).powf(float(interpreter, &args[1])))
+        })
+        .function("min", 2, |interpreter, args| extreme(interpreter, args, Ordering::Less))
+        .function("max", 2, |interpreter, args| extreme(interpreter, args, Ordering::Greater))
+        .function("sin", 1, |interpreter, args| Value::Number(float(interpreter, &args[0]).sin()))
+        .function("cos", 1, |interpreter, args| Value::Number(float(interpreter, &args[0]).cos()))
+        .function("tan", 1, |interpreter, args| Value::Number(float(interpreter, &args[0]).tan()))
+        .function("asin", 1, |interpreter, args| Value::Number(float(interpreter, &args[0]).asin()))
+        .function("acos", 1, |interpreter, args| Value::Number(float(interpreter, &args[0]).acos()))
+        .function("atan", 1, |interpreter, args| Value::Number(float(interpreter, &args[0]).atan()))
+        .function("atan2", 2, |interpreter, args| {
+            Value::Number(float(interpreter, &args[0]).atan2(float(interpreter, &args[1])))
+        })
+        .function("log", 1, |interpreter, args| Value::Number(float(interpreter, &args[0]).ln()))
+        .function("log10", 1, |interpreter, args| Value::Number(float(interpreter, &args[0]).log10()))
+        .function("exp", 1, |interpreter, args| Value::Number(float(interpreter, &args[0]).exp()))
+        .function("isNaN", 1, |interpreter, args| Value::Boolean(float(interpreter, &args[0]).is_nan()));
+    Value::Module(Rc::new(math))
+}
+
+/// Defines the global number conversion natives `num` and `fixed`.
+pub fn define_globals(globals: &mut Environment) {
+    // num("3.5") parses a string into an integer or float.
+    globals.define(
+        "num",
+        Value::Callable(Box::new(NativeFunction {
+            arity: 1,
+            optional: 0,
+            function: |interpreter, args| match &args[0] {
+                Value::String(s) => parse(s.trim())
+                    .unwrap_or_else(|| interpreter.native_error(&format!("Cannot parse '{}' as a number.", s))),
+                value if number::is_number(value) => value.clone(),
+                _ => interpreter.native_error("Argument must be a string or number."),
+            },
+        })),
+    );
+
+    // fixed(x, digits) formats a number with a fixed number of decimals.
+    globals.define(
+        "fixed",
+        Value::Callable(Box::new(NativeFunction {
+            arity: 2,
+            optional: 0,
+            function: |interpreter, args| {
+                let value = float(interpreter, &args[0]);
+                let digits = match &args[1] {
+                    Value::Integer(d) if (0..=20).contains(d) => *d as usize,
+                    _ => interpreter.native_error("Digits must be an integer between 0 and 20."),
+                };
+                Value::String(format!("{:.*}", digits, value))
+            },
+        })),
+    );
+}
+
+/// Parses number text the way the scanner would read a literal, also
+/// accepting a leading sign and exponent notation.
+fn parse(text: &str) -> Option<Value> {
+    if let Ok(i) = text.parse::<i64>() {
+        return Some(Value::Integer(i));
+    }
+    if let Some(big) = BigInt::parse(text.strip_prefix('+').unwrap_or(text)) {
+        return Some(Value::BigInteger(big));
+    }
+    // Reject the words Rust accepts but Lox has no literal for.
+    if text.chars().any(|c| c.is_ascii_alphabetic() && c != 'e' && c != 'E') {
+        return None;
+    }
+    text.parse::<f64>().ok().map(Value::Number)
+}
+
+fn float(interpreter: &Interpreter, value: &Value) -> f64 {
+    number::as_f64(value).unwrap_or_else(|| interpreter.native_error("Argument must be a number."))
+}
+
+/// Applies a rounding function, returning an integer when the result fits.
+fn rounded(interpreter: &Interpreter, value: &Value, round: fn(f64) -> f64) -> Value {
+    match value {
+        Value::Integer(_) | Value::BigInteger(_) => value.clone(),
+        _ => {
+            let result = round(float(interpreter, value));
+            if result.is_finite() && result.abs() < i64::MAX as f64 {
+                Value::Integer(result as i64)
+            } else {
+                Value::Number(result)
+            }
+        }
+    }
+}
+
+/// Returns whichever argument compares as `wanted` against the other.
+fn extreme(interpreter: &Interpreter, args: Vec<Value>, wanted: Ordering) -> Value {
+    match number::compare(&args[0], &args[1]) {
+        Ok(Some(ordering)) if ordering == wanted => args[0].clone(),
+        Ok(Some(_)) => args[1].clone(),
+        Ok(None) => Value::Number(f64::NAN),
+        Err(message) => interpreter.native_error(&message),
+    }
+}
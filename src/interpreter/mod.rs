@@ -1,17 +1,42 @@
+pub mod ast_json;
 pub mod ast_printer;
+pub mod bigint;
+pub mod cancellation;
+pub mod coverage;
+pub mod dap;
+pub mod debugger;
+pub mod doc;
 pub mod environment;
+pub mod formatter;
+#[allow(clippy::module_inception)]
 pub mod interpreter;
+pub mod json_module;
+pub mod io_natives;
+pub mod limits;
+pub mod lint;
 pub mod lox;
+pub mod lsp;
 pub mod lox_callable;
 pub mod lox_class;
 pub mod lox_function;
 pub mod lox_instance;
+pub mod lox_string;
+pub mod math_module;
+pub mod native_module;
+pub mod number;
+pub mod optimizer;
 pub mod parser;
+pub mod profiler;
+pub mod random_module;
+pub mod repl;
 pub mod resolver;
 pub mod return_value;
+pub mod time_module;
 pub mod runtime_error;
 pub mod scanner;
 pub mod stmt;
+pub mod symbols;
+pub mod test_runner;
 pub mod token;
 pub mod token_type;
 pub mod expr;
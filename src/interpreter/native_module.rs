@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::lox_callable::NativeFunction;
+use crate::interpreter::runtime_error::RuntimeError;
+use crate::interpreter::token::Token;
+use crate::interpreter::value::Value;
+
+/// A namespace of native functions and constants, such as `math`, exposed
+/// to scripts as a global whose members are read with `module.name`.
+#[derive(Debug, Clone)]
+pub struct NativeModule {
+    pub name: String,
+    members: HashMap<String, Value>,
+}
+
+impl NativeModule {
+    /// Creates an empty module.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            members: HashMap::new(),
+        }
+    }
+
+    /// Adds a native function member.
+    pub fn function(
+        &mut self,
+        name: &str,
+        arity: usize,
+        function: fn(&mut Interpreter, Vec<Value>) -> Value,
+    ) -> &mut Self {
+        self.function_with_optional(name, arity, 0, function)
+    }
+
+    /// Adds a native function member whose last `optional` parameters may
+    /// be left out.
+    pub fn function_with_optional(
+        &mut self,
+        name: &str,
+        arity: usize,
+        optional: usize,
+        function: fn(&mut Interpreter, Vec<Value>) -> Value,
+    ) -> &mut Self {
+        let native = NativeFunction { arity, optional, function };
+        self.members.insert(name.to_string(), Value::Callable(Box::new(native)));
+        self
+    }
+
+    /// Adds a constant member.
+    pub fn constant(&mut self, name: &str, value: Value) -> &mut Self {
+        self.members.insert(name.to_string(), value);
+        self
+    }
+
+    /// Reads a member, raising a runtime error if the module lacks it.
+    pub fn get(&self, name: &Token) -> Value {
+        match self.members.get(&name.lexeme) {
+            Some(value) => value.clone(),
+            None => RuntimeError::new(
+                name.clone(),
+                format!("Undefined property '{}' on module '{}'.", name.lexeme, self.name),
+            ).raise(),
+        }
+    }
+}
+
+impl fmt::Display for NativeModule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<module {}>", self.name)
+    }
+}
@@ -0,0 +1,246 @@
+use std::cmp::Ordering;
+
+use crate::interpreter::bigint::BigInt;
+use crate::interpreter::token_type::TokenType;
+use crate::interpreter::value::Value;
+
+/// Numeric view of a value. Integers stay exact and overflow into
+/// `BigInt`; any float operand turns the whole operation into floats.
+enum Number {
+    Int(i64),
+    Big(BigInt),
+    Float(f64),
+}
+
+impl Number {
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Integer(i) => Some(Number::Int(*i)),
+            Value::BigInteger(b) => Some(Number::Big(b.clone())),
+            Value::Number(n) => Some(Number::Float(*n)),
+            _ => None,
+        }
+    }
+
+    fn to_f64(&self) -> f64 {
+        match self {
+            Number::Int(i) => *i as f64,
+            Number::Big(b) => b.to_f64(),
+            Number::Float(n) => *n,
+        }
+    }
+
+    fn to_big(&self) -> BigInt {
+        match self {
+            Number::Int(i) => BigInt::from_i64(*i),
+            Number::Big(b) => b.clone(),
+            Number::Float(_) => unreachable!("floats never take the integer path"),
+        }
+    }
+}
+
+/// Narrows a big integer back to `Value::Integer` when it fits.
+fn integer_value(value: BigInt) -> Value {
+    match value.to_i64() {
+        Some(i) => Value::Integer(i),
+        None => Value::BigInteger(value),
+    }
+}
+
+/// Returns true if the value is any kind of number.
+pub fn is_number(value: &Value) -> bool {
+    Number::from_value(value).is_some()
+}
+
+/// Converts a numeric value to `f64`.
+pub fn as_f64(value: &Value) -> Option<f64> {
+    Number::from_value(value).map(|number| number.to_f64())
+}
+
+/// Evaluates `+ - * / ~/ %` on two numbers.
+pub fn arithmetic(operator: &TokenType, left: &Value, right: &Value) -> Result<Value, String> {
+    let (a, b) = match (Number::from_value(left), Number::from_value(right)) {
+        (Some(a), Some(b)) => (a, b),
+        _ => return Err("Operands must be numbers.".to_string()),
+    };
+
+    // `/` is always true division; mixed operands promote to floats.
+    if *operator == TokenType::Slash || matches!(a, Number::Float(_)) || matches!(b, Number::Float(_)) {
+        return float_arithmetic(operator, a.to_f64(), b.to_f64());
+    }
+
+    if let (Number::Int(x), Number::Int(y)) = (&a, &b) {
+        let result = match operator {
+            TokenType::Plus => x.checked_add(*y),
+            TokenType::Minus => x.checked_sub(*y),
+            TokenType::Star => x.checked_mul(*y),
+            TokenType::TildeSlash | TokenType::Percent if *y == 0 => {
+                return Err("Division by zero.".to_string())
+            }
+            TokenType::TildeSlash => x.checked_div(*y).map(|q| {
+                if x % y != 0 && (*x < 0) != (*y < 0) {
+                    q - 1
+                } else {
+                    q
+                }
+            }),
+            TokenType::Percent => x.checked_rem(*y).map(|r| {
+                if r != 0 && (r < 0) != (*y < 0) {
+                    r + y
+                } else {
+                    r
+                }
+            }),
+            _ => return Err("Unsupported numeric operator.".to_string()),
+        };
+        if let Some(result) = result {
+            return Ok(Value::Integer(result));
+        }
+        // Overflowed: redo the operation with big integers.
+    }
+
+    let (x, y) = (a.to_big(), b.to_big());
+    let result = match operator {
+        TokenType::Plus => &x + &y,
+        TokenType::Minus => &x - &y,
+        TokenType::Star => &x * &y,
+        TokenType::TildeSlash | TokenType::Percent => {
+            let (quotient, remainder) = x
+                .div_rem_floor(&y)
+                .ok_or_else(|| "Division by zero.".to_string())?;
+            if *operator == TokenType::TildeSlash {
+                quotient
+            } else {
+                remainder
+            }
+        }
+        _ => return Err("Unsupported numeric operator.".to_string()),
+    };
+    Ok(integer_value(result))
+}
+
+fn float_arithmetic(operator: &TokenType, a: f64, b: f64) -> Result<Value, String> {
+    let result = match operator {
+        TokenType::Plus => a + b,
+        TokenType::Minus => a - b,
+        TokenType::Star => a * b,
+        TokenType::Slash => a / b,
+        TokenType::TildeSlash => (a / b).floor(),
+        TokenType::Percent => a - b * (a / b).floor(),
+        _ => return Err("Unsupported numeric operator.".to_string()),
+    };
+    Ok(Value::Number(result))
+}
+
+/// The largest left shift allowed; the result grows with the shift, so an
+/// unbounded one could exhaust memory.
+pub const MAX_SHIFT: u32 = 1 << 16;
+
+/// Evaluates `& | ^ << >>`, which require integer operands. Big integers
+/// behave as two's complement with as many sign bits as needed.
+pub fn bitwise(operator: &TokenType, left: &Value, right: &Value) -> Result<Value, String> {
+    let (a, b) = match (Number::from_value(left), Number::from_value(right)) {
+        (Some(Number::Float(_)), _) | (_, Some(Number::Float(_))) | (None, _) | (_, None) => {
+            return Err("Operands must be integers.".to_string())
+        }
+        (Some(a), Some(b)) => (a, b),
+    };
+
+    if matches!(operator, TokenType::LessLess | TokenType::GreaterGreater) {
+        return shift(operator, a, b);
+    }
+
+    if let (Number::Int(x), Number::Int(y)) = (&a, &b) {
+        return match operator {
+            TokenType::Ampersand => Ok(Value::Integer(x & y)),
+            TokenType::Pipe => Ok(Value::Integer(x | y)),
+            TokenType::Caret => Ok(Value::Integer(x ^ y)),
+            _ => Err("Unsupported bitwise operator.".to_string()),
+        };
+    }
+
+    let (x, y) = (a.to_big(), b.to_big());
+    let result = match operator {
+        TokenType::Ampersand => x.bitwise(&y, |a, b| a & b),
+        TokenType::Pipe => x.bitwise(&y, |a, b| a | b),
+        TokenType::Caret => x.bitwise(&y, |a, b| a ^ b),
+        _ => return Err("Unsupported bitwise operator.".to_string()),
+    };
+    Ok(integer_value(result))
+}
+
+/// Evaluates `<<` or `>>`. Shifting right rounds towards negative infinity.
+fn shift(operator: &TokenType, value: Number, amount: Number) -> Result<Value, String> {
+    let amount = match amount {
+        Number::Int(amount) if amount < 0 => return Err("Shift amount must not be negative.".to_string()),
+        Number::Int(amount) => amount as u64,
+        Number::Big(amount) if amount < BigInt::from_i64(0) => {
+            return Err("Shift amount must not be negative.".to_string())
+        }
+        _ => u64::MAX, // Any big amount shifts every bit out
+    };
+
+    if *operator == TokenType::GreaterGreater {
+        return Ok(match value {
+            Number::Int(i) => Value::Integer(i >> amount.min(63)),
+            value => integer_value(value.to_big().shr(amount)),
+        });
+    }
+
+    match value {
+        Number::Int(0) => return Ok(Value::Integer(0)),
+        Number::Int(i) if amount < 64 => {
+            let shifted = i << amount;
+            if shifted >> amount == i {
+                return Ok(Value::Integer(shifted));
+            }
+        }
+        _ => {}
+    }
+    match u32::try_from(amount) {
+        Ok(amount) if amount <= MAX_SHIFT => Ok(integer_value(value.to_big().shl(amount))),
+        _ => Err(format!("Shift amount must be at most {}.", MAX_SHIFT)),
+    }
+}
+
+/// Negates a number, promoting `-i64::MIN` to a big integer.
+pub fn negate(value: &Value) -> Result<Value, String> {
+    match Number::from_value(value) {
+        Some(Number::Int(i)) => Ok(match i.checked_neg() {
+            Some(negated) => Value::Integer(negated),
+            None => integer_value(-&BigInt::from_i64(i)),
+        }),
+        Some(Number::Big(b)) => Ok(integer_value(-&b)),
+        Some(Number::Float(n)) => Ok(Value::Number(-n)),
+        None => Err("Operand must be a number.".to_string()),
+    }
+}
+
+/// Evaluates `~` on an integer: `~x` is `-x - 1`.
+pub fn bit_not(value: &Value) -> Result<Value, String> {
+    match value {
+        Value::Integer(i) => Ok(Value::Integer(!i)),
+        Value::BigInteger(b) => Ok(integer_value(&-b - &BigInt::from_i64(1))),
+        _ => Err("Operand must be an integer.".to_string()),
+    }
+}
+
+/// Orders two numbers; `Ok(None)` means unordered (a NaN was involved).
+pub fn compare(left: &Value, right: &Value) -> Result<Option<Ordering>, String> {
+    match (Number::from_value(left), Number::from_value(right)) {
+        (Some(Number::Float(a)), Some(b)) => Ok(a.partial_cmp(&b.to_f64())),
+        (Some(a), Some(Number::Float(b))) => Ok(a.to_f64().partial_cmp(&b)),
+        (Some(Number::Int(a)), Some(Number::Int(b))) => Ok(Some(a.cmp(&b))),
+        (Some(a), Some(b)) => Ok(Some(a.to_big().cmp(&b.to_big()))),
+        _ => Err("Operands must be numbers.".to_string()),
+    }
+}
+
+/// Compares two values numerically, or returns `None` if either is not a
+/// number so the caller can fall back to its own equality.
+pub fn numbers_equal(left: &Value, right: &Value) -> Option<bool> {
+    if !is_number(left) || !is_number(right) {
+        return None;
+    }
+    Some(compare(left, right).ok()? == Some(Ordering::Equal))
+}
@@ -0,0 +1,308 @@
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+use crate::interpreter::expr::Expr;
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::number;
+use crate::interpreter::stmt::{
+    Stmt, StmtBlock, StmtClass, StmtExpression, StmtFunction, StmtIf, StmtPrint, StmtReturn,
+    StmtTest, StmtVar, StmtWhile,
+};
+use crate::interpreter::token::Token;
+use crate::interpreter::token_type::TokenType;
+use crate::interpreter::value::{Object, Value};
+
+/// Rewrites a resolved program into a simpler one that behaves the same:
+/// literal arithmetic and concatenation are folded, `!` on constants is
+/// inlined, branches on constant conditions are taken or dropped, and
+/// statements after a `return` are removed. Anything that would fail at
+/// runtime is left for the interpreter to report.
+pub struct Optimizer<'a> {
+    interpreter: &'a mut Interpreter,
+}
+
+impl<'a> Optimizer<'a> {
+    pub fn new(interpreter: &'a mut Interpreter) -> Self {
+        Self { interpreter }
+    }
+
+    /// Optimizes a program the resolver has already checked.
+    pub fn optimize(&mut self, statements: Vec<Stmt>) -> Vec<Stmt> {
+        self.statements(statements)
+    }
+
+    fn statements(&mut self, statements: Vec<Stmt>) -> Vec<Stmt> {
+        let mut optimized = Vec::new();
+        for statement in statements {
+            if let Some(statement) = self.statement(statement) {
+                let returns = matches!(statement, Stmt::Return(_));
+                optimized.push(statement);
+                // Nothing after a `return` can run.
+                if returns {
+                    break;
+                }
+            }
+        }
+        optimized
+    }
+
+    /// Optimizes a statement, or returns `None` if it would do nothing.
+    fn statement(&mut self, stmt: Stmt) -> Option<Stmt> {
+        let optimized = match stmt {
+            Stmt::Block(stmt) => Stmt::Block(StmtBlock {
+                brace: stmt.brace,
+                statements: self.statements(stmt.statements),
+            }),
+            Stmt::Class(stmt) => Stmt::Class(StmtClass {
+                methods: self.methods(stmt.methods),
+                class_methods: self.methods(stmt.class_methods),
+                getters: self.methods(stmt.getters),
+                setters: self.methods(stmt.setters),
+                ..stmt
+            }),
+            Stmt::Expression(stmt) => Stmt::Expression(StmtExpression {
+                expression: self.expr(stmt.expression),
+            }),
+            Stmt::Function(stmt) => Stmt::Function(self.shared_function(stmt)),
+            Stmt::If(stmt) => {
+                let condition = self.expr(stmt.condition);
+                let then_branch = self.branch(*stmt.then_branch);
+                let else_branch = stmt.else_branch.map(|branch| Box::new(self.branch(*branch)));
+                match truthiness(&condition) {
+                    Some(true) => then_branch,
+                    Some(false) => return else_branch.map(|branch| *branch),
+                    None => Stmt::If(StmtIf {
+                        keyword: stmt.keyword,
+                        condition,
+                        then_branch: Box::new(then_branch),
+                        else_branch,
+                    }),
+                }
+            }
+            Stmt::Print(stmt) => Stmt::Print(StmtPrint {
+                keyword: stmt.keyword,
+                expression: self.expr(stmt.expression),
+            }),
+            Stmt::Return(stmt) => Stmt::Return(StmtReturn {
+                keyword: stmt.keyword,
+                value: stmt.value.map(|value| self.expr(value)),
+            }),
+            Stmt::Test(stmt) => Stmt::Test(StmtTest {
+                body: self.statements(stmt.body),
+                ..stmt
+            }),
+            Stmt::Var(stmt) => Stmt::Var(StmtVar {
+                name: stmt.name,
+                initializer: stmt.initializer.map(|initializer| self.expr(initializer)),
+            }),
+            Stmt::While(stmt) => {
+                let condition = self.expr(stmt.condition);
+                if truthiness(&condition) == Some(false) {
+                    return None;
+                }
+                Stmt::While(StmtWhile {
+                    keyword: stmt.keyword,
+                    condition,
+                    body: Box::new(self.branch(*stmt.body)),
+                    increment: stmt.increment.map(|increment| self.expr(increment)),
+                })
+            }
+        };
+        Some(optimized)
+    }
+
+    /// Optimizes the body of an `if` or loop, which needs some statement.
+    fn branch(&mut self, stmt: Stmt) -> Stmt {
+        let line = stmt.line();
+        self.statement(stmt).unwrap_or_else(|| {
+            Stmt::Block(StmtBlock {
+                brace: Token::new(TokenType::LeftBrace, "{".to_string(), None, line),
+                statements: Vec::new(),
+            })
+        })
+    }
+
+    fn function(&mut self, function: StmtFunction) -> StmtFunction {
+        StmtFunction {
+            body: self.statements(function.body),
+            ..function
+        }
+    }
+
+    fn shared_function(&mut self, function: Rc<StmtFunction>) -> Rc<StmtFunction> {
+        // Freshly parsed functions are not shared yet.
+        match Rc::try_unwrap(function) {
+            Ok(function) => Rc::new(self.function(function)),
+            Err(function) => function,
+        }
+    }
+
+    fn methods(&mut self, methods: Vec<Rc<StmtFunction>>) -> Vec<Rc<StmtFunction>> {
+        methods.into_iter().map(|method| self.shared_function(method)).collect()
+    }
+
+    fn expr(&mut self, expr: Expr) -> Expr {
+        match expr {
+            Expr::Assign { .. } => self.assign(expr),
+            Expr::Binary { left, operator, right } => {
+                let left = self.expr(*left);
+                let right = self.expr(*right);
+                let folded = match (constant(&left), constant(&right)) {
+                    (Some(a), Some(b)) => {
+                        fold_binary(&operator, &a, &b).and_then(|value| literal(value, left.line()))
+                    }
+                    _ => None,
+                };
+                folded.unwrap_or_else(|| Expr::Binary {
+                    left: Box::new(left),
+                    operator,
+                    right: Box::new(right),
+                })
+            }
+            Expr::Call { callee, paren, arguments } => Expr::Call {
+                callee: Box::new(self.expr(*callee)),
+                paren,
+                arguments: arguments.into_iter().map(|argument| self.expr(argument)).collect(),
+            },
+            Expr::Grouping { expression } => match self.expr(*expression) {
+                literal @ Expr::Literal { .. } => literal,
+                expression => Expr::Grouping { expression: Box::new(expression) },
+            },
+            Expr::Logical { left, operator, right } => {
+                let left = self.expr(*left);
+                let right = self.expr(*right);
+                // A constant left operand decides which side is the value.
+                match (&operator.token_type, truthiness(&left)) {
+                    (TokenType::Or, Some(true)) | (TokenType::And, Some(false)) => left,
+                    (TokenType::Or, Some(false)) | (TokenType::And, Some(true)) => right,
+                    _ => Expr::Logical {
+                        left: Box::new(left),
+                        operator,
+                        right: Box::new(right),
+                    },
+                }
+            }
+            Expr::Unary { operator, right } => {
+                let right = self.expr(*right);
+                let folded = constant(&right).and_then(|value| match operator.token_type {
+                    TokenType::Bang => Some(Value::Boolean(!Interpreter::is_truthy(&value))),
+                    TokenType::Minus => number::negate(&value).ok(),
+                    TokenType::Tilde => number::bit_not(&value).ok(),
+                    _ => None,
+                });
+                folded.and_then(|value| literal(value, operator.line)).unwrap_or_else(|| Expr::Unary {
+                    operator,
+                    right: Box::new(right),
+                })
+            }
+            Expr::Get { object, name } => Expr::Get {
+                object: Box::new(self.expr(*object)),
+                name,
+            },
+            Expr::Set { object, name, value } => Expr::Set {
+                object: Box::new(self.expr(*object)),
+                name,
+                value: Box::new(self.expr(*value)),
+            },
+            Expr::Index { object, bracket, index } => Expr::Index {
+                object: Box::new(self.expr(*object)),
+                bracket,
+                index: Box::new(self.expr(*index)),
+            },
+            Expr::Interpolation { start, segments, values } => Expr::Interpolation {
+                start,
+                segments,
+                values: values.into_iter().map(|value| self.expr(value)).collect(),
+            },
+            Expr::Lambda { declaration } => Expr::Lambda {
+                declaration: self.shared_function(declaration),
+            },
+            Expr::Literal { .. } | Expr::Variable { .. } | Expr::Super { .. } | Expr::This { .. } => expr,
+        }
+    }
+
+    /// Optimizes an assignment's value. Resolved depths are looked up by
+    /// the whole expression, so the rewritten one takes over the depth.
+    fn assign(&mut self, expr: Expr) -> Expr {
+        let original = expr.clone();
+        let Expr::Assign { name, value, id } = expr else {
+            return expr;
+        };
+        let optimized = Expr::Assign {
+            name,
+            value: Box::new(self.expr(*value)),
+            id,
+        };
+        self.interpreter.re_resolve(&original, &optimized);
+        optimized
+    }
+}
+
+/// The value of a literal expression.
+fn constant(expr: &Expr) -> Option<Value> {
+    match expr {
+        Expr::Literal { value, .. } => Some(match value {
+            Some(Object::Number(n)) => Value::Number(*n),
+            Some(Object::Integer(i)) => Value::Integer(*i),
+            Some(Object::BigInteger(b)) => Value::BigInteger(b.clone()),
+            Some(Object::String(s)) => Value::String(s.clone()),
+            Some(Object::Boolean(b)) => Value::Boolean(*b),
+            Some(Object::Nil) | None => Value::Nil,
+        }),
+        _ => None,
+    }
+}
+
+/// A literal expression on `line` for a value, if it can be written as one.
+fn literal(value: Value, line: usize) -> Option<Expr> {
+    let object = match value {
+        Value::Number(n) => Object::Number(n),
+        Value::Integer(i) => Object::Integer(i),
+        Value::BigInteger(b) => Object::BigInteger(b),
+        Value::String(s) => Object::String(s),
+        Value::Boolean(b) => Object::Boolean(b),
+        Value::Nil => Object::Nil,
+        _ => return None,
+    };
+    Some(Expr::Literal { value: Some(object), line })
+}
+
+fn truthiness(expr: &Expr) -> Option<bool> {
+    constant(expr).map(|value| Interpreter::is_truthy(&value))
+}
+
+/// Computes a binary operator the way the interpreter does, or returns
+/// `None` where it would raise an error.
+fn fold_binary(operator: &Token, left: &Value, right: &Value) -> Option<Value> {
+    match operator.token_type {
+        TokenType::Plus => match (left, right) {
+            (Value::String(a), Value::String(b)) => Some(Value::String(format!("{}{}", a, b))),
+            _ if number::is_number(left) && number::is_number(right) => {
+                number::arithmetic(&operator.token_type, left, right).ok()
+            }
+            _ => None,
+        },
+        TokenType::Minus
+        | TokenType::Slash
+        | TokenType::Star
+        | TokenType::TildeSlash
+        | TokenType::Percent => number::arithmetic(&operator.token_type, left, right).ok(),
+        TokenType::Ampersand
+        | TokenType::Pipe
+        | TokenType::Caret
+        | TokenType::LessLess
+        | TokenType::GreaterGreater => number::bitwise(&operator.token_type, left, right).ok(),
+        TokenType::Greater | TokenType::GreaterEqual | TokenType::Less | TokenType::LessEqual => {
+            let ordering = number::compare(left, right).ok()?;
+            Some(Value::Boolean(match operator.token_type {
+                TokenType::Greater => ordering == Some(Ordering::Greater),
+                TokenType::GreaterEqual => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+                TokenType::Less => ordering == Some(Ordering::Less),
+                _ => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            }))
+        }
+        TokenType::EqualEqual => Some(Value::Boolean(Interpreter::is_equal(left, right))),
+        TokenType::BangEqual => Some(Value::Boolean(!Interpreter::is_equal(left, right))),
+        _ => None,
+    }
+}
@@ -1,16 +1,25 @@
-use crate::interpreter::lox::Lox;
-use crate::interpreter::stmt::Stmt;
-use crate::interpreter::token::Token;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::interpreter::scanner::Comment;
+use crate::interpreter::stmt::{
+    Stmt, StmtBlock, StmtClass, StmtExpression, StmtFunction, StmtIf, StmtPrint, StmtReturn,
+    StmtTest, StmtVar, StmtWhile,
+};
+use crate::interpreter::token::{Literal, Token};
 use crate::interpreter::token_type::TokenType;
-use crate::interpreter::expr::Expr;
+use crate::interpreter::expr::{Expr, ExprId};
+use crate::interpreter::value::Object;
 
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    docs: HashMap<usize, String>, // `///` comment text, by the line it documents
 }
 
 #[derive(Debug)]
 pub struct ParseError {
+    pub token: Token, // Where the error was detected
     pub message: String,
 }
 
@@ -20,9 +29,34 @@ impl Parser {
         Self {
             tokens,
             current: 0,
+            docs: HashMap::new(),
         }
     }
 
+    /// Creates a parser that attaches `///` comments to the class, method
+    /// or function declared on the line after them.
+    pub fn with_comments(tokens: Vec<Token>, comments: &[Comment]) -> Self {
+        let mut parser = Self::new(tokens);
+        let mut lines: Vec<&str> = Vec::new();
+        for (index, comment) in comments.iter().enumerate() {
+            let text = match comment.text.strip_prefix("///") {
+                Some(text) if !comment.trailing && !text.starts_with('/') => text,
+                _ => continue,
+            };
+            lines.push(text.strip_prefix(' ').unwrap_or(text).trim_end());
+
+            // A run of doc comments ends where the next line is not one.
+            let continues = comments.get(index + 1).is_some_and(|next| {
+                next.line == comment.line + 1 && !next.trailing && next.text.starts_with("///")
+            });
+            if !continues {
+                parser.docs.insert(comment.line + 1, lines.join("\n"));
+                lines.clear();
+            }
+        }
+        parser
+    }
+
     /// Parses a list of statements.
     pub fn parse(&mut self) -> Result<Vec<Stmt>, ParseError> {
         let mut statements = Vec::new();
@@ -36,10 +70,16 @@ impl Parser {
     fn declaration(&mut self) -> Result<Stmt, ParseError> {
         let result = if self.match_token(&[TokenType::Class]) {
             self.class_declaration()
-        } else if self.match_token(&[TokenType::Fun]) {
-            self.function("function")
+        } else if self.check(TokenType::Fun) && self.check_next(TokenType::Identifier) {
+            // A bare `fun (` starts an anonymous function expression instead.
+            self.advance();
+            self.function("function").map(|function| Stmt::Function(Rc::new(function)))
         } else if self.match_token(&[TokenType::Var]) {
             self.var_declaration()
+        } else if self.check(TokenType::Identifier) && self.peek().lexeme == "test" && self.check_next(TokenType::String) {
+            // `test` only starts a test before a name, so it stays usable
+            // as an identifier.
+            self.test_declaration()
         } else {
             self.statement()
         };
@@ -50,6 +90,18 @@ impl Parser {
         result
     }
 
+    /// Parses a `test "name" { ... }` declaration.
+    fn test_declaration(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.advance();
+        let name = match self.advance().literal {
+            Some(Literal::String(name)) => name,
+            _ => String::new(),
+        };
+        self.consume(TokenType::LeftBrace, "Expect '{' before test body.")?;
+        let body = self.block()?;
+        Ok(Stmt::Test(StmtTest { keyword, name, body }))
+    }
+
     /// Parses a class declaration.
     fn class_declaration(&mut self) -> Result<Stmt, ParseError> {
         let name = self.consume(TokenType::Identifier, "Expect class name.")?;
@@ -57,6 +109,7 @@ impl Parser {
             let superclass_name = self.consume(TokenType::Identifier, "Expect superclass name.")?;
             Some(Expr::Variable {
                 name: superclass_name,
+                id: ExprId::fresh(),
             })
         } else {
             None
@@ -65,18 +118,153 @@ impl Parser {
         self.consume(TokenType::LeftBrace, "Expect '{' before class body.")?;
 
         let mut methods = Vec::new();
+        let mut class_methods = Vec::new();
+        let mut getters = Vec::new();
+        let mut setters = Vec::new();
         while !self.check(TokenType::RightBrace) && !self.is_at_end() {
-            methods.push(self.function("method")?);
+            if self.match_token(&[TokenType::Class]) {
+                class_methods.push(Rc::new(self.function("method")?));
+            } else if self.check(TokenType::Identifier) && self.check_next(TokenType::LeftBrace) {
+                getters.push(Rc::new(self.getter()?));
+            } else if self.check(TokenType::Identifier) && self.check_next(TokenType::Equal) {
+                setters.push(Rc::new(self.setter()?));
+            } else {
+                methods.push(Rc::new(self.function("method")?));
+            }
         }
 
         self.consume(TokenType::RightBrace, "Expect '}' after class body.")?;
-        Ok(Stmt::Class {
+        Ok(Stmt::Class(StmtClass {
+            doc: self.docs.get(&name.line).cloned(),
             name,
             superclass,
             methods,
+            class_methods,
+            getters,
+            setters,
+        }))
+    }
+
+    /// Parses a property getter: `name { ... }`.
+    fn getter(&mut self) -> Result<StmtFunction, ParseError> {
+        let name = self.consume(TokenType::Identifier, "Expect getter name.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before getter body.")?;
+        let body = self.block()?;
+        Ok(StmtFunction {
+            doc: self.docs.get(&name.line).cloned(),
+            name,
+            params: Vec::new(),
+            body,
+        })
+    }
+
+    /// Parses a property setter: `name=(value) { ... }`.
+    fn setter(&mut self) -> Result<StmtFunction, ParseError> {
+        let name = self.consume(TokenType::Identifier, "Expect setter name.")?;
+        self.consume(TokenType::Equal, "Expect '=' after setter name.")?;
+        self.consume(TokenType::LeftParen, "Expect '(' after '='.")?;
+        let param = self.consume(TokenType::Identifier, "Expect setter parameter name.")?;
+        self.consume(TokenType::RightParen, "Setter must take exactly one parameter.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before setter body.")?;
+        let body = self.block()?;
+        Ok(StmtFunction {
+            doc: self.docs.get(&name.line).cloned(),
+            name,
+            params: vec![param],
+            body,
+        })
+    }
+
+    /// Parses a named function or method declaration.
+    fn function(&mut self, kind: &str) -> Result<StmtFunction, ParseError> {
+        let name = self.consume(TokenType::Identifier, &format!("Expect {} name.", kind))?;
+        self.consume(TokenType::LeftParen, &format!("Expect '(' after {} name.", kind))?;
+        let params = self.parameters()?;
+        self.consume(TokenType::LeftBrace, &format!("Expect '{{' before {} body.", kind))?;
+        let body = self.block()?;
+        let doc = self.docs.get(&name.line).cloned();
+        Ok(StmtFunction { name, params, body, doc })
+    }
+
+    /// Parses a parameter list up to and including the closing ')'.
+    fn parameters(&mut self) -> Result<Vec<Token>, ParseError> {
+        let mut params = Vec::new();
+        if !self.check(TokenType::RightParen) {
+            loop {
+                if params.len() >= 255 {
+                    return Err(ParseError {
+                        token: self.peek().clone(),
+                        message: "Can't have more than 255 parameters.".to_string(),
+                    });
+                }
+                params.push(self.consume(TokenType::Identifier, "Expect parameter name.")?);
+                if !self.match_token(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+        self.consume(TokenType::RightParen, "Expect ')' after parameters.")?;
+        Ok(params)
+    }
+
+    /// Parses an anonymous function: `fun (a, b) { ... }`.
+    fn lambda(&mut self) -> Result<Expr, ParseError> {
+        let keyword = self.previous().clone();
+        self.consume(TokenType::LeftParen, "Expect '(' after 'fun'.")?;
+        let params = self.parameters()?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before function body.")?;
+        let body = self.block()?;
+        Ok(Expr::Lambda {
+            declaration: Rc::new(StmtFunction { name: keyword, params, body, doc: None }),
         })
     }
 
+    /// Parses an arrow function: `(a, b) => a + b` or `(a, b) => { ... }`.
+    fn arrow_function(&mut self) -> Result<Expr, ParseError> {
+        self.consume(TokenType::LeftParen, "Expect '(' before parameters.")?;
+        let params = self.parameters()?;
+        let arrow = self.consume(TokenType::Arrow, "Expect '=>' after parameters.")?;
+
+        let body = if self.match_token(&[TokenType::LeftBrace]) {
+            self.block()?
+        } else {
+            let value = self.expression()?;
+            vec![Stmt::Return(StmtReturn {
+                keyword: arrow.clone(),
+                value: Some(value),
+            })]
+        };
+
+        Ok(Expr::Lambda {
+            declaration: Rc::new(StmtFunction { name: arrow, params, body, doc: None }),
+        })
+    }
+
+    /// Looks ahead from a '(' to see whether it opens an arrow function's
+    /// parameter list rather than a grouping.
+    fn is_arrow_function(&self) -> bool {
+        let mut index = self.current + 1;
+        let token_type = |i: usize| self.tokens.get(i).map(|token| &token.token_type);
+
+        if token_type(index) != Some(&TokenType::RightParen) {
+            loop {
+                if token_type(index) != Some(&TokenType::Identifier) {
+                    return false;
+                }
+                index += 1;
+                if token_type(index) != Some(&TokenType::Comma) {
+                    break;
+                }
+                index += 1;
+            }
+            if token_type(index) != Some(&TokenType::RightParen) {
+                return false;
+            }
+        }
+
+        token_type(index + 1) == Some(&TokenType::Arrow)
+    }
+
     /// Parses a statement.
     fn statement(&mut self) -> Result<Stmt, ParseError> {
         if self.match_token(&[TokenType::For]) {
@@ -90,9 +278,10 @@ impl Parser {
         } else if self.match_token(&[TokenType::While]) {
             self.while_statement()
         } else if self.match_token(&[TokenType::LeftBrace]) {
-            Ok(Stmt::Block {
+            Ok(Stmt::Block(StmtBlock {
+                brace: self.previous().clone(),
                 statements: self.block()?,
-            })
+            }))
         } else {
             self.expression_statement()
         }
@@ -100,6 +289,7 @@ impl Parser {
 
     /// Parses a for statement.
     fn for_statement(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous().clone();
         self.consume(TokenType::LeftParen, "Expect '(' after 'for'.")?;
         let initializer = if self.match_token(&[TokenType::Semicolon]) {
             None
@@ -123,32 +313,75 @@ impl Parser {
         };
         self.consume(TokenType::RightParen, "Expect ')' after for clauses.")?;
 
-        let mut body = self.statement()?;
-        if let Some(increment) = increment {
-            body = Stmt::Block {
-                statements: vec![body, Stmt::Expression { expression: increment }],
-            };
-        }
-
-        let condition = condition.unwrap_or_else(|| Expr::Literal {
-            value: Some(Lox::Bool(true)),
+        let body = self.statement()?;
+        let condition = condition.unwrap_or(Expr::Literal {
+            value: Some(Object::Boolean(true)),
+            line: keyword.line,
         });
-        body = Stmt::While {
+        let while_loop = Stmt::While(StmtWhile {
+            keyword: keyword.clone(),
             condition,
             body: Box::new(body),
+            increment,
+        });
+
+        Ok(match initializer {
+            Some(initializer) => Stmt::Block(StmtBlock {
+                brace: keyword,
+                statements: vec![initializer, while_loop],
+            }),
+            None => while_loop,
+        })
+    }
+
+    /// Parses a print statement.
+    fn print_statement(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous().clone();
+        let expression = self.expression()?;
+        self.consume(TokenType::Semicolon, "Expect ';' after value.")?;
+        Ok(Stmt::Print(StmtPrint { keyword, expression }))
+    }
+
+    /// Parses a return statement.
+    fn return_statement(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous().clone();
+        let value = if !self.check(TokenType::Semicolon) {
+            Some(self.expression()?)
+        } else {
+            None
         };
+        self.consume(TokenType::Semicolon, "Expect ';' after return value.")?;
+        Ok(Stmt::Return(StmtReturn { keyword, value }))
+    }
 
-        if let Some(initializer) = initializer {
-            body = Stmt::Block {
-                statements: vec![initializer, body],
-            };
-        }
+    /// Parses a while statement.
+    fn while_statement(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous().clone();
+        self.consume(TokenType::LeftParen, "Expect '(' after 'while'.")?;
+        let condition = self.expression()?;
+        self.consume(TokenType::RightParen, "Expect ')' after condition.")?;
+        let body = self.statement()?;
+        Ok(Stmt::While(StmtWhile {
+            keyword,
+            condition,
+            body: Box::new(body),
+            increment: None,
+        }))
+    }
 
-        Ok(body)
+    /// Parses the statements of a block up to and including the closing '}'.
+    fn block(&mut self) -> Result<Vec<Stmt>, ParseError> {
+        let mut statements = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            statements.push(self.declaration()?);
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after block.")?;
+        Ok(statements)
     }
 
     /// Parses an if statement.
     fn if_statement(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous().clone();
         self.consume(TokenType::LeftParen, "Expect '(' after 'if'.")?;
         let condition = self.expression()?;
         self.consume(TokenType::RightParen, "Expect ')' after if condition.")?;
@@ -160,11 +393,12 @@ impl Parser {
             None
         };
 
-        Ok(Stmt::If {
+        Ok(Stmt::If(StmtIf {
+            keyword,
             condition,
             then_branch: Box::new(then_branch),
             else_branch: else_branch.map(Box::new),
-        })
+        }))
     }
 
     /// Parses a variable declaration.
@@ -177,14 +411,14 @@ impl Parser {
         };
 
         self.consume(TokenType::Semicolon, "Expect ';' after variable declaration.")?;
-        Ok(Stmt::Var { name, initializer })
+        Ok(Stmt::Var(StmtVar { name, initializer }))
     }
 
     /// Parses an expression statement.
     fn expression_statement(&mut self) -> Result<Stmt, ParseError> {
         let expr = self.expression()?;
         self.consume(TokenType::Semicolon, "Expect ';' after expression.")?;
-        Ok(Stmt::Expression { expression: expr })
+        Ok(Stmt::Expression(StmtExpression { expression: expr }))
     }
 
     /// Parses an expression.
@@ -200,14 +434,26 @@ impl Parser {
             let equals = self.previous().clone();
             let value = self.assignment()?;
 
-            if let Expr::Variable { name } = expr {
-                return Ok(Expr::Assign {
-                    name,
-                    value: Box::new(value),
-                });
+            match expr {
+                Expr::Variable { name, .. } => {
+                    return Ok(Expr::Assign {
+                        name,
+                        value: Box::new(value),
+                        id: ExprId::fresh(),
+                    });
+                }
+                Expr::Get { object, name } => {
+                    return Ok(Expr::Set {
+                        object,
+                        name,
+                        value: Box::new(value),
+                    });
+                }
+                _ => {}
             }
 
             return Err(ParseError {
+                token: equals.clone(),
                 message: format!("Invalid assignment target at {:?}", equals),
             });
         }
@@ -215,10 +461,327 @@ impl Parser {
         Ok(expr)
     }
 
+    /// Parses a logical `or` expression.
+    fn or(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.and()?;
+        while self.match_token(&[TokenType::Or]) {
+            let operator = self.previous().clone();
+            let right = self.and()?;
+            expr = Expr::Logical {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+        Ok(expr)
+    }
+
+    /// Parses a logical `and` expression.
+    fn and(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.equality()?;
+        while self.match_token(&[TokenType::And]) {
+            let operator = self.previous().clone();
+            let right = self.equality()?;
+            expr = Expr::Logical {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+        Ok(expr)
+    }
+
+    /// Parses an equality expression.
+    fn equality(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.comparison()?;
+        while self.match_token(&[TokenType::BangEqual, TokenType::EqualEqual]) {
+            let operator = self.previous().clone();
+            let right = self.comparison()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+        Ok(expr)
+    }
+
+    /// Parses a comparison expression.
+    fn comparison(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.bit_or()?;
+        while self.match_token(&[
+            TokenType::Greater,
+            TokenType::GreaterEqual,
+            TokenType::Less,
+            TokenType::LessEqual,
+        ]) {
+            let operator = self.previous().clone();
+            let right = self.bit_or()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+        Ok(expr)
+    }
+
+    /// Parses a bitwise `|` expression. Bitwise operators bind tighter than
+    /// comparisons, so `a & 1 == 0` means `(a & 1) == 0`.
+    fn bit_or(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.bit_xor()?;
+        while self.match_token(&[TokenType::Pipe]) {
+            let operator = self.previous().clone();
+            let right = self.bit_xor()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+        Ok(expr)
+    }
+
+    /// Parses a bitwise `^` expression.
+    fn bit_xor(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.bit_and()?;
+        while self.match_token(&[TokenType::Caret]) {
+            let operator = self.previous().clone();
+            let right = self.bit_and()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+        Ok(expr)
+    }
+
+    /// Parses a bitwise `&` expression.
+    fn bit_and(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.shift()?;
+        while self.match_token(&[TokenType::Ampersand]) {
+            let operator = self.previous().clone();
+            let right = self.shift()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+        Ok(expr)
+    }
+
+    /// Parses a `<<` or `>>` shift expression.
+    fn shift(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.term()?;
+        while self.match_token(&[TokenType::LessLess, TokenType::GreaterGreater]) {
+            let operator = self.previous().clone();
+            let right = self.term()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+        Ok(expr)
+    }
+
+    /// Parses an addition or subtraction expression.
+    fn term(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.factor()?;
+        while self.match_token(&[TokenType::Minus, TokenType::Plus]) {
+            let operator = self.previous().clone();
+            let right = self.factor()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+        Ok(expr)
+    }
+
+    /// Parses a multiplication, division or modulo expression.
+    fn factor(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.unary()?;
+        while self.match_token(&[
+            TokenType::Slash,
+            TokenType::Star,
+            TokenType::TildeSlash,
+            TokenType::Percent,
+        ]) {
+            let operator = self.previous().clone();
+            let right = self.unary()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+        Ok(expr)
+    }
+
+    /// Parses a unary expression.
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        if self.match_token(&[TokenType::Bang, TokenType::Minus, TokenType::Tilde]) {
+            let operator = self.previous().clone();
+            let right = self.unary()?;
+            return Ok(Expr::Unary {
+                operator,
+                right: Box::new(right),
+            });
+        }
+        self.call()
+    }
+
+    /// Parses calls and property accesses.
+    fn call(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.primary()?;
+        loop {
+            if self.match_token(&[TokenType::LeftParen]) {
+                expr = self.finish_call(expr)?;
+            } else if self.match_token(&[TokenType::Dot]) {
+                let name = self.consume(TokenType::Identifier, "Expect property name after '.'.")?;
+                expr = Expr::Get {
+                    object: Box::new(expr),
+                    name,
+                };
+            } else if self.match_token(&[TokenType::LeftBracket]) {
+                let index = self.expression()?;
+                let bracket = self.consume(TokenType::RightBracket, "Expect ']' after index.")?;
+                expr = Expr::Index {
+                    object: Box::new(expr),
+                    bracket,
+                    index: Box::new(index),
+                };
+            } else {
+                break;
+            }
+        }
+        Ok(expr)
+    }
+
+    /// Parses the argument list of a call.
+    fn finish_call(&mut self, callee: Expr) -> Result<Expr, ParseError> {
+        let mut arguments = Vec::new();
+        if !self.check(TokenType::RightParen) {
+            loop {
+                if arguments.len() >= 255 {
+                    return Err(ParseError {
+                        token: self.peek().clone(),
+                        message: "Can't have more than 255 arguments.".to_string(),
+                    });
+                }
+                arguments.push(self.expression()?);
+                if !self.match_token(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+        let paren = self.consume(TokenType::RightParen, "Expect ')' after arguments.")?;
+        Ok(Expr::Call {
+            callee: Box::new(callee),
+            paren,
+            arguments,
+        })
+    }
+
+    /// Parses a primary expression.
+    fn primary(&mut self) -> Result<Expr, ParseError> {
+        if self.match_token(&[TokenType::False]) {
+            return Ok(Expr::Literal { value: Some(Object::Boolean(false)), line: self.previous().line });
+        }
+        if self.match_token(&[TokenType::True]) {
+            return Ok(Expr::Literal { value: Some(Object::Boolean(true)), line: self.previous().line });
+        }
+        if self.match_token(&[TokenType::Nil]) {
+            return Ok(Expr::Literal { value: Some(Object::Nil), line: self.previous().line });
+        }
+        if self.match_token(&[TokenType::Number, TokenType::Integer, TokenType::String]) {
+            let value = match &self.previous().literal {
+                Some(Literal::Number(n)) => Object::Number(*n),
+                Some(Literal::Integer(i)) => Object::Integer(*i),
+                Some(Literal::BigInteger(b)) => Object::BigInteger(b.clone()),
+                Some(Literal::String(s)) => Object::String(s.clone()),
+                Some(Literal::Boolean(b)) => Object::Boolean(*b),
+                Some(Literal::Nil) | None => Object::Nil,
+            };
+            return Ok(Expr::Literal { value: Some(value), line: self.previous().line });
+        }
+        if self.match_token(&[TokenType::Interpolation]) {
+            return self.interpolation();
+        }
+        if self.match_token(&[TokenType::Super]) {
+            self.consume(TokenType::Dot, "Expect '.' after 'super'.")?;
+            let method = self.consume(TokenType::Identifier, "Expect superclass method name.")?;
+            return Ok(Expr::Super { method, id: ExprId::fresh() });
+        }
+        if self.match_token(&[TokenType::This]) {
+            return Ok(Expr::This {
+                keyword: self.previous().clone(),
+                id: ExprId::fresh(),
+            });
+        }
+        if self.match_token(&[TokenType::Identifier]) {
+            return Ok(Expr::Variable {
+                name: self.previous().clone(),
+                id: ExprId::fresh(),
+            });
+        }
+        if self.match_token(&[TokenType::Fun]) {
+            return self.lambda();
+        }
+        if self.check(TokenType::LeftParen) && self.is_arrow_function() {
+            return self.arrow_function();
+        }
+        if self.match_token(&[TokenType::LeftParen]) {
+            let expression = self.expression()?;
+            self.consume(TokenType::RightParen, "Expect ')' after expression.")?;
+            return Ok(Expr::Grouping {
+                expression: Box::new(expression),
+            });
+        }
+
+        Err(ParseError {
+            token: self.peek().clone(),
+            message: "Expect expression.".to_string(),
+        })
+    }
+
+    /// Parses an interpolated string. `"a ${x} b"` becomes the segments
+    /// `"a "` and `" b"` around the value `x`, which is stringified the
+    /// way `print` does when the string is built.
+    fn interpolation(&mut self) -> Result<Expr, ParseError> {
+        let start = self.previous().clone();
+        let mut segments = vec![Self::segment_text(&start)];
+        let mut values = Vec::new();
+
+        loop {
+            values.push(self.expression()?);
+            let segment = if self.match_token(&[TokenType::Interpolation]) {
+                self.previous().clone()
+            } else {
+                self.consume(TokenType::String, "Expect '}' after interpolated expression.")?
+            };
+            segments.push(Self::segment_text(&segment));
+            if segment.token_type == TokenType::String {
+                return Ok(Expr::Interpolation { start, segments, values });
+            }
+        }
+    }
+
+    fn segment_text(token: &Token) -> String {
+        match &token.literal {
+            Some(Literal::String(s)) => s.clone(),
+            _ => String::new(),
+        }
+    }
+
     // Utility methods for parsing
     fn match_token(&mut self, types: &[TokenType]) -> bool {
-        for &t in types {
-            if self.check(t) {
+        for t in types {
+            if self.check(t.clone()) {
                 self.advance();
                 return true;
             }
@@ -234,18 +797,26 @@ impl Parser {
         }
     }
 
+    fn check_next(&self, token_type: TokenType) -> bool {
+        match self.tokens.get(self.current + 1) {
+            Some(token) => token.token_type == token_type,
+            None => false,
+        }
+    }
+
     fn consume(&mut self, token_type: TokenType, message: &str) -> Result<Token, ParseError> {
         if self.check(token_type) {
             Ok(self.advance())
         } else {
             Err(ParseError {
+                token: self.peek().clone(),
                 message: message.to_string(),
             })
         }
     }
 
     fn is_at_end(&self) -> bool {
-        self.peek().token_type == TokenType::EOF
+        self.peek().token_type == TokenType::Eof
     }
 
     fn advance(&mut self) -> Token {
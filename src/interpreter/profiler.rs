@@ -0,0 +1,138 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::time::{Duration, Instant};
+
+use crate::interpreter::stmt::Stmt;
+
+/// Totals for one function, over every call to it.
+#[derive(Debug, Clone, Default)]
+pub struct FunctionProfile {
+    pub calls: u64,
+    pub inclusive: Duration, // Time inside the function, callees included
+    pub exclusive: Duration, // Time in its own statements only
+}
+
+/// A call being timed.
+struct Activation {
+    function: String,
+    started: Instant,
+    children: Duration, // Time spent in calls it made
+}
+
+/// Records where a script spends its time: calls and time per function,
+/// hits per line, and time per call stack. The interpreter reports calls
+/// to it as they enter and leave `LoxFunction::call`.
+pub struct Profiler {
+    functions: HashMap<String, FunctionProfile>,
+    lines: BTreeMap<usize, u64>,
+    stacks: HashMap<String, Duration>, // Exclusive time per stack, joined with ';'
+    active: Vec<Activation>,
+}
+
+impl Profiler {
+    /// Creates a profiler and starts timing the script itself.
+    pub fn new() -> Self {
+        let mut profiler = Self {
+            functions: HashMap::new(),
+            lines: BTreeMap::new(),
+            stacks: HashMap::new(),
+            active: Vec::new(),
+        };
+        profiler.enter("<script>");
+        profiler
+    }
+
+    pub fn enter(&mut self, function: &str) {
+        self.functions.entry(function.to_string()).or_default().calls += 1;
+        self.active.push(Activation {
+            function: function.to_string(),
+            started: Instant::now(),
+            children: Duration::ZERO,
+        });
+    }
+
+    pub fn exit(&mut self) {
+        let activation = match self.active.pop() {
+            Some(activation) => activation,
+            None => return,
+        };
+        let elapsed = activation.started.elapsed();
+        let exclusive = elapsed.saturating_sub(activation.children);
+
+        // A recursive call's time is already inside its outermost call.
+        let recursive = self.active.iter().any(|outer| outer.function == activation.function);
+        let profile = self.functions.entry(activation.function.clone()).or_default();
+        if !recursive {
+            profile.inclusive += elapsed;
+        }
+        profile.exclusive += exclusive;
+
+        let mut stack: Vec<&str> = self.active.iter().map(|outer| outer.function.as_str()).collect();
+        stack.push(&activation.function);
+        *self.stacks.entry(stack.join(";")).or_default() += exclusive;
+
+        if let Some(caller) = self.active.last_mut() {
+            caller.children += elapsed;
+        }
+    }
+
+    /// Ends the calls a runtime error unwound through, keeping the script.
+    pub fn unwind(&mut self) {
+        while self.active.len() > 1 {
+            self.exit();
+        }
+    }
+
+    /// Counts a statement about to run against its line.
+    pub fn statement(&mut self, stmt: &Stmt) {
+        // A block is counted through its statements instead.
+        if !matches!(stmt, Stmt::Block(_)) {
+            *self.lines.entry(stmt.line()).or_default() += 1;
+        }
+    }
+
+    /// Stops timing the script. Call once it has finished.
+    pub fn finish(&mut self) {
+        while !self.active.is_empty() {
+            self.exit();
+        }
+    }
+
+    /// Formats the totals as a table, slowest function first, followed by
+    /// the hit count of each line that ran.
+    pub fn report(&self) -> String {
+        let mut functions: Vec<(&String, &FunctionProfile)> = self.functions.iter().collect();
+        functions.sort_by(|a, b| b.1.exclusive.cmp(&a.1.exclusive).then(a.0.cmp(b.0)));
+        let width = functions.iter().map(|(name, _)| name.len()).max().unwrap_or(0).max("Function".len());
+
+        let mut report = String::new();
+        let _ = writeln!(report, "{:<width$}  {:>8}  {:>14}  {:>14}", "Function", "Calls", "Inclusive ms", "Exclusive ms");
+        for (name, profile) in functions {
+            let _ = writeln!(
+                report,
+                "{:<width$}  {:>8}  {:>14.3}  {:>14.3}",
+                name,
+                profile.calls,
+                profile.inclusive.as_secs_f64() * 1000.0,
+                profile.exclusive.as_secs_f64() * 1000.0,
+            );
+        }
+
+        let _ = writeln!(report, "\n{:>6}  {:>10}", "Line", "Hits");
+        for (line, hits) in &self.lines {
+            let _ = writeln!(report, "{:>6}  {:>10}", line, hits);
+        }
+        report
+    }
+
+    /// Formats time per call stack in the folded format flame graph tools
+    /// read: one `outer;inner microseconds` line per stack.
+    pub fn folded(&self) -> String {
+        let mut stacks: Vec<(&String, &Duration)> = self.stacks.iter().collect();
+        stacks.sort();
+        stacks
+            .into_iter()
+            .map(|(stack, time)| format!("{} {}\n", stack, time.as_micros()))
+            .collect()
+    }
+}
@@ -0,0 +1,115 @@
+use std::rc::Rc;
+
+use crate::interpreter::native_module::NativeModule;
+use crate::interpreter::value::Value;
+
+/// xoshiro256** pseudo-random generator. It is small, fast and fully
+/// determined by its seed, so seeded scripts print the same output on
+/// every run and platform.
+#[derive(Debug, Clone)]
+pub struct Random {
+    state: [u64; 4],
+}
+
+impl Random {
+    /// Creates a generator from a seed, expanding it with SplitMix64.
+    pub fn new(seed: u64) -> Self {
+        let mut mix = seed;
+        let mut next = || {
+            mix = mix.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = mix;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^ (z >> 31)
+        };
+        Self {
+            state: [next(), next(), next(), next()],
+        }
+    }
+
+    /// Creates a generator seeded from the clock.
+    pub fn from_time() -> Self {
+        Self::new(chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default() as u64)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let result = self.state[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = self.state[1] << 17;
+        self.state[2] ^= self.state[0];
+        self.state[3] ^= self.state[1];
+        self.state[1] ^= self.state[2];
+        self.state[0] ^= self.state[3];
+        self.state[2] ^= t;
+        self.state[3] = self.state[3].rotate_left(45);
+        result
+    }
+
+    /// Returns a float in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns an integer in `[0, bound)` without modulo bias.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return value % bound;
+            }
+        }
+    }
+}
+
+/// Builds the `random` module. The generator lives on the interpreter, so
+/// `Interpreter::set_seed` fixes the output for a whole engine.
+pub fn module() -> Value {
+    let mut random = NativeModule::new("random");
+    random
+        .function("random", 0, |interpreter, _| Value::Number(interpreter.rng().next_f64()))
+        .function("randInt", 2, |interpreter, args| match (&args[0], &args[1]) {
+            (Value::Integer(low), Value::Integer(high)) if low <= high => {
+                let span = (*high as i128 - *low as i128 + 1) as u128;
+                let offset = if span > u64::MAX as u128 {
+                    interpreter.rng().next_u64()
+                } else {
+                    interpreter.rng().below(span as u64)
+                };
+                Value::Integer((*low as i128 + offset as i128) as i64)
+            }
+            (Value::Integer(_), Value::Integer(_)) => interpreter.native_error("randInt needs low <= high."),
+            _ => interpreter.native_error("randInt bounds must be integers."),
+        })
+        .function("choice", 1, |interpreter, args| match &args[0] {
+            Value::List(list) => {
+                let length = list.borrow().len();
+                if length == 0 {
+                    interpreter.native_error("Cannot choose from an empty list.");
+                }
+                let index = interpreter.rng().below(length as u64) as usize;
+                let chosen = list.borrow()[index].clone();
+                chosen
+            }
+            _ => interpreter.native_error("choice expects a list."),
+        })
+        .function("shuffle", 1, |interpreter, args| match &args[0] {
+            Value::List(list) => {
+                // Fisher-Yates, in place.
+                let length = list.borrow().len();
+                for i in (1..length).rev() {
+                    let j = interpreter.rng().below(i as u64 + 1) as usize;
+                    list.borrow_mut().swap(i, j);
+                }
+                args[0].clone()
+            }
+            _ => interpreter.native_error("shuffle expects a list."),
+        })
+        .function("seed", 1, |interpreter, args| match &args[0] {
+            Value::Integer(seed) => {
+                interpreter.set_seed(*seed as u64);
+                Value::Nil
+            }
+            _ => interpreter.native_error("Seed must be an integer."),
+        });
+    Value::Module(Rc::new(random))
+}
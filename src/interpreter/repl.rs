@@ -0,0 +1,115 @@
+use std::env;
+use std::path::PathBuf;
+
+use crate::interpreter::stmt::{Stmt, StmtPrint};
+use crate::interpreter::token::Token;
+use crate::interpreter::token_type::TokenType;
+
+/// A REPL meta-command, entered as a line starting with `:`.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Load(String),
+    Ast(String),
+    Env,
+    Reset,
+    Quit,
+    Help,
+}
+
+impl Command {
+    /// Parses a meta-command line, or returns an error message for the user.
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let (name, argument) = match line.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (line, ""),
+        };
+
+        match (name, argument.is_empty()) {
+            (":load", false) => Ok(Command::Load(argument.to_string())),
+            (":ast", false) => Ok(Command::Ast(argument.to_string())),
+            (":load", true) => Err("Usage: :load <file>".to_string()),
+            (":ast", true) => Err("Usage: :ast <code>".to_string()),
+            (":env", _) => Ok(Command::Env),
+            (":reset", _) => Ok(Command::Reset),
+            (":quit", _) | (":q", _) => Ok(Command::Quit),
+            (":help", _) => Ok(Command::Help),
+            _ => Err(format!("Unknown command '{}'. Type :help for a list.", name)),
+        }
+    }
+}
+
+pub const HELP: &str = "\
+:load <file>  Run a file in this session
+:ast <code>   Print the syntax tree of some code
+:env          List global variables
+:reset        Forget all definitions
+:quit         Exit the REPL";
+
+/// Returns true while `source` has unclosed parens, braces, brackets or
+/// strings, meaning the REPL should keep reading lines.
+pub fn is_incomplete(source: &str) -> bool {
+    let mut depth: i32 = 0;
+    let mut in_string = false;
+    let mut chars = source.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_string = true,
+            '(' | '{' | '[' => depth += 1,
+            ')' | '}' | ']' => depth -= 1,
+            '/' if chars.peek() == Some(&'/') => {
+                // Skip the comment up to the end of the line.
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    in_string || depth > 0
+}
+
+/// Lets a bare expression be typed without its trailing semicolon.
+pub fn complete_statement(source: &str) -> String {
+    let trimmed = source.trim_end();
+    if trimmed.is_empty() || trimmed.ends_with(';') || trimmed.ends_with('}') {
+        source.to_string()
+    } else {
+        format!("{};", trimmed)
+    }
+}
+
+/// Turns a lone expression statement into a print, so the REPL echoes
+/// its value.
+pub fn echo_expression(mut statements: Vec<Stmt>) -> Vec<Stmt> {
+    if let [Stmt::Expression(_)] = statements.as_slice() {
+        if let Some(Stmt::Expression(stmt)) = statements.pop() {
+            let line = stmt.expression.line();
+            statements.push(Stmt::Print(StmtPrint {
+                keyword: Token::new(TokenType::Print, "print".to_string(), None, line),
+                expression: stmt.expression,
+            }));
+        }
+    }
+    statements
+}
+
+/// The history file, `~/.lox_history`.
+pub fn history_path() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".lox_history"))
+}
@@ -1,14 +1,72 @@
 use std::collections::{HashMap, VecDeque};
-use crate::interpreter::{expr::Expr, stmt::Stmt, token::Token};
-use crate::interpreter::{lox::Lox, interpreter::Interpreter};
+use std::rc::Rc;
+use crate::interpreter::{expr::Expr, stmt::Stmt, stmt::StmtFunction, token::Token};
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::lint::Linter;
+use crate::interpreter::symbols::{SymbolIndex, SymbolKind};
 use crate::interpreter::expr::ExprVisitor;
-use crate::interpreter::stmt::StmtVisitor;
+use crate::interpreter::stmt::{
+    StmtBlock, StmtClass, StmtExpression, StmtIf, StmtPrint, StmtReturn, StmtTest, StmtVar,
+    StmtVisitor, StmtWhile,
+};
 
 pub struct Resolver<'a> {
     interpreter: &'a mut Interpreter,
-    scopes: VecDeque<HashMap<String, bool>>,
+    scopes: VecDeque<HashMap<String, Local>>,
     current_function: FunctionType,
     current_class: ClassType,
+    linter: Option<Linter>, // Set when resolving for `lox lint`
+    index: Option<SymbolIndex>, // Set when resolving for `lox lsp`
+    errors: Vec<(Token, String)>,
+}
+
+/// What the resolver knows about a name declared in a local scope.
+#[derive(Debug, Clone)]
+struct Local {
+    name: Option<Token>, // `None` for the implicit `this`
+    kind: LocalKind,
+    defined: bool,
+    used: bool,
+    assigned: bool,
+    arity: Option<usize>,         // Parameter count of a `fun` declaration
+    calls: Vec<(Token, usize)>,   // Calls through this name, with argument counts
+    symbol: Option<usize>,        // Id in the symbol index, if any
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LocalKind {
+    Variable,
+    Parameter,
+    Function,
+    Class,
+}
+
+impl Local {
+    fn new(name: &Token, kind: LocalKind, arity: Option<usize>) -> Self {
+        Self {
+            name: Some(name.clone()),
+            kind,
+            defined: false,
+            used: false,
+            assigned: false,
+            arity,
+            calls: Vec::new(),
+            symbol: None,
+        }
+    }
+
+    fn this() -> Self {
+        Self {
+            name: None,
+            kind: LocalKind::Variable,
+            defined: true,
+            used: true,
+            assigned: false,
+            arity: None,
+            calls: Vec::new(),
+            symbol: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -34,12 +92,54 @@ impl<'a> Resolver<'a> {
             scopes: VecDeque::new(),
             current_function: FunctionType::None,
             current_class: ClassType::None,
+            linter: None,
+            index: None,
+            errors: Vec::new(),
         }
     }
 
+    /// Creates a Resolver that also reports lint diagnostics.
+    pub fn with_linter(interpreter: &'a mut Interpreter, linter: Linter) -> Self {
+        let mut resolver = Self::new(interpreter);
+        resolver.linter = Some(linter);
+        resolver
+    }
+
+    /// Returns the linter, once resolution is done.
+    pub fn into_linter(self) -> Option<Linter> {
+        self.linter
+    }
+
+    /// Also records declarations and their uses in a `SymbolIndex`.
+    pub fn index_symbols(&mut self) {
+        self.index = Some(SymbolIndex::new());
+    }
+
+    /// Returns the symbol index, once resolution is done.
+    pub fn take_index(&mut self) -> Option<SymbolIndex> {
+        let mut index = self.index.take()?;
+        index.finish();
+        Some(index)
+    }
+
+    /// Returns the errors reported so far, with the token at fault.
+    pub fn errors(&self) -> &[(Token, String)] {
+        &self.errors
+    }
+
+    fn error(&mut self, token: &Token, message: &str) {
+        self.errors.push((token.clone(), message.to_string()));
+    }
+
     /// Resolves a list of statements.
     pub fn resolve(&mut self, statements: &[Stmt]) {
+        if let Some(linter) = &mut self.linter {
+            linter.check_unreachable(statements);
+        }
         for statement in statements {
+            if let Some(index) = &mut self.index {
+                index.touch(statement.line());
+            }
             self.resolve_stmt(statement);
         }
     }
@@ -55,18 +155,19 @@ impl<'a> Resolver<'a> {
     }
 
     /// Resolves a function, including its parameters and body.
-    fn resolve_function(&mut self, function: &Stmt, func_type: FunctionType) {
+    fn resolve_function(&mut self, function: &StmtFunction, func_type: FunctionType) {
         let enclosing_function = self.current_function;
         self.current_function = func_type;
 
         self.begin_scope();
-        if let Stmt::Function { params, body, .. } = function {
-            for param in params {
-                self.declare(param);
-                self.define(param);
-            }
-            self.resolve(body);
+        if let Some(index) = &mut self.index {
+            index.touch(function.name.line);
+        }
+        for param in &function.params {
+            self.declare(param, LocalKind::Parameter, None, format!("parameter {}", param.lexeme));
+            self.define(param);
         }
+        self.resolve(&function.body);
         self.end_scope();
         self.current_function = enclosing_function;
     }
@@ -74,30 +175,111 @@ impl<'a> Resolver<'a> {
     /// Begins a new scope.
     fn begin_scope(&mut self) {
         self.scopes.push_back(HashMap::new());
+        if let Some(index) = &mut self.index {
+            index.begin_scope();
+        }
     }
 
     /// Ends the current scope.
     fn end_scope(&mut self) {
-        self.scopes.pop_back();
+        if let Some(index) = &mut self.index {
+            index.end_scope();
+        }
+        let scope = self.scopes.pop_back();
+        if let (Some(scope), Some(linter)) = (scope, &mut self.linter) {
+            for local in scope.values() {
+                let name = match &local.name {
+                    Some(name) => name,
+                    None => continue,
+                };
+
+                if !local.used && !name.lexeme.starts_with('_') {
+                    let (rule, what) = match local.kind {
+                        LocalKind::Variable => ("unused-variable", "Variable"),
+                        LocalKind::Parameter => ("unused-parameter", "Parameter"),
+                        LocalKind::Function => ("unused-variable", "Function"),
+                        LocalKind::Class => ("unused-variable", "Class"),
+                    };
+                    linter.report(rule, name.line, format!("{} '{}' is never used.", what, name.lexeme));
+                }
+
+                // A function that is never reassigned has a known arity.
+                if let (Some(arity), false) = (local.arity, local.assigned) {
+                    for (call, arguments) in &local.calls {
+                        linter.check_arity(call, arity, 0, *arguments);
+                    }
+                }
+            }
+        }
     }
 
-    /// Declares a variable in the current scope.
-    fn declare(&mut self, name: &Token) {
+    /// Declares a variable in the current scope. At the top level, only
+    /// the linter and symbol index keep track of it. `detail` describes the
+    /// declaration for the index.
+    fn declare(&mut self, name: &Token, kind: LocalKind, arity: Option<usize>, detail: String) {
+        let symbol = self.index.as_mut().map(|index| {
+            let kind = match kind {
+                LocalKind::Variable => SymbolKind::Variable,
+                LocalKind::Parameter => SymbolKind::Parameter,
+                LocalKind::Function => SymbolKind::Function,
+                LocalKind::Class => SymbolKind::Class,
+            };
+            index.declare(name, kind, detail, arity)
+        });
+
+        if self.scopes.is_empty() {
+            if let Some(linter) = &mut self.linter {
+                linter.declare_global(name, arity);
+            }
+            return;
+        }
+
+        if let Some(linter) = &mut self.linter {
+            let enclosing = self.scopes.len() - 1;
+            let shadows = self.scopes.iter().take(enclosing).any(|scope| scope.contains_key(&name.lexeme))
+                || linter.is_global(&name.lexeme);
+            if shadows {
+                linter.report(
+                    "shadowed-name",
+                    name.line,
+                    format!("'{}' shadows a variable in an enclosing scope.", name.lexeme),
+                );
+            }
+        }
+
         if let Some(scope) = self.scopes.back_mut() {
-            if scope.contains_key(&name.lexeme) {
-                Lox::error(name, "Variable already declared in this scope.");
+            let redeclared = scope.contains_key(&name.lexeme);
+            let mut local = Local::new(name, kind, arity);
+            local.symbol = symbol;
+            scope.insert(name.lexeme.clone(), local);
+            if redeclared {
+                self.error(name, "Variable already declared in this scope.");
             }
-            scope.insert(name.lexeme.clone(), false);
         }
     }
 
     /// Defines a variable in the current scope.
     fn define(&mut self, name: &Token) {
-        if let Some(scope) = self.scopes.back_mut() {
-            scope.insert(name.lexeme.clone(), true);
+        if let Some(local) = self.scopes.back_mut().and_then(|scope| scope.get_mut(&name.lexeme)) {
+            local.defined = true;
+        }
+    }
+
+    /// Records a use of `name` in the symbol index.
+    fn reference(&mut self, name: &Token) {
+        if self.index.is_some() {
+            let symbol = self.local_mut(&name.lexeme).and_then(|local| local.symbol);
+            if let Some(index) = &mut self.index {
+                index.reference(name, symbol);
+            }
         }
     }
 
+    /// Finds the innermost local declaration of `name`.
+    fn local_mut(&mut self, name: &str) -> Option<&mut Local> {
+        self.scopes.iter_mut().rev().find_map(|scope| scope.get_mut(name))
+    }
+
     /// Resolves a local variable by checking its scope.
     fn resolve_local(&mut self, expr: &Expr, name: &Token) {
         for (i, scope) in self.scopes.iter().rev().enumerate() {
@@ -109,74 +291,185 @@ impl<'a> Resolver<'a> {
     }
 }
 
-impl<'a> ExprVisitor for Resolver<'a> {
-    type Output = ();
-
-    fn visit_variable_expr(&mut self, expr: &Expr::Variable) {
+impl<'a> ExprVisitor<()> for Resolver<'a> {
+    fn visit_variable_expr(&mut self, expr: &Expr) {
+        let Expr::Variable { name, .. } = expr else {
+            return;
+        };
         if let Some(scope) = self.scopes.back() {
-            if let Some(false) = scope.get(&expr.name.lexeme) {
-                Lox::error(&expr.name, "Cannot read variable in its own initializer.");
+            if let Some(Local { defined: false, .. }) = scope.get(&name.lexeme) {
+                self.error(name, "Cannot read variable in its own initializer.");
             }
         }
-        self.resolve_local(expr, &expr.name);
+        self.reference(name);
+        if let Some(local) = self.local_mut(&name.lexeme) {
+            local.used = true;
+        }
+        self.resolve_local(expr, name);
     }
 
-    fn visit_assign_expr(&mut self, expr: &Expr::Assign) {
-        self.resolve_expr(&expr.value);
-        self.resolve_local(expr, &expr.name);
+    fn visit_assign_expr(&mut self, expr: &Expr) {
+        let Expr::Assign { name, value, .. } = expr else {
+            return;
+        };
+        self.resolve_expr(value);
+        if let Some(local) = self.local_mut(&name.lexeme) {
+            local.assigned = true;
+        } else if let Some(linter) = &mut self.linter {
+            linter.assign_global(name);
+        }
+        self.reference(name);
+        self.resolve_local(expr, name);
     }
 
-    fn visit_binary_expr(&mut self, expr: &Expr::Binary) {
-        self.resolve_expr(&expr.left);
-        self.resolve_expr(&expr.right);
+    fn visit_binary_expr(&mut self, expr: &Expr) {
+        let Expr::Binary { left, operator, right } = expr else {
+            return;
+        };
+        if let Some(linter) = &mut self.linter {
+            linter.check_comparison(left, operator, right);
+        }
+        self.resolve_expr(left);
+        self.resolve_expr(right);
     }
 
-    fn visit_call_expr(&mut self, expr: &Expr::Call) {
-        self.resolve_expr(&expr.callee);
-        for argument in &expr.arguments {
+    fn visit_call_expr(&mut self, expr: &Expr) {
+        let Expr::Call { callee, arguments, .. } = expr else {
+            return;
+        };
+        // Record calls through plain names so their arity can be checked
+        // once it is known whether the name is ever reassigned.
+        if let (Expr::Variable { name, .. }, true) = (&**callee, self.linter.is_some()) {
+            let count = arguments.len();
+            if let Some(local) = self.local_mut(&name.lexeme) {
+                local.calls.push((name.clone(), count));
+            } else if let Some(linter) = &mut self.linter {
+                linter.call_global(name.clone(), count);
+            }
+        }
+        self.resolve_expr(callee);
+        for argument in arguments {
             self.resolve_expr(argument);
         }
     }
 
-    fn visit_grouping_expr(&mut self, expr: &Expr::Grouping) {
-        self.resolve_expr(&expr.expression);
+    fn visit_index_expr(&mut self, expr: &Expr) {
+        if let Expr::Index { object, index, .. } = expr {
+            self.resolve_expr(object);
+            self.resolve_expr(index);
+        }
+    }
+
+    fn visit_grouping_expr(&mut self, expr: &Expr) {
+        if let Expr::Grouping { expression } = expr {
+            self.resolve_expr(expression);
+        }
     }
 
-    fn visit_literal_expr(&mut self, _expr: &Expr::Literal) {}
+    fn visit_literal_expr(&mut self, _expr: &Expr) {}
+
+    fn visit_logical_expr(&mut self, expr: &Expr) {
+        if let Expr::Logical { left, right, .. } = expr {
+            self.resolve_expr(left);
+            self.resolve_expr(right);
+        }
+    }
 
-    fn visit_unary_expr(&mut self, expr: &Expr::Unary) {
-        self.resolve_expr(&expr.right);
+    fn visit_unary_expr(&mut self, expr: &Expr) {
+        if let Expr::Unary { right, .. } = expr {
+            self.resolve_expr(right);
+        }
     }
-}
 
-impl<'a> StmtVisitor for Resolver<'a> {
-    type Output = ();
+    fn visit_get_expr(&mut self, expr: &Expr) {
+        if let Expr::Get { object, .. } = expr {
+            self.resolve_expr(object);
+        }
+    }
+
+    fn visit_set_expr(&mut self, expr: &Expr) {
+        if let Expr::Set { object, value, .. } = expr {
+            self.resolve_expr(value);
+            self.resolve_expr(object);
+        }
+    }
 
-    fn visit_block_stmt(&mut self, stmt: &Stmt::Block) {
+    fn visit_super_expr(&mut self, expr: &Expr) {
+        let Expr::Super { method, .. } = expr else {
+            return;
+        };
+        match self.current_class {
+            ClassType::None => self.error(method, "Can't use 'super' outside of a class."),
+            ClassType::Class => self.error(method, "Can't use 'super' in a class with no superclass."),
+            ClassType::Subclass => {}
+        }
+        for (i, scope) in self.scopes.iter().rev().enumerate() {
+            if scope.contains_key("super") {
+                self.interpreter.resolve(expr, i);
+                return;
+            }
+        }
+    }
+
+    fn visit_this_expr(&mut self, expr: &Expr) {
+        let Expr::This { keyword, .. } = expr else {
+            return;
+        };
+        for (i, scope) in self.scopes.iter().rev().enumerate() {
+            if scope.contains_key("this") {
+                self.interpreter.resolve(expr, i);
+                return;
+            }
+        }
+        // Inside a class, only class methods are resolved outside the scope
+        // that defines `this`.
+        match self.current_class {
+            ClassType::None => self.error(keyword, "Can't use 'this' outside of a class."),
+            ClassType::Class | ClassType::Subclass => self.error(keyword, "Can't use 'this' in a class method."),
+        }
+    }
+
+    fn visit_interpolation_expr(&mut self, expr: &Expr) {
+        if let Expr::Interpolation { values, .. } = expr {
+            for value in values {
+                self.resolve_expr(value);
+            }
+        }
+    }
+
+    fn visit_lambda_expr(&mut self, expr: &Expr) {
+        if let Expr::Lambda { declaration } = expr {
+            self.resolve_function(declaration, FunctionType::Function);
+        }
+    }
+}
+
+impl<'a> StmtVisitor<()> for Resolver<'a> {
+    fn visit_block_stmt(&mut self, stmt: &StmtBlock) {
         self.begin_scope();
         self.resolve(&stmt.statements);
         self.end_scope();
     }
 
-    fn visit_var_stmt(&mut self, stmt: &Stmt::Var) {
-        self.declare(&stmt.name);
+    fn visit_var_stmt(&mut self, stmt: &StmtVar) {
+        self.declare(&stmt.name, LocalKind::Variable, None, format!("var {}", stmt.name.lexeme));
         if let Some(initializer) = &stmt.initializer {
             self.resolve_expr(initializer);
         }
         self.define(&stmt.name);
     }
 
-    fn visit_function_stmt(&mut self, stmt: &Stmt::Function) {
-        self.declare(&stmt.name);
+    fn visit_function_stmt(&mut self, stmt: &Rc<StmtFunction>) {
+        self.declare(&stmt.name, LocalKind::Function, Some(stmt.params.len()), signature("fun ", stmt));
         self.define(&stmt.name);
         self.resolve_function(stmt, FunctionType::Function);
     }
 
-    fn visit_expression_stmt(&mut self, stmt: &Stmt::Expression) {
+    fn visit_expression_stmt(&mut self, stmt: &StmtExpression) {
         self.resolve_expr(&stmt.expression);
     }
 
-    fn visit_if_stmt(&mut self, stmt: &Stmt::If) {
+    fn visit_if_stmt(&mut self, stmt: &StmtIf) {
         self.resolve_expr(&stmt.condition);
         self.resolve_stmt(&stmt.then_branch);
         if let Some(else_branch) = &stmt.else_branch {
@@ -184,40 +477,93 @@ impl<'a> StmtVisitor for Resolver<'a> {
         }
     }
 
-    fn visit_while_stmt(&mut self, stmt: &Stmt::While) {
+    fn visit_print_stmt(&mut self, stmt: &StmtPrint) {
+        self.resolve_expr(&stmt.expression);
+    }
+
+    fn visit_while_stmt(&mut self, stmt: &StmtWhile) {
         self.resolve_expr(&stmt.condition);
         self.resolve_stmt(&stmt.body);
+        if let Some(increment) = &stmt.increment {
+            self.resolve_expr(increment);
+        }
     }
 
-    fn visit_return_stmt(&mut self, stmt: &Stmt::Return) {
-        if self.current_function == FunctionType::None {
-            Lox::error(&stmt.keyword, "Cannot return from top-level code.");
+    fn visit_return_stmt(&mut self, stmt: &StmtReturn) {
+        if matches!(self.current_function, FunctionType::None) {
+            self.error(&stmt.keyword, "Cannot return from top-level code.");
         }
         if let Some(value) = &stmt.value {
+            if matches!(self.current_function, FunctionType::Initializer) {
+                self.error(&stmt.keyword, "Can't return a value from an initializer.");
+            }
             self.resolve_expr(value);
         }
     }
 
-    fn visit_class_stmt(&mut self, stmt: &Stmt::Class) {
+    fn visit_test_stmt(&mut self, stmt: &StmtTest) {
+        // `lox test` only finds tests declared at the top level.
+        if !self.scopes.is_empty() || !matches!(self.current_function, FunctionType::None) {
+            self.error(&stmt.keyword, "Tests must be declared at the top level.");
+        }
+        self.begin_scope();
+        self.resolve(&stmt.body);
+        self.end_scope();
+    }
+
+    fn visit_class_stmt(&mut self, stmt: &StmtClass) {
         let enclosing_class = self.current_class;
         self.current_class = ClassType::Class;
 
-        self.declare(&stmt.name);
+        // Calling a class runs `init`; without one, a subclass inherits its
+        // superclass's arity, which is not known here.
+        let arity = match stmt.methods.iter().find(|method| method.name.lexeme == "init") {
+            Some(init) => Some(init.params.len()),
+            None if stmt.superclass.is_none() => Some(0),
+            None => None,
+        };
+        let detail = match &stmt.superclass {
+            Some(Expr::Variable { name, .. }) => format!("class {} < {}", stmt.name.lexeme, name.lexeme),
+            _ => format!("class {}", stmt.name.lexeme),
+        };
+        self.declare(&stmt.name, LocalKind::Class, arity, detail);
         self.define(&stmt.name);
 
+        if let Some(index) = &mut self.index {
+            let class = index.symbols().len() - 1;
+            let members = stmt.methods.iter().map(|method| ("", method))
+                .chain(stmt.class_methods.iter().map(|method| ("class ", method)))
+                .chain(stmt.getters.iter().map(|getter| ("get ", getter)))
+                .chain(stmt.setters.iter().map(|setter| ("set ", setter)));
+            for (prefix, method) in members {
+                index.declare_method(&method.name, signature(prefix, method), method.params.len(), class);
+            }
+        }
+
         if let Some(superclass) = &stmt.superclass {
-            if let Expr::Variable { name } = superclass {
+            if let Expr::Variable { name, .. } = superclass {
                 if stmt.name.lexeme == name.lexeme {
-                    Lox::error(name, "A class cannot inherit from itself.");
+                    self.error(name, "A class cannot inherit from itself.");
                 }
             }
             self.current_class = ClassType::Subclass;
             self.resolve_expr(superclass);
+
+            // Methods close over a scope that defines `super`.
+            self.begin_scope();
+            if let Some(scope) = self.scopes.back_mut() {
+                scope.insert("super".to_string(), Local::this());
+            }
+        }
+
+        // Class methods are called on the class itself, so they see no `this`.
+        for method in &stmt.class_methods {
+            self.resolve_function(method, FunctionType::Method);
         }
 
         self.begin_scope();
         if let Some(scope) = self.scopes.back_mut() {
-            scope.insert("this".to_string(), true);
+            scope.insert("this".to_string(), Local::this());
         }
 
         for method in &stmt.methods {
@@ -229,7 +575,20 @@ impl<'a> StmtVisitor for Resolver<'a> {
             self.resolve_function(method, declaration);
         }
 
+        for accessor in stmt.getters.iter().chain(&stmt.setters) {
+            self.resolve_function(accessor, FunctionType::Method);
+        }
+
         self.end_scope();
+        if stmt.superclass.is_some() {
+            self.end_scope();
+        }
         self.current_class = enclosing_class;
     }
-}
\ No newline at end of file
+}
+
+/// Describes a function as it is declared, such as `fun add(a, b)`.
+pub fn signature(prefix: &str, function: &StmtFunction) -> String {
+    let params: Vec<&str> = function.params.iter().map(|param| param.lexeme.as_str()).collect();
+    format!("{}{}({})", prefix, function.name.lexeme, params.join(", "))
+}
@@ -1,21 +1,15 @@
-use crate::interpreter::value::Object;
+use crate::interpreter::value::Value;
 
+/// Carries a `return` statement's value out of the function body that
+/// ran it, as the error side of a statement's result.
 #[derive(Debug, Clone)]
 pub struct Return {
-    pub value: Option<Object>,
+    pub value: Value,
 }
 
 impl Return {
-    /// Creates a new Return exception with a value.
-    pub fn new(value: Option<Object>) -> Self {
+    /// Creates a new Return with the returned value.
+    pub fn new(value: Value) -> Self {
         Self { value }
     }
 }
-
-impl std::fmt::Display for Return {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Return exception with value: {:?}", self.value)
-    }
-}
-
-impl std::error::Error for Return {}
\ No newline at end of file
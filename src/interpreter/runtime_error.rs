@@ -1,5 +1,6 @@
 use crate::interpreter::token::Token;
 use std::fmt;
+use std::panic;
 
 #[derive(Debug, Clone)]
 pub struct RuntimeError {
@@ -12,6 +13,13 @@ impl RuntimeError {
     pub fn new(token: Token, message: String) -> Self {
         Self { token, message }
     }
+
+    /// Unwinds to the nearest `Interpreter::interpret` or `catch_error`
+    /// with this error as the payload. Unlike `panic!`, this skips the
+    /// panic hook, so nothing is printed on the way out.
+    pub fn raise(self) -> ! {
+        panic::resume_unwind(Box::new(self))
+    }
 }
 
 impl fmt::Display for RuntimeError {
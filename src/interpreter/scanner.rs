@@ -1,15 +1,35 @@
 use std::collections::HashMap;
 
+use crate::interpreter::bigint::BigInt;
 use crate::interpreter::token::Token;
 use crate::interpreter::token_type::TokenType;
 use crate::interpreter::token::Literal;
 
+/// A `//` comment kept as trivia for tools such as the formatter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    pub line: usize,
+    pub text: String,   // Including the leading `//`
+    pub trailing: bool, // Follows code on the same line
+}
+
+/// A lexical error, also reported on stderr as it is found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanError {
+    pub line: usize,
+    pub message: String,
+}
+
 pub struct Scanner {
     source: String,
     tokens: Vec<Token>,
     start: usize,
     current: usize,
     line: usize,
+    line_start: usize, // Byte offset of the first character of the current line
+    interpolations: Vec<usize>, // Open `${` nesting, counting unmatched '{' inside each
+    comments: Vec<Comment>,
+    errors: Vec<ScanError>,
 }
 
 impl Scanner {
@@ -20,9 +40,23 @@ impl Scanner {
             start: 0,
             current: 0,
             line: 1,
+            line_start: 0,
+            interpolations: Vec::new(),
+            comments: Vec::new(),
+            errors: Vec::new(),
         }
     }
 
+    /// Returns the errors found so far.
+    pub fn errors(&self) -> &[ScanError] {
+        &self.errors
+    }
+
+    /// Returns the comments seen so far, in source order.
+    pub fn comments(&self) -> &[Comment] {
+        &self.comments
+    }
+
     pub fn scan_tokens(&mut self) -> Vec<Token> {
         while !self.is_at_end() {
             self.start = self.current;
@@ -43,14 +77,44 @@ impl Scanner {
         match c {
             '(' => self.add_token(TokenType::LeftParen),
             ')' => self.add_token(TokenType::RightParen),
-            '{' => self.add_token(TokenType::LeftBrace),
-            '}' => self.add_token(TokenType::RightBrace),
+            '{' => {
+                if let Some(depth) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                self.add_token(TokenType::LeftBrace);
+            }
+            '}' => {
+                if self.interpolations.last() == Some(&0) {
+                    // Closes a `${...}`: carry on scanning the enclosing string.
+                    self.interpolations.pop();
+                    self.string();
+                } else {
+                    if let Some(depth) = self.interpolations.last_mut() {
+                        *depth -= 1;
+                    }
+                    self.add_token(TokenType::RightBrace);
+                }
+            }
+            '[' => self.add_token(TokenType::LeftBracket),
+            ']' => self.add_token(TokenType::RightBracket),
             ',' => self.add_token(TokenType::Comma),
             '.' => self.add_token(TokenType::Dot),
             '-' => self.add_token(TokenType::Minus),
             '+' => self.add_token(TokenType::Plus),
             ';' => self.add_token(TokenType::Semicolon),
             '*' => self.add_token(TokenType::Star),
+            '%' => self.add_token(TokenType::Percent),
+            '&' => self.add_token(TokenType::Ampersand),
+            '|' => self.add_token(TokenType::Pipe),
+            '^' => self.add_token(TokenType::Caret),
+            '~' => {
+                let is_slash = self.match_char('/');
+                self.add_token(if is_slash {
+                    TokenType::TildeSlash
+                } else {
+                    TokenType::Tilde
+                });
+            }
             '!' => {
                 let is_equal = self.match_char('=');
                 self.add_token(if is_equal {
@@ -60,48 +124,60 @@ impl Scanner {
                 });
             }
             '=' => {
-                let is_equal = self.match_char('=');
-                self.add_token(if is_equal {
+                let token_type = if self.match_char('=') {
                     TokenType::EqualEqual
+                } else if self.match_char('>') {
+                    TokenType::Arrow
                 } else {
                     TokenType::Equal
-                });
+                };
+                self.add_token(token_type);
             }
             '<' => {
-                let is_equal = self.match_char('=');
-                self.add_token(if is_equal {
+                let token_type = if self.match_char('=') {
                     TokenType::LessEqual
+                } else if self.match_char('<') {
+                    TokenType::LessLess
                 } else {
                     TokenType::Less
-                });
+                };
+                self.add_token(token_type);
             }
             '>' => {
-                let is_equal = self.match_char('=');
-                self.add_token(if is_equal {
+                let token_type = if self.match_char('=') {
                     TokenType::GreaterEqual
+                } else if self.match_char('>') {
+                    TokenType::GreaterGreater
                 } else {
                     TokenType::Greater
-                });
+                };
+                self.add_token(token_type);
             }
             '/' => {
                 if self.match_char('/') {
                     while self.peek() != '\n' && !self.is_at_end() {
                         self.advance();
                     }
+                    let trailing = self.tokens.last().is_some_and(|token| token.line == self.line);
+                    self.comments.push(Comment {
+                        line: self.line,
+                        text: self.source[self.start..self.current].trim_end().to_string(),
+                        trailing,
+                    });
                 } else {
                     self.add_token(TokenType::Slash);
                 }
             }
             ' ' | '\r' | '\t' => {} // Ignore whitespace
-            '\n' => self.line += 1,
+            '\n' => self.new_line(),
             '"' => self.string(),
             _ => {
-                if c.is_digit(10) {
+                if c.is_ascii_digit() {
                     self.number();
                 } else if c.is_alphabetic() || c == '_' {
                     self.identifier();
                 } else {
-                    eprintln!("Line {}: Unexpected character '{}'", self.line, c);
+                    self.error(format!("Unexpected character '{}'", c));
                 }
             }
         }
@@ -119,56 +195,137 @@ impl Scanner {
     }
 
     fn number(&mut self) {
-        while self.peek().is_digit(10) {
+        while self.peek().is_ascii_digit() {
             self.advance();
         }
 
-        if self.peek() == '.' && self.peek_next().is_digit(10) {
+        if self.peek() == '.' && self.peek_next().is_ascii_digit() {
             self.advance();
-            while self.peek().is_digit(10) {
+            while self.peek().is_ascii_digit() {
                 self.advance();
             }
+
+            let value: f64 = self.source[self.start..self.current]
+                .parse()
+                .expect("Failed to parse number");
+            self.add_token_with_literal(TokenType::Number, Some(Literal::Number(value)));
+            return;
         }
 
-        let value: f64 = self.source[self.start..self.current]
-            .parse()
-            .expect("Failed to parse number");
-        self.add_token_with_literal(TokenType::Number, Some(Literal::Number(value)));
+        // Literals without a decimal point are integers, big ones included.
+        let text = &self.source[self.start..self.current];
+        let literal = match text.parse::<i64>() {
+            Ok(value) => Literal::Integer(value),
+            Err(_) => Literal::BigInteger(BigInt::parse(text).expect("Failed to parse integer")),
+        };
+        self.add_token_with_literal(TokenType::Integer, Some(literal));
     }
 
+    /// Scans a string segment, processing escapes. A segment ending at `${`
+    /// becomes an `Interpolation` token; the parser then reads the embedded
+    /// expression and the scanner resumes the string at the matching '}'.
     fn string(&mut self) {
-        while self.peek() != '"' && !self.is_at_end() {
-            if self.peek() == '\n' {
-                self.line += 1;
+        let mut value = String::new();
+
+        loop {
+            if self.is_at_end() {
+                self.error("Unterminated string".to_string());
+                self.interpolations.clear();
+                return;
+            }
+
+            let c = self.advance();
+            match c {
+                '"' => break,
+                '\\' => {
+                    if let Some(escaped) = self.escape() {
+                        value.push(escaped);
+                    }
+                }
+                '$' if self.peek() == '{' => {
+                    self.advance();
+                    self.interpolations.push(0);
+                    self.add_token_with_literal(TokenType::Interpolation, Some(Literal::String(value)));
+                    return;
+                }
+                _ => {
+                    if c == '\n' {
+                        self.new_line();
+                    }
+                    value.push(c);
+                }
             }
-            self.advance();
         }
 
+        self.add_token_with_literal(TokenType::String, Some(Literal::String(value)));
+    }
+
+    /// Decodes the escape sequence following a backslash.
+    fn escape(&mut self) -> Option<char> {
         if self.is_at_end() {
-            eprintln!("Line {}: Unterminated string", self.line);
-            return;
+            return None;
+        }
+
+        match self.advance() {
+            'n' => Some('\n'),
+            't' => Some('\t'),
+            'r' => Some('\r'),
+            '0' => Some('\0'),
+            '"' => Some('"'),
+            '\\' => Some('\\'),
+            '$' => Some('$'),
+            'u' => {
+                if !self.match_char('{') {
+                    self.error("Expect '{' after '\\u'.".to_string());
+                    return None;
+                }
+                let mut digits = String::new();
+                while self.peek().is_ascii_hexdigit() && digits.len() < 6 {
+                    digits.push(self.advance());
+                }
+                if !self.match_char('}') {
+                    self.error("Unterminated unicode escape.".to_string());
+                    return None;
+                }
+                let escaped = u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32);
+                if escaped.is_none() {
+                    self.error(format!("Invalid unicode escape '\\u{{{}}}'.", digits));
+                }
+                escaped
+            }
+            other => {
+                self.error(format!("Invalid escape sequence '\\{}'.", other));
+                None
+            }
         }
+    }
 
-        self.advance(); // Closing "
+    fn new_line(&mut self) {
+        self.line += 1;
+        self.line_start = self.current;
+    }
 
-        let value = &self.source[self.start + 1..self.current - 1];
-        self.add_token_with_literal(TokenType::String, Some(Literal::String(value.to_string())));
+    /// Records an error for `errors`; callers decide how to report it.
+    fn error(&mut self, message: String) {
+        self.errors.push(ScanError { line: self.line, message });
     }
 
     fn match_char(&mut self, expected: char) -> bool {
-        if self.is_at_end() || self.source.chars().nth(self.current) != Some(expected) {
+        if self.is_at_end() || self.peek() != expected {
             return false;
         }
-        self.current += 1;
+        self.current += expected.len_utf8();
         true
     }
 
+    // `current` and `start` are byte offsets, so slicing `source` between
+    // them always lands on character boundaries.
     fn peek(&self) -> char {
-        self.source.chars().nth(self.current).unwrap_or('\0')
+        self.source[self.current..].chars().next().unwrap_or('\0')
     }
 
     fn peek_next(&self) -> char {
-        self.source.chars().nth(self.current + 1).unwrap_or('\0')
+        self.source[self.current..].chars().nth(1).unwrap_or('\0')
     }
 
     fn is_at_end(&self) -> bool {
@@ -176,8 +333,8 @@ impl Scanner {
     }
 
     fn advance(&mut self) -> char {
-        let c = self.source.chars().nth(self.current).unwrap();
-        self.current += 1;
+        let c = self.peek();
+        self.current += c.len_utf8();
         c
     }
 
@@ -187,13 +344,16 @@ impl Scanner {
 
     fn add_token_with_literal(&mut self, token_type: TokenType, literal: Option<Literal>) {
         let text = self.source[self.start..self.current].to_string();
-        self.tokens.push(Token::new(token_type, text, literal, self.line));
+        let mut token = Token::new(token_type, text, literal, self.line);
+        // `start` and `line_start` are byte offsets; a string spanning lines starts before the line
+        token.column = self.source.get(self.line_start..self.start).map_or(0, |before| before.chars().count());
+        self.tokens.push(token);
     }
 }
 
 // Keywords map
 lazy_static::lazy_static! {
-    static ref KEYWORDS: HashMap<&'static str, TokenType> = {
+    pub static ref KEYWORDS: HashMap<&'static str, TokenType> = {
         let mut map = HashMap::new();
         map.insert("and", TokenType::And);
         map.insert("class", TokenType::Class);
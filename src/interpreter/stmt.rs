@@ -1,3 +1,6 @@
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
 use crate::interpreter::token::Token;
 use crate::interpreter::expr::Expr;
 
@@ -5,28 +8,47 @@ pub trait StmtVisitor<R> {
     fn visit_block_stmt(&mut self, stmt: &StmtBlock) -> R;
     fn visit_class_stmt(&mut self, stmt: &StmtClass) -> R;
     fn visit_expression_stmt(&mut self, stmt: &StmtExpression) -> R;
-    fn visit_function_stmt(&mut self, stmt: &StmtFunction) -> R;
+    fn visit_function_stmt(&mut self, stmt: &Rc<StmtFunction>) -> R;
     fn visit_if_stmt(&mut self, stmt: &StmtIf) -> R;
     fn visit_print_stmt(&mut self, stmt: &StmtPrint) -> R;
     fn visit_return_stmt(&mut self, stmt: &StmtReturn) -> R;
+    fn visit_test_stmt(&mut self, stmt: &StmtTest) -> R;
     fn visit_var_stmt(&mut self, stmt: &StmtVar) -> R;
     fn visit_while_stmt(&mut self, stmt: &StmtWhile) -> R;
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Stmt {
     Block(StmtBlock),
     Class(StmtClass),
     Expression(StmtExpression),
-    Function(StmtFunction),
+    Function(Rc<StmtFunction>),
     If(StmtIf),
     Print(StmtPrint),
     Return(StmtReturn),
+    Test(StmtTest),
     Var(StmtVar),
     While(StmtWhile),
 }
 
 impl Stmt {
+    /// Returns the line the statement starts on.
+    pub fn line(&self) -> usize {
+        match self {
+            Stmt::Block(stmt) => stmt.brace.line,
+            Stmt::Class(stmt) => stmt.name.line,
+            Stmt::Expression(stmt) => stmt.expression.line(),
+            Stmt::Function(stmt) => stmt.name.line,
+            Stmt::If(stmt) => stmt.keyword.line,
+            Stmt::Print(stmt) => stmt.keyword.line,
+            Stmt::Return(stmt) => stmt.keyword.line,
+            Stmt::Test(stmt) => stmt.keyword.line,
+            Stmt::Var(stmt) => stmt.name.line,
+            Stmt::While(stmt) => stmt.keyword.line,
+        }
+    }
+
     pub fn accept<R>(&self, visitor: &mut dyn StmtVisitor<R>) -> R {
         match self {
             Stmt::Block(stmt) => visitor.visit_block_stmt(stmt),
@@ -36,6 +58,7 @@ impl Stmt {
             Stmt::If(stmt) => visitor.visit_if_stmt(stmt),
             Stmt::Print(stmt) => visitor.visit_print_stmt(stmt),
             Stmt::Return(stmt) => visitor.visit_return_stmt(stmt),
+            Stmt::Test(stmt) => visitor.visit_test_stmt(stmt),
             Stmt::Var(stmt) => visitor.visit_var_stmt(stmt),
             Stmt::While(stmt) => visitor.visit_while_stmt(stmt),
         }
@@ -43,7 +66,7 @@ impl Stmt {
 }
 
 impl StmtFunction {
-    pub fn accept<R>(&self, visitor: &mut dyn StmtVisitor<R>) -> R {
+    pub fn accept<R>(self: &Rc<Self>, visitor: &mut dyn StmtVisitor<R>) -> R {
         visitor.visit_function_stmt(self)
     }
 }
@@ -52,55 +75,101 @@ impl StmtFunction {
 // Nested Stmt structs
 
 #[derive(Debug)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StmtBlock {
+    pub brace: Token, // The opening '{', or `for` when desugared from a for loop
     pub statements: Vec<Stmt>,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StmtClass {
     pub name: Token,
     pub superclass: Option<Expr>,
-    pub methods: Vec<StmtFunction>,
+    pub methods: Vec<Rc<StmtFunction>>,
+    pub class_methods: Vec<Rc<StmtFunction>>, // `class name(...) { ... }`
+    pub getters: Vec<Rc<StmtFunction>>,       // `name { ... }`
+    pub setters: Vec<Rc<StmtFunction>>,       // `name=(value) { ... }`
+    pub doc: Option<String>,                  // From `///` comments, when parsed with them
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StmtExpression {
     pub expression: Expr,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StmtFunction {
     pub name: Token,
     pub params: Vec<Token>,
     pub body: Vec<Stmt>,
+    pub doc: Option<String>, // From `///` comments, when parsed with them
+}
+
+// Anonymous functions live inside `Expr::Lambda`, which has to be hashable.
+// A declaration is parsed once and shared through `Rc`, so functions are
+// compared by identity: two with the same name and parameters but different
+// bodies are different functions.
+impl PartialEq for StmtFunction {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Eq for StmtFunction {}
+
+impl Hash for StmtFunction {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::ptr::hash(self, state);
+    }
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StmtIf {
+    pub keyword: Token,
     pub condition: Expr,
     pub then_branch: Box<Stmt>,
     pub else_branch: Option<Box<Stmt>>,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StmtPrint {
+    pub keyword: Token,
     pub expression: Expr,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StmtReturn {
     pub keyword: Token,
     pub value: Option<Expr>,
 }
 
+/// A `test "name" { ... }` declaration, run only by `lox test`.
+#[derive(Debug)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StmtTest {
+    pub keyword: Token,
+    pub name: String,
+    pub body: Vec<Stmt>,
+}
+
 #[derive(Debug)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StmtVar {
     pub name: Token,
     pub initializer: Option<Expr>,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StmtWhile {
+    pub keyword: Token, // `while`, or `for` when desugared from a for loop
     pub condition: Expr,
     pub body: Box<Stmt>,
+    pub increment: Option<Expr>, // A for loop's increment, run after each iteration
 }
\ No newline at end of file
@@ -0,0 +1,205 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::interpreter::token::Token;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolKind {
+    Variable,
+    Parameter,
+    Function,
+    Class,
+    Method,
+}
+
+/// A declared name.
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub name: Token,
+    pub kind: SymbolKind,
+    pub detail: String,            // Declaration summary, such as `fun add(a, b)`
+    pub arity: Option<usize>,      // Argument count when callable
+    pub container: Option<usize>,  // The class a method belongs to
+    pub global: bool,
+}
+
+/// The line span of a scope and the symbols declared in it.
+#[derive(Debug, Clone)]
+struct Scope {
+    first_line: usize,
+    last_line: usize,
+    symbols: Vec<usize>,
+}
+
+impl Scope {
+    fn new() -> Self {
+        Self {
+            first_line: usize::MAX,
+            last_line: 0,
+            symbols: Vec::new(),
+        }
+    }
+
+    fn touch(&mut self, line: usize) {
+        self.first_line = self.first_line.min(line);
+        self.last_line = self.last_line.max(line);
+    }
+}
+
+/// Declarations and the uses that refer to them, recorded by the
+/// `Resolver` for editor tooling.
+#[derive(Debug, Default)]
+pub struct SymbolIndex {
+    symbols: Vec<Symbol>,
+    occurrences: Vec<(Token, usize)>, // Declarations and uses, by symbol
+    unresolved: Vec<Token>,           // Uses of globals, bound by `finish`
+    open: Vec<Scope>,
+    scopes: Vec<Scope>,
+}
+
+impl SymbolIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a declaration in the innermost open scope and returns its id.
+    pub fn declare(&mut self, name: &Token, kind: SymbolKind, detail: String, arity: Option<usize>) -> usize {
+        let id = self.symbols.len();
+        self.symbols.push(Symbol {
+            name: name.clone(),
+            kind,
+            detail,
+            arity,
+            container: None,
+            global: self.open.is_empty(),
+        });
+        self.occurrences.push((name.clone(), id));
+        if let Some(scope) = self.open.last_mut() {
+            scope.touch(name.line);
+            scope.symbols.push(id);
+        }
+        id
+    }
+
+    /// Records a method of the class `container`. Methods are looked up at
+    /// runtime, so they get no scope and no uses.
+    pub fn declare_method(&mut self, name: &Token, detail: String, arity: usize, container: usize) {
+        self.symbols.push(Symbol {
+            name: name.clone(),
+            kind: SymbolKind::Method,
+            detail,
+            arity: Some(arity),
+            container: Some(container),
+            global: false,
+        });
+        self.occurrences.push((name.clone(), self.symbols.len() - 1));
+    }
+
+    /// Records a use of `name`: of the local `symbol`, or of a global when
+    /// `None`.
+    pub fn reference(&mut self, name: &Token, symbol: Option<usize>) {
+        self.touch(name.line);
+        match symbol {
+            Some(id) => self.occurrences.push((name.clone(), id)),
+            None => self.unresolved.push(name.clone()),
+        }
+    }
+
+    pub fn begin_scope(&mut self) {
+        self.open.push(Scope::new());
+    }
+
+    pub fn end_scope(&mut self) {
+        if let Some(scope) = self.open.pop() {
+            // A scope lies within its parent.
+            if let Some(parent) = self.open.last_mut() {
+                if scope.first_line <= scope.last_line {
+                    parent.touch(scope.first_line);
+                    parent.touch(scope.last_line);
+                }
+            }
+            self.scopes.push(scope);
+        }
+    }
+
+    /// Extends the innermost open scope to cover `line`.
+    pub fn touch(&mut self, line: usize) {
+        if let Some(scope) = self.open.last_mut() {
+            scope.touch(line);
+        }
+    }
+
+    /// Binds uses of globals to their declarations, once the whole program
+    /// has been seen. Uses of names never declared, such as natives, are
+    /// dropped.
+    pub fn finish(&mut self) {
+        let mut globals = HashMap::new();
+        for (id, symbol) in self.symbols.iter().enumerate() {
+            if symbol.global {
+                globals.entry(symbol.name.lexeme.clone()).or_insert(id);
+            }
+        }
+        for name in std::mem::take(&mut self.unresolved) {
+            if let Some(&id) = globals.get(&name.lexeme) {
+                self.occurrences.push((name, id));
+            }
+        }
+    }
+
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    pub fn symbol(&self, id: usize) -> &Symbol {
+        &self.symbols[id]
+    }
+
+    /// Returns the symbol whose declaration or use covers the 1-based
+    /// `line` and 0-based `column`.
+    pub fn symbol_at(&self, line: usize, column: usize) -> Option<usize> {
+        self.occurrences
+            .iter()
+            .find(|(token, _)| {
+                token.line == line && token.column <= column && column < token.column + token.lexeme.chars().count()
+            })
+            .map(|(_, id)| *id)
+    }
+
+    /// Returns every occurrence of a symbol in source order, with its
+    /// declaration unless `declaration` is false.
+    pub fn occurrences(&self, id: usize, declaration: bool) -> Vec<&Token> {
+        let declared = &self.symbols[id].name;
+        let mut tokens: Vec<&Token> = self
+            .occurrences
+            .iter()
+            .filter(|(token, symbol)| *symbol == id && (declaration || token != declared))
+            .map(|(token, _)| token)
+            .collect();
+        tokens.sort_by_key(|token| (token.line, token.column));
+        tokens.dedup();
+        tokens
+    }
+
+    /// Returns the symbols that may be named on `line`: every global, and
+    /// locals declared earlier in a scope spanning the line. Inner names
+    /// hide outer ones.
+    pub fn visible_at(&self, line: usize) -> Vec<&Symbol> {
+        let mut scopes: Vec<&Scope> = self
+            .scopes
+            .iter()
+            .filter(|scope| scope.first_line <= line && line <= scope.last_line)
+            .collect();
+        scopes.sort_by_key(|scope| std::cmp::Reverse(scope.first_line));
+
+        let mut seen = HashSet::new();
+        let mut visible = Vec::new();
+        let locals = scopes.iter().flat_map(|scope| scope.symbols.iter());
+        let globals = (0..self.symbols.len()).filter(|id| self.symbols[*id].global);
+        for id in locals.copied().chain(globals) {
+            let symbol = &self.symbols[id];
+            if (symbol.global || symbol.name.line <= line) && seen.insert(symbol.name.lexeme.as_str()) {
+                visible.push(symbol);
+            }
+        }
+        visible
+    }
+}
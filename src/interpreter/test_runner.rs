@@ -0,0 +1,386 @@
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+
+use crate::interpreter::debugger::describe;
+use crate::interpreter::environment::Environment;
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::lox_callable::NativeFunction;
+use crate::interpreter::parser::Parser;
+use crate::interpreter::resolver::Resolver;
+use crate::interpreter::runtime_error::RuntimeError;
+use crate::interpreter::scanner::Scanner;
+use crate::interpreter::stmt::{Stmt, StmtTest};
+use crate::interpreter::value::Value;
+
+/// How results are printed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Text,
+    Tap,
+    Junit,
+}
+
+/// Raised by a failed assertion. Unlike a runtime error, `assertThrows`
+/// lets it through.
+struct AssertionFailed {
+    line: usize,
+    message: String,
+}
+
+/// The result of one test.
+struct Outcome {
+    name: String,
+    failure: Option<String>,
+    output: String, // What the test printed
+}
+
+/// The tests of one file, or why they could not run.
+struct Suite {
+    path: String,
+    outcomes: Vec<Outcome>,
+    error: Option<String>,
+}
+
+/// Collects what a test prints.
+struct Captured(Rc<RefCell<String>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().push_str(&String::from_utf8_lossy(buf));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Defines `assert`, `assertEqual` and `assertThrows`.
+fn define_assertions(globals: &mut Environment) {
+    define(globals, "assert", 1, |interpreter, args| {
+        if matches!(args[0], Value::Nil | Value::Boolean(false)) {
+            fail(interpreter.call_site().line, format!("assert failed: got {}", describe(&args[0])));
+        }
+        Value::Nil
+    });
+
+    define(globals, "assertEqual", 2, |interpreter, args| {
+        let (actual, expected) = (&args[0], &args[1]);
+        if !equal(actual, expected) {
+            let message = format!("assertEqual failed\n{}", diff(&show(expected), &show(actual)));
+            fail(interpreter.call_site().line, message);
+        }
+        Value::Nil
+    });
+
+    define(globals, "assertThrows", 1, |interpreter, args| {
+        let line = interpreter.call_site().line; // Calling back moves the call site
+        let function = match &args[0] {
+            Value::Callable(function) if function.arity() == 0 => function.clone(),
+            _ => interpreter.native_error("assertThrows expects a function that takes no arguments."),
+        };
+        if interpreter.catch_error(|interpreter| function.call(interpreter, Vec::new())).is_ok() {
+            fail(line, "assertThrows failed: the function returned without an error".to_string());
+        }
+        Value::Nil
+    });
+}
+
+fn define(globals: &mut Environment, name: &str, arity: usize, function: fn(&mut Interpreter, Vec<Value>) -> Value) {
+    globals.define(name, Value::Callable(Box::new(NativeFunction { arity, optional: 0, function })));
+}
+
+fn fail(line: usize, message: String) -> ! {
+    // `resume_unwind` skips the panic hook, so nothing is printed.
+    panic::resume_unwind(Box::new(AssertionFailed { line, message }))
+}
+
+/// Compares like `==`, except that lists and maps are equal when their
+/// contents are.
+fn equal(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::List(a), Value::List(b)) => {
+            // The same list is equal to itself even if it contains itself.
+            Rc::ptr_eq(a, b) || {
+                let (a, b) = (a.borrow(), b.borrow());
+                a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| equal(a, b))
+            }
+        }
+        (Value::Map(a), Value::Map(b)) => {
+            Rc::ptr_eq(a, b) || {
+                let (a, b) = (a.borrow(), b.borrow());
+                a.len() == b.len()
+                    && a.iter().zip(b.iter()).all(|((a_key, a), (b_key, b))| a_key == b_key && equal(a, b))
+            }
+        }
+        _ => Interpreter::is_equal(actual, expected),
+    }
+}
+
+/// Formats a value for a diff. Strings with several lines are left as they
+/// are, so they can be compared line by line.
+fn show(value: &Value) -> String {
+    match value {
+        Value::String(s) if s.contains('\n') => s.clone(),
+        value => describe(value),
+    }
+}
+
+/// Shows how two values differ: side by side if both fit on a line, and
+/// line by line otherwise.
+fn diff(expected: &str, actual: &str) -> String {
+    if !expected.contains('\n') && !actual.contains('\n') {
+        return format!("  expected: {}\n    actual: {}", expected, actual);
+    }
+
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    let mut lines = Vec::new();
+    for i in 0..expected.len().max(actual.len()) {
+        match (expected.get(i), actual.get(i)) {
+            (Some(expected), Some(actual)) if expected == actual => lines.push(format!("    {}", expected)),
+            (expected, actual) => {
+                if let Some(expected) = expected {
+                    lines.push(format!("  - {}", expected));
+                }
+                if let Some(actual) = actual {
+                    lines.push(format!("  + {}", actual));
+                }
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+/// Parses a file and runs each of its tests in a fresh interpreter, after
+/// the file's other top-level statements.
+fn run_file(path: &str) -> Suite {
+    let mut suite = Suite {
+        path: path.to_string(),
+        outcomes: Vec::new(),
+        error: None,
+    };
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(err) => {
+            suite.error = Some(err.to_string());
+            return suite;
+        }
+    };
+
+    let mut scanner = Scanner::new(source);
+    let tokens = scanner.scan_tokens();
+    if let Some(error) = scanner.errors().first() {
+        suite.error = Some(format!("[line {}] {}", error.line, error.message));
+        return suite;
+    }
+    let statements = match Parser::new(tokens).parse() {
+        Ok(statements) => statements,
+        Err(error) => {
+            suite.error = Some(format!("[line {}] {}", error.token.line, error.message));
+            return suite;
+        }
+    };
+
+    for statement in &statements {
+        if let Stmt::Test(test) = statement {
+            match run_test(&statements, test) {
+                Ok(outcome) => suite.outcomes.push(outcome),
+                Err(message) => {
+                    suite.error = Some(message);
+                    return suite;
+                }
+            }
+        }
+    }
+    suite
+}
+
+/// Runs one test. Returns an error if the file does not resolve.
+fn run_test(statements: &[Stmt], test: &StmtTest) -> Result<Outcome, String> {
+    let mut interpreter = Interpreter::new();
+    define_assertions(&mut interpreter.globals().borrow_mut());
+    let output = Rc::new(RefCell::new(String::new()));
+    interpreter.set_output(Box::new(Captured(output.clone())));
+
+    let mut resolver = Resolver::new(&mut interpreter);
+    resolver.resolve(statements);
+    if let Some((token, message)) = resolver.errors().first() {
+        return Err(format!("[line {}] Error at '{}': {}", token.line, token.lexeme, message));
+    }
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let _ = interpreter.interpret(statements);
+        interpreter.run_test(test);
+    }));
+    let failure = result.err().map(|payload| {
+        if let Some(failed) = payload.downcast_ref::<AssertionFailed>() {
+            format!("line {}: {}", failed.line, failed.message)
+        } else if let Some(error) = payload.downcast_ref::<RuntimeError>() {
+            error.to_string()
+        } else {
+            "The test panicked.".to_string()
+        }
+    });
+
+    let output = output.borrow().clone();
+    Ok(Outcome {
+        name: test.name.clone(),
+        failure,
+        output,
+    })
+}
+
+fn indent(text: &str, prefix: &str) -> String {
+    text.lines().map(|line| format!("{}{}\n", prefix, line)).collect()
+}
+
+fn text_report(suites: &[Suite]) -> String {
+    let mut report = String::new();
+    let (mut passed, mut failed) = (0, 0);
+    for suite in suites {
+        report.push_str(&format!("{}\n", suite.path));
+        for outcome in &suite.outcomes {
+            match &outcome.failure {
+                None => {
+                    passed += 1;
+                    report.push_str(&format!("  ok    {}\n", outcome.name));
+                }
+                Some(failure) => {
+                    failed += 1;
+                    report.push_str(&format!("  FAIL  {}\n", outcome.name));
+                    report.push_str(&indent(failure, "        "));
+                    if !outcome.output.is_empty() {
+                        report.push_str("        output:\n");
+                        report.push_str(&indent(&outcome.output, "          "));
+                    }
+                }
+            }
+        }
+        if let Some(error) = &suite.error {
+            report.push_str(&format!("  error: {}\n", error));
+        }
+    }
+    report.push_str(&format!("\n{} passed, {} failed\n", passed, failed));
+    report
+}
+
+fn tap_report(suites: &[Suite]) -> String {
+    let total: usize = suites.iter().map(|suite| suite.outcomes.len()).sum();
+    let mut report = format!("TAP version 13\n1..{}\n", total);
+    let mut number = 0;
+    for suite in suites {
+        if let Some(error) = &suite.error {
+            report.push_str(&format!("# {}: {}\n", suite.path, error));
+        }
+        for outcome in &suite.outcomes {
+            number += 1;
+            let status = if outcome.failure.is_some() { "not ok" } else { "ok" };
+            report.push_str(&format!("{} {} - {}: {}\n", status, number, suite.path, outcome.name));
+            if let Some(failure) = &outcome.failure {
+                report.push_str("  ---\n  message: |\n");
+                report.push_str(&indent(failure, "    "));
+                if !outcome.output.is_empty() {
+                    report.push_str("  output: |\n");
+                    report.push_str(&indent(&outcome.output, "    "));
+                }
+                report.push_str("  ...\n");
+            }
+        }
+    }
+    report
+}
+
+fn junit_report(suites: &[Suite]) -> String {
+    let count = |suite: &Suite| suite.outcomes.iter().filter(|outcome| outcome.failure.is_some()).count();
+    let total: usize = suites.iter().map(|suite| suite.outcomes.len()).sum();
+    let failures: usize = suites.iter().map(count).sum();
+    let errors = suites.iter().filter(|suite| suite.error.is_some()).count();
+
+    let mut report = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    report.push_str(&format!(
+        "<testsuites tests=\"{}\" failures=\"{}\" errors=\"{}\">\n",
+        total, failures, errors
+    ));
+    for suite in suites {
+        let path = escape(&suite.path);
+        report.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\">\n",
+            path,
+            suite.outcomes.len(),
+            count(suite),
+            usize::from(suite.error.is_some())
+        ));
+        if let Some(error) = &suite.error {
+            report.push_str(&format!("    <error message=\"{}\"/>\n", escape(error)));
+        }
+        for outcome in &suite.outcomes {
+            let name = escape(&outcome.name);
+            let failure = match &outcome.failure {
+                Some(failure) => failure,
+                None => {
+                    report.push_str(&format!("    <testcase name=\"{}\" classname=\"{}\"/>\n", name, path));
+                    continue;
+                }
+            };
+            report.push_str(&format!("    <testcase name=\"{}\" classname=\"{}\">\n", name, path));
+            let summary = failure.lines().next().unwrap_or("");
+            report.push_str(&format!(
+                "      <failure message=\"{}\">{}</failure>\n",
+                escape(summary),
+                escape(failure)
+            ));
+            if !outcome.output.is_empty() {
+                report.push_str(&format!("      <system-out>{}</system-out>\n", escape(&outcome.output)));
+            }
+            report.push_str("    </testcase>\n");
+        }
+        report.push_str("  </testsuite>\n");
+    }
+    report.push_str("</testsuites>\n");
+    report
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Runs `lox test [--tap | --junit] <files...>`, running every `test`
+/// declaration in the files. Exits with 1 if any test failed.
+pub fn command(args: &[String]) -> i32 {
+    let (format, paths) = match args.first().map(String::as_str) {
+        Some("--tap") => (Format::Tap, &args[1..]),
+        Some("--junit") => (Format::Junit, &args[1..]),
+        _ => (Format::Text, args),
+    };
+    if paths.is_empty() || paths.iter().any(|path| path.starts_with("--")) {
+        eprintln!("Usage: lox test [--tap | --junit] <files...>");
+        return 64;
+    }
+
+    let suites: Vec<Suite> = paths.iter().map(|path| run_file(path)).collect();
+    let report = match format {
+        Format::Text => text_report(&suites),
+        Format::Tap => tap_report(&suites),
+        Format::Junit => junit_report(&suites),
+    };
+    print!("{}", report);
+
+    let failed = suites
+        .iter()
+        .flat_map(|suite| &suite.outcomes)
+        .any(|outcome| outcome.failure.is_some());
+    if suites.iter().any(|suite| suite.error.is_some()) {
+        65
+    } else if failed {
+        1
+    } else {
+        0
+    }
+}
@@ -0,0 +1,166 @@
+use std::fmt::Write;
+use std::rc::Rc;
+
+use chrono::{DateTime, Datelike, FixedOffset, Local, Months, NaiveDate, NaiveDateTime, Timelike, Utc};
+
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::lox_class::LoxClass;
+use crate::interpreter::lox_instance::LoxInstance;
+use crate::interpreter::native_module::NativeModule;
+use crate::interpreter::number;
+use crate::interpreter::value::Value;
+
+thread_local! {
+    /// `time.Date`, the class of the instances `time` hands out. It has no
+    /// methods; its fields are filled in by `define_date_fields`.
+    static DATE_CLASS: Rc<LoxClass> = Rc::new(LoxClass::native("Date", 1, |interpreter, instance, args| {
+        define_date_fields(instance, date_time(interpreter, &args[0]));
+    }));
+}
+
+/// Builds the `time` module. Timestamps are seconds since the Unix epoch;
+/// wherever one is expected a `Date` instance is accepted too.
+pub fn module() -> Value {
+    let mut time = NativeModule::new("time");
+    time.function("now", 0, |_, _| date_value(Local::now().fixed_offset()))
+        .function("utcNow", 0, |_, _| date_value(Utc::now().fixed_offset()))
+        .function("timestamp", 1, |interpreter, args| Value::Number(timestamp(&date_time(interpreter, &args[0]))))
+        .function("parse", 2, |interpreter, args| {
+            let text = string_arg(interpreter, &args[0]);
+            let format = string_arg(interpreter, &args[1]);
+            match parse(text, format) {
+                Some(parsed) => date_value(parsed),
+                None => interpreter.native_error(&format!("Cannot parse '{}' with format '{}'.", text, format)),
+            }
+        })
+        .function("format", 2, |interpreter, args| {
+            let date = date_time(interpreter, &args[0]);
+            let format = string_arg(interpreter, &args[1]);
+            let mut formatted = String::new();
+            if write!(formatted, "{}", date.format(format)).is_err() {
+                interpreter.native_error(&format!("Invalid format string '{}'.", format));
+            }
+            Value::String(formatted)
+        })
+        .function("seconds", 1, |interpreter, args| duration(interpreter, &args[0], 1.0))
+        .function("minutes", 1, |interpreter, args| duration(interpreter, &args[0], 60.0))
+        .function("hours", 1, |interpreter, args| duration(interpreter, &args[0], 3600.0))
+        .function("days", 1, |interpreter, args| duration(interpreter, &args[0], 86400.0))
+        .function("add", 2, |interpreter, args| {
+            let date = date_time(interpreter, &args[0]);
+            let seconds = float_arg(interpreter, &args[1]);
+            let shifted = timestamp(&date) + seconds;
+            match from_timestamp(shifted) {
+                Some(utc) => date_value(utc.with_timezone(date.offset())),
+                None => interpreter.native_error("Date out of range."),
+            }
+        })
+        .function("addMonths", 2, |interpreter, args| {
+            let date = date_time(interpreter, &args[0]);
+            let shifted = match &args[1] {
+                Value::Integer(n) => match u32::try_from(n.unsigned_abs()) {
+                    Ok(months) if *n >= 0 => date.checked_add_months(Months::new(months)),
+                    Ok(months) => date.checked_sub_months(Months::new(months)),
+                    Err(_) => None,
+                },
+                _ => interpreter.native_error("Month count must be an integer."),
+            };
+            match shifted {
+                Some(shifted) => date_value(shifted),
+                None => interpreter.native_error("Date out of range."),
+            }
+        })
+        .function("diff", 2, |interpreter, args| {
+            let a = date_time(interpreter, &args[0]);
+            let b = date_time(interpreter, &args[1]);
+            Value::Number(timestamp(&a) - timestamp(&b))
+        });
+    // `time.Date(ts)` makes a date from a timestamp or another `Date`.
+    time.constant("Date", Value::Callable(Box::new(DATE_CLASS.with(|class| (**class).clone()))));
+    Value::Module(Rc::new(time))
+}
+
+/// Wraps a date in a `Date` instance exposing its calendar fields.
+fn date_value(date: DateTime<FixedOffset>) -> Value {
+    let instance = LoxInstance::new(DATE_CLASS.with(|class| class.clone()));
+    define_date_fields(&instance, date);
+    Value::Instance(Rc::new(instance))
+}
+
+fn define_date_fields(instance: &LoxInstance, date: DateTime<FixedOffset>) {
+    let fields = [
+        ("year", Value::Integer(date.year() as i64)),
+        ("month", Value::Integer(date.month() as i64)),
+        ("day", Value::Integer(date.day() as i64)),
+        ("hour", Value::Integer(date.hour() as i64)),
+        ("minute", Value::Integer(date.minute() as i64)),
+        ("second", Value::Integer(date.second() as i64)),
+        ("weekday", Value::Integer(date.weekday().number_from_monday() as i64)),
+        ("yearDay", Value::Integer(date.ordinal() as i64)),
+        ("offset", Value::Integer(date.offset().local_minus_utc() as i64)),
+        ("timestamp", Value::Number(timestamp(&date))),
+    ];
+    for (name, value) in fields {
+        instance.define_field(name, value);
+    }
+}
+
+/// Reads a timestamp or `Date` argument. Plain timestamps are taken as UTC;
+/// dates keep the offset they were created with.
+fn date_time(interpreter: &Interpreter, value: &Value) -> DateTime<FixedOffset> {
+    let (seconds, offset) = match value {
+        Value::Instance(instance) => match (instance.field("timestamp"), instance.field("offset")) {
+            (Some(Value::Number(seconds)), Some(Value::Integer(offset))) => (seconds, offset as i32),
+            _ => interpreter.native_error("Expected a timestamp or Date."),
+        },
+        other => (float_arg(interpreter, other), 0),
+    };
+
+    let offset = FixedOffset::east_opt(offset)
+        .unwrap_or_else(|| interpreter.native_error("Invalid UTC offset on Date."));
+    match from_timestamp(seconds) {
+        Some(utc) => utc.with_timezone(&offset),
+        None => interpreter.native_error("Timestamp out of range."),
+    }
+}
+
+/// Parses `text`. Formats without a `%z` offset are read as UTC, and
+/// date-only formats give midnight.
+fn parse(text: &str, format: &str) -> Option<DateTime<FixedOffset>> {
+    if let Ok(parsed) = DateTime::parse_from_str(text, format) {
+        return Some(parsed);
+    }
+    if let Ok(naive) = NaiveDateTime::parse_from_str(text, format) {
+        return Some(naive.and_utc().fixed_offset());
+    }
+    let date = NaiveDate::parse_from_str(text, format).ok()?;
+    Some(date.and_hms_opt(0, 0, 0)?.and_utc().fixed_offset())
+}
+
+fn timestamp(date: &DateTime<FixedOffset>) -> f64 {
+    date.timestamp() as f64 + date.timestamp_subsec_nanos() as f64 / 1e9
+}
+
+fn from_timestamp(seconds: f64) -> Option<DateTime<Utc>> {
+    if !seconds.is_finite() {
+        return None;
+    }
+    let whole = seconds.floor();
+    let nanos = ((seconds - whole) * 1e9).round().min(999_999_999.0) as u32;
+    DateTime::from_timestamp(whole as i64, nanos)
+}
+
+fn duration(interpreter: &Interpreter, value: &Value, unit: f64) -> Value {
+    Value::Number(float_arg(interpreter, value) * unit)
+}
+
+fn float_arg(interpreter: &Interpreter, value: &Value) -> f64 {
+    number::as_f64(value).unwrap_or_else(|| interpreter.native_error("Argument must be a number."))
+}
+
+fn string_arg<'a>(interpreter: &Interpreter, value: &'a Value) -> &'a str {
+    match value {
+        Value::String(s) => s,
+        _ => interpreter.native_error("Argument must be a string."),
+    }
+}
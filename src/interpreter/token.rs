@@ -1,19 +1,25 @@
+use crate::interpreter::bigint::BigInt;
 use crate::interpreter::token_type::TokenType;
 use std::fmt;
 use std::cmp::PartialEq;
 use std::hash::{Hash, Hasher};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Token {
     pub token_type: TokenType,
     pub lexeme: String,
     pub literal: Option<Literal>,
     pub line: usize, // [location]
+    pub column: usize, // Chars (not bytes) from the start of the line; 0 if synthesized
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Literal {
     Number(f64),
+    Integer(i64),
+    BigInteger(BigInt),
     String(String),
     Boolean(bool),
     Nil,
@@ -25,6 +31,8 @@ impl PartialEq for Literal {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Literal::Number(a), Literal::Number(b)) => a == b,
+            (Literal::Integer(a), Literal::Integer(b)) => a == b,
+            (Literal::BigInteger(a), Literal::BigInteger(b)) => a == b,
             (Literal::String(a), Literal::String(b)) => a == b,
             (Literal::Boolean(a), Literal::Boolean(b)) => a == b,
             (Literal::Nil, Literal::Nil) => true,
@@ -37,9 +45,11 @@ impl Hash for Literal {
     fn hash<H: Hasher>(&self, state: &mut H) {
         match self {
             Literal::Number(value) => value.to_bits().hash(state),
+            Literal::Integer(value) => value.hash(state),
+            Literal::BigInteger(value) => value.hash(state),
             Literal::String(value) => value.hash(state),
             Literal::Boolean(value) => value.hash(state),
-            Literal::Nil => ().hash(state),
+            Literal::Nil => {}
         }
     }
 }
@@ -52,26 +62,19 @@ impl Token {
             lexeme,
             literal,
             line,
+            column: 0,
         }
     }
-
-    pub fn to_string(&self) -> String {
-        let literal_string = match &self.literal {
-            Some(Literal::Number(n)) => n.to_string(),
-            Some(Literal::String(s)) => s.clone(),
-            Some(Literal::Boolean(b)) => b.to_string(),
-            Some(Literal::Nil) | None => "nil".to_string(),
-        };
-
-        format!("{:?} {} {}", self.token_type, self.lexeme, literal_string)
-    }
 }
 
 impl From<serde_json::Value> for Literal {
     fn from(value: serde_json::Value) -> Self {
         match value {
             serde_json::Value::String(s) => Literal::String(s),
-            serde_json::Value::Number(n) => Literal::Number(n.as_f64().unwrap()), // Example
+            serde_json::Value::Number(n) => match n.as_i64() {
+                Some(i) => Literal::Integer(i),
+                None => Literal::Number(n.as_f64().unwrap()),
+            },
             serde_json::Value::Bool(b) => Literal::Boolean(b),
             _ => Literal::Nil, // Handle other cases
         }
@@ -1,10 +1,13 @@
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TokenType {
     // Single-character tokens.
     LeftParen,
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
     Dot,
     Minus,
@@ -12,6 +15,11 @@ pub enum TokenType {
     Semicolon,
     Slash,
     Star,
+    Percent,
+    Ampersand,
+    Pipe,
+    Caret,
+    Tilde,
 
     // One or two character tokens.
     Bang,
@@ -22,11 +30,17 @@ pub enum TokenType {
     GreaterEqual,
     Less,
     LessEqual,
+    LessLess,
+    GreaterGreater,
+    TildeSlash,
+    Arrow,
 
     // Literals.
     Identifier,
     String,
+    Interpolation, // A string segment that ends at `${`.
     Number,
+    Integer,
 
     // Keywords.
     And,
@@ -1,10 +1,17 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::fmt;
 use std::rc::Rc;
 use std::hash::{Hash, Hasher};
 
+use crate::interpreter::bigint::BigInt;
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Object {
     Number(f64),
+    Integer(i64),
+    BigInteger(BigInt),
     String(String),
     Boolean(bool),
     Nil,
@@ -14,6 +21,8 @@ impl PartialEq for Object {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Object::Number(a), Object::Number(b)) => a.to_bits() == b.to_bits(), // Compare bitwise representation of `f64`
+            (Object::Integer(a), Object::Integer(b)) => a == b,
+            (Object::BigInteger(a), Object::BigInteger(b)) => a == b,
             (Object::String(a), Object::String(b)) => a == b,
             (Object::Boolean(a), Object::Boolean(b)) => a == b,
             (Object::Nil, Object::Nil) => true,
@@ -31,15 +40,19 @@ impl Hash for Object {
                 // Convert the f64 to its bitwise representation for consistent hashing
                 value.to_bits().hash(state);
             }
+            Object::Integer(value) => {
+                value.hash(state);
+            }
+            Object::BigInteger(value) => {
+                value.hash(state);
+            }
             Object::String(value) => {
                 value.hash(state);
             }
             Object::Boolean(value) => {
                 value.hash(state);
             }
-            Object::Nil => {
-                ().hash(state); // Hash `Nil` as an empty tuple
-            }
+            Object::Nil => {}
         }
     }
 }
@@ -47,22 +60,50 @@ impl Hash for Object {
 #[derive(Debug, Clone)]
 pub enum Value {
     Number(f64),
+    Integer(i64),
+    BigInteger(BigInt),
     String(String),
     Boolean(bool),
     Nil,
     Callable(Box<dyn crate::interpreter::lox_callable::LoxCallable>),
     Instance(Rc<crate::interpreter::lox_instance::LoxInstance>),
+    List(Rc<RefCell<Vec<Value>>>),
+    Map(Rc<RefCell<BTreeMap<String, Value>>>),
+    Module(Rc<crate::interpreter::native_module::NativeModule>),
 }
 
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Value::Number(n) => write!(f, "{}", n),
+            Value::Integer(i) => write!(f, "{}", i),
+            Value::BigInteger(b) => write!(f, "{}", b),
             Value::String(s) => write!(f, "{}", s),
             Value::Boolean(b) => write!(f, "{}", b),
             Value::Nil => write!(f, "nil"),
-            Value::Callable(_) => write!(f, "<callable>"),
+            Value::Callable(callable) => write!(f, "{}", callable),
             Value::Instance(instance) => write!(f, "{}", instance),
+            Value::Module(module) => write!(f, "{}", module),
+            Value::List(list) => {
+                write!(f, "[")?;
+                for (i, item) in list.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Value::Map(map) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in map.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", key, value)?;
+                }
+                write!(f, "}}")
+            }
         }
     }
 }
\ No newline at end of file
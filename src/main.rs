@@ -1,9 +1,8 @@
 mod interpreter;
+#[allow(dead_code)] // The bytecode VM is not written yet
 mod vm;
 
 fn main() {
-    println!("Lox Interpreter and VM in Rust");
-    // Uncomment the line for the phase you are testing
-    //interpreter::lox::run();
+    interpreter::lox::Lox::main();
     //vm::main_vm::run();
 }
@@ -8,4 +8,5 @@ pub mod object;
 pub mod scanner;
 pub mod table;
 pub mod value;
+#[allow(clippy::module_inception)]
 pub mod vm;
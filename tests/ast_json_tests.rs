@@ -0,0 +1,72 @@
+mod common;
+
+use common::lox;
+
+#[cfg(feature = "ast-serde")]
+#[test]
+fn programs_round_trip_through_json() {
+    use std::{env, fs};
+
+    for script in ["tests/scripts/classes.lox", "tests/scripts/lambdas.lox", "tests/scripts/numbers.lox"] {
+        let dumped = lox(&["ast", "--json", script]);
+        assert!(dumped.status.success(), "{}", String::from_utf8_lossy(&dumped.stderr));
+
+        let path = env::temp_dir().join(format!("lox-ast-{}.json", std::process::id()));
+        fs::write(&path, &dumped.stdout).unwrap();
+        let from_json = lox(&["--json-ast", path.to_str().unwrap()]);
+        let _ = fs::remove_file(&path);
+
+        let from_source = lox(&[script]);
+        assert_eq!(
+            String::from_utf8_lossy(&from_json.stdout),
+            String::from_utf8_lossy(&from_source.stdout),
+            "output of {}",
+            script
+        );
+        assert_eq!(from_json.status.code(), from_source.status.code(), "exit code of {}", script);
+    }
+}
+
+/// Gives every node in pretty-printed JSON the same position, the way
+/// a tool that generates ASTs without source text might.
+#[cfg(feature = "ast-serde")]
+fn without_positions(json: &str) -> String {
+    json.lines()
+        .map(|line| match line.trim_start() {
+            field if field.starts_with("\"line\":") => line.replace(field.trim_end_matches(','), "\"line\": 1"),
+            field if field.starts_with("\"column\":") => line.replace(field.trim_end_matches(','), "\"column\": 0"),
+            _ => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(feature = "ast-serde")]
+#[test]
+fn variables_resolve_apart_without_positions() {
+    use std::{env, fs};
+
+    let dumped = lox(&["ast", "--json", "tests/ast_json/shadowing.lox"]);
+    assert!(dumped.status.success(), "{}", String::from_utf8_lossy(&dumped.stderr));
+    let json = without_positions(&String::from_utf8_lossy(&dumped.stdout));
+    assert!(!json.contains("\"line\": 2"));
+
+    let path = env::temp_dir().join(format!("lox-ast-positions-{}.json", std::process::id()));
+    fs::write(&path, json).unwrap();
+    let output = lox(&["--json-ast", path.to_str().unwrap()]);
+    let _ = fs::remove_file(&path);
+
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "local\nglobal\n");
+    assert!(output.status.success());
+}
+
+#[cfg(not(feature = "ast-serde"))]
+#[test]
+fn json_needs_the_ast_serde_feature() {
+    let output = lox(&["ast", "--json", "tests/scripts/classes.lox"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "lox was built without the 'ast-serde' feature.\n"
+    );
+    assert_eq!(output.status.code(), Some(70));
+}
@@ -0,0 +1,78 @@
+// Each test crate uses only some of these helpers.
+#![allow(dead_code)]
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+use serde_json::Value;
+
+/// Runs `lox` with `args` from the crate root.
+pub fn lox(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_lox-in-rust"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .expect("lox runs")
+}
+
+/// Reads `tests/<dir>/<name>`.
+pub fn fixture(dir: &str, name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join(dir).join(name);
+    fs::read_to_string(&path).unwrap_or_else(|err| panic!("{}: {}", path.display(), err))
+}
+
+/// Replays a recorded session against `lox <command>`, whose transcripts
+/// live in `tests/transcripts/<command>`. In a transcript, `->` lines are
+/// sent in order, `<-` lines are everything that must come back, and `#`
+/// lines are comments.
+pub fn replay(command: &str, name: &str) {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let path = root.join("tests/transcripts").join(command).join(name);
+    let transcript = fs::read_to_string(&path).expect("transcript exists");
+
+    let mut input = String::new();
+    let mut expected = Vec::new();
+    for line in transcript.lines() {
+        if let Some(message) = line.strip_prefix("-> ") {
+            input.push_str(&format!("Content-Length: {}\r\n\r\n{}", message.len(), message));
+        } else if let Some(message) = line.strip_prefix("<- ") {
+            expected.push(serde_json::from_str::<Value>(message).expect("expected message is JSON"));
+        }
+    }
+
+    let mut server = Command::new(env!("CARGO_BIN_EXE_lox-in-rust"))
+        .arg(command)
+        .current_dir(root)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("server starts");
+    server.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = server.wait_with_output().unwrap();
+
+    assert!(output.status.success(), "server exited with {}", output.status);
+    assert_eq!(messages(&output.stdout), expected);
+}
+
+/// Splits `Content-Length` framed output into messages.
+fn messages(mut output: &[u8]) -> Vec<Value> {
+    let mut messages = Vec::new();
+    while !output.is_empty() {
+        let header_end = output
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .expect("message has a header");
+        let header = std::str::from_utf8(&output[..header_end]).unwrap();
+        let length: usize = header
+            .strip_prefix("Content-Length: ")
+            .and_then(|length| length.parse().ok())
+            .expect("header gives the length");
+        let body = &output[header_end + 4..header_end + 4 + length];
+        messages.push(serde_json::from_slice(body).expect("message is JSON"));
+        output = &output[header_end + 4 + length..];
+    }
+    messages
+}
@@ -0,0 +1,21 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn lcov_report_covers_every_line_and_branch() {
+    let report = env::temp_dir().join(format!("lox-coverage-{}.info", std::process::id()));
+    let output = Command::new(env!("CARGO_BIN_EXE_lox-in-rust"))
+        .args(["--coverage", report.to_str().unwrap(), "tests/coverage/branches.lox"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .expect("lox runs");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let lcov = fs::read_to_string(&report).expect("report is written");
+    let _ = fs::remove_file(&report);
+    let expected = fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/coverage/branches.info"))
+        .expect("expected report exists");
+    assert_eq!(lcov, expected);
+}
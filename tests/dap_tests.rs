@@ -0,0 +1,13 @@
+mod common;
+
+use common::replay;
+
+#[test]
+fn breakpoints() {
+    replay("dap", "breakpoints.txt");
+}
+
+#[test]
+fn stepping() {
+    replay("dap", "stepping.txt");
+}
@@ -0,0 +1,48 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// Runs `lox debug` on `tests/debugger/<name>`, typing `commands` at the
+/// prompt.
+fn debug(name: &str, commands: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_lox-in-rust"))
+        .args(["debug", &format!("tests/debugger/{}", name)])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("lox runs");
+    child.stdin.take().unwrap().write_all(commands.as_bytes()).unwrap();
+    child.wait_with_output().expect("lox finishes")
+}
+
+#[test]
+fn breakpoints_stop_on_this_and_if_statements() {
+    let output = debug("stepping.lox", "break 3\nbreak 7\ncontinue\nbacktrace\ncontinue\nstep\nprint p\ncontinue\n");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stops: Vec<_> = stdout.split("(debug) ").filter(|text| !text.is_empty()).collect();
+    assert_eq!(
+        stops,
+        [
+            "Stopped at line 1 in <script>: class Point {\n",
+            "Breakpoint at line 3.\n",
+            "Breakpoint at line 7.\n",
+            "Stopped at line 3 in Point.init: this.x = 1;\n",
+            "#0 Point.init at line 3\n#1 <script> at line 6\n",
+            "Stopped at line 7 in <script>: if (true) {\n",
+            "Stopped at line 8 in <script>: print p.x;\n",
+            "p = Point instance\n",
+            "1\ndone\n",
+        ]
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn quit_stops_the_script_cleanly() {
+    let output = debug("stepping.lox", "quit\n");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout, "Stopped at line 1 in <script>: class Point {\n(debug) ");
+    assert!(output.stderr.is_empty(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(output.status.code(), Some(0));
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use std::env;
+use std::fs;
+
+use common::lox;
+
+fn fixture(name: &str) -> String {
+    common::fixture("doc", name)
+}
+
+#[test]
+fn markdown_lists_classes_members_and_functions() {
+    let output = lox(&["doc", "tests/doc/shapes.lox"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), fixture("shapes.md"));
+}
+
+#[test]
+fn html_escapes_signatures_and_splits_paragraphs() {
+    let output = lox(&["doc", "--html", "tests/doc/shapes.lox"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), fixture("shapes.html"));
+}
+
+#[test]
+fn out_writes_a_page_per_file() {
+    let dir = env::temp_dir().join(format!("lox-doc-{}", std::process::id()));
+    let output = lox(&["doc", "--out", dir.to_str().unwrap(), "tests/doc/shapes.lox"]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let page = fs::read_to_string(dir.join("shapes.md")).expect("page is written");
+    let _ = fs::remove_dir_all(&dir);
+    assert_eq!(page, fixture("shapes.md"));
+}
+
+#[test]
+fn syntax_errors_are_reported_per_file() {
+    let output = lox(&["doc", "tests/scripts/bad_escape.lox"]);
+    assert_eq!(output.status.code(), Some(65));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("tests/scripts/bad_escape.lox: [line 1] Invalid escape sequence '\\q'."));
+}
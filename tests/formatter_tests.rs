@@ -0,0 +1,56 @@
+mod common;
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// Runs `lox fmt` with `args`, feeding `input` on stdin.
+fn fmt(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_lox-in-rust"))
+        .arg("fmt")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("lox runs");
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    child.wait_with_output().expect("lox finishes")
+}
+
+fn fixture(name: &str) -> String {
+    common::fixture("formatter", name)
+}
+
+#[test]
+fn comments_stay_with_their_statements() {
+    let output = fmt(&[], &fixture("messy.lox"));
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), fixture("messy.formatted"));
+}
+
+#[test]
+fn formatting_is_idempotent() {
+    let formatted = fixture("messy.formatted");
+    let output = fmt(&[], &formatted);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), formatted);
+    assert_eq!(fmt(&["--check"], &formatted).status.code(), Some(0));
+}
+
+#[test]
+fn check_rejects_unformatted_source() {
+    assert_eq!(fmt(&["--check"], &fixture("messy.lox")).status.code(), Some(1));
+}
+
+#[test]
+fn test_names_keep_their_escapes() {
+    let source = "test \"says \\\"hi\\\"\\n\" {\n    print \"hi\";\n}\n";
+    let output = fmt(&[], source);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), source);
+}
+
+#[test]
+fn syntax_errors_name_their_line() {
+    let output = fmt(&[], "var x = 1;\nprint x x;\n");
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "<stdin>: [line 2] Expect ';' after value.\n");
+    assert_eq!(output.status.code(), Some(65));
+}
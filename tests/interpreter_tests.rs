@@ -0,0 +1,171 @@
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Runs `tests/scripts/<name>` and checks it against the expectations in
+/// its comments: `// expect: text` is a line of output, in order, and
+/// `// expect runtime error: message` or `// expect error: message` must
+/// appear on stderr, with the exit code lox uses for that kind of error.
+/// A `// flags: ...` line passes command-line flags before the script.
+fn check(name: &str) {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let path = root.join("tests/scripts").join(name);
+    let source = fs::read_to_string(&path).expect("script exists");
+
+    let mut expected = Vec::new();
+    let mut error = None;
+    let mut flags = Vec::new();
+    for line in source.lines() {
+        if let Some(text) = line.strip_prefix("// flags: ") {
+            flags.extend(text.split_whitespace());
+        } else if let Some((_, text)) = line.split_once("// expect: ") {
+            expected.push(text.to_string());
+        } else if let Some((_, message)) = line.split_once("// expect runtime error: ") {
+            error = Some((70, message.to_string()));
+        } else if let Some((_, message)) = line.split_once("// expect error: ") {
+            error = Some((65, message.to_string()));
+        }
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_lox-in-rust"))
+        .args(&flags)
+        .arg(&path)
+        .current_dir(root)
+        .output()
+        .expect("lox runs");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(stdout.lines().collect::<Vec<_>>(), expected, "output of {}", name);
+    match error {
+        Some((code, message)) => {
+            assert!(stderr.contains(&message), "{} reported {:?}, not {:?}", name, stderr, message);
+            assert_eq!(output.status.code(), Some(code), "exit code of {}", name);
+        }
+        None => assert!(output.status.success(), "{} failed: {}", name, stderr),
+    }
+}
+
+#[test]
+fn lambdas() {
+    check("lambdas.lox");
+}
+
+#[test]
+fn string_methods() {
+    check("strings.lox");
+    check("string_method_arguments.lox");
+    check("string_unknown_method.lox");
+}
+
+#[test]
+fn math() {
+    check("math.lox");
+    check("num_rejects_words.lox");
+    check("fixed_digits_out_of_range.lox");
+}
+
+#[test]
+fn io_natives() {
+    check("io.lox");
+    check("io_without_capability.lox");
+}
+
+#[test]
+fn io_natives_write_files_and_read_stdin() {
+    let scratch = env::temp_dir().join(format!("lox-io-{}.txt", std::process::id()));
+    let mut child = Command::new(env!("CARGO_BIN_EXE_lox-in-rust"))
+        .args(["--allow-read", "--allow-write", "--allow-stdin", "tests/scripts/io_write.lox"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("lox runs");
+    let stdin = format!("{}\nAda\n", scratch.display());
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    let output = child.wait_with_output().expect("lox finishes");
+    let written = fs::read_to_string(&scratch);
+    let _ = fs::remove_file(&scratch);
+
+    // Values are written as `print` shows them; the prompt goes to stdout.
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(written.expect("file is written"), "(1, 2)\n3");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "(1, 2)\n3\nName? Hello, Ada!\nnil\n");
+}
+
+#[test]
+fn random() {
+    check("random.lox");
+    check("random_empty_choice.lox");
+}
+
+#[test]
+fn interpolation() {
+    check("interpolation.lox");
+}
+
+#[test]
+fn escapes() {
+    check("escapes.lox");
+}
+
+#[test]
+fn invalid_escapes_are_syntax_errors() {
+    check("bad_escape.lox");
+}
+
+#[test]
+fn unclosed_interpolation_is_a_syntax_error() {
+    check("unterminated_interpolation.lox");
+}
+
+#[test]
+fn classes() {
+    check("classes.lox");
+}
+
+#[test]
+fn this_in_a_class_method_is_an_error() {
+    check("this_in_class_method.lox");
+}
+
+#[test]
+fn this_outside_a_class_is_an_error() {
+    check("this_outside_class.lox");
+}
+
+#[test]
+fn numbers() {
+    check("numbers.lox");
+}
+
+#[test]
+fn huge_shifts_are_runtime_errors() {
+    check("shift_too_large.lox");
+}
+
+#[test]
+fn special_methods() {
+    check("special_methods.lox");
+}
+
+#[test]
+fn special_methods_check_their_arity() {
+    check("special_method_arity.lox");
+    check("str_arity.lox");
+}
+
+#[test]
+fn json() {
+    check("json.lox");
+    check("json_too_large.lox");
+}
+
+#[test]
+fn time() {
+    check("time.lox");
+    check("time_months_out_of_range.lox");
+}
@@ -0,0 +1,93 @@
+use std::process::{Command, Output};
+
+/// Runs a script from `tests/limits` with extra command-line flags.
+fn run(flags: &[&str], script: &str) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_lox-in-rust"))
+        .args(flags)
+        .arg(format!("tests/limits/{}", script))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .expect("lox runs")
+}
+
+/// Asserts the script printed `stdout` and then failed with `error`.
+fn assert_limit(output: &Output, stdout: &str, error: &str) {
+    assert_eq!(output.status.code(), Some(70));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), stdout);
+    assert!(
+        String::from_utf8_lossy(&output.stderr).starts_with(error),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn unbounded_recursion_overflows_by_default() {
+    assert_limit(&run(&[], "recursion.lox"), "", "Stack overflow.\n[line 2]");
+}
+
+#[test]
+fn getters_and_special_methods_count_toward_call_depth() {
+    assert_limit(&run(&[], "getter.lox"), "", "Stack overflow.\n[line 2]");
+    assert_limit(&run(&[], "operator.lox"), "", "Stack overflow.\n[line 2]");
+}
+
+#[test]
+fn call_depth_is_configurable() {
+    assert_limit(&run(&["--max-call-depth", "10"], "depth.lox"), "8\n", "Stack overflow.\n[line 3]");
+
+    let output = run(&["--max-call-depth", "0"], "depth.lox");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "8\n20\n");
+}
+
+#[test]
+fn statement_budget_stops_infinite_loops() {
+    assert_limit(&run(&["--max-statements", "1000"], "spin.lox"), "", "Statement budget exceeded.");
+
+    let output = run(&["--max-statements", "20"], "count.lox");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "0\n1\n2\n");
+}
+
+#[test]
+fn timeout_stops_infinite_loops() {
+    assert_limit(&run(&["--timeout", "100"], "spin.lox"), "", "Execution timed out.");
+}
+
+#[test]
+fn alloc_budget_stops_string_doubling() {
+    assert_limit(&run(&["--max-alloc", "1000000"], "doubling.lox"), "", "Allocation budget exceeded.\n[line 3]");
+}
+
+#[test]
+fn alloc_budget_is_checked_before_replacing() {
+    assert_limit(&run(&["--max-alloc", "100000"], "replace.lox"), "4096\n", "Allocation budget exceeded.\n[line 4]");
+}
+
+#[test]
+fn alloc_budget_stops_big_integer_growth() {
+    assert_limit(&run(&["--max-alloc", "100000"], "squaring.lox"), "", "Allocation budget exceeded.\n[line 3]");
+}
+
+#[test]
+fn alloc_budget_counts_lists_and_maps() {
+    assert_limit(&run(&["--max-alloc", "2000"], "split.lox"), "289\n", "Allocation budget exceeded.\n[line 3]");
+    assert_limit(&run(&["--max-alloc", "2000"], "json.lox"), "291\n", "Allocation budget exceeded.\n[line 3]");
+
+    let output = run(&[], "json.lox");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "291\n99\n");
+}
+
+#[test]
+fn alloc_budget_counts_instances() {
+    assert_limit(&run(&["--max-alloc", "100000"], "instances.lox"), "", "Allocation budget exceeded.\n[line 8]");
+}
+
+#[test]
+fn limit_flags_need_a_number() {
+    let output = run(&["--max-alloc", "lots"], "spin.lox");
+    assert_eq!(output.status.code(), Some(64));
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "--max-alloc expects a non-negative integer.\n");
+}
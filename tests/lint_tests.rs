@@ -0,0 +1,39 @@
+mod common;
+
+use common::{fixture, lox};
+
+/// Lints `tests/lint/<name>.lox` and checks the report against
+/// `tests/lint/<name>.txt`, returning the exit code.
+fn lint(name: &str) -> Option<i32> {
+    let output = lox(&["lint", &format!("tests/lint/{}.lox", name)]);
+    let expected = fixture("lint", &format!("{}.txt", name));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected, "report for {}", name);
+    output.status.code()
+}
+
+#[test]
+fn every_rule_is_reported() {
+    // `undeclared-global` and `wrong-arity` are errors.
+    assert_eq!(lint("rules"), Some(1));
+}
+
+#[test]
+fn allow_comments_silence_their_rules() {
+    // A trailing comment covers its own line, one on its own line the next.
+    assert_eq!(lint("allowed"), Some(0));
+}
+
+#[test]
+fn resolver_errors_are_reported_as_errors() {
+    assert_eq!(lint("resolve"), Some(1));
+}
+
+#[test]
+fn syntax_errors_name_their_line() {
+    let output = lox(&["lint", "tests/lint/syntax.lox"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "tests/lint/syntax.lox: [line 2] Expect ';' after value.\n"
+    );
+    assert_eq!(output.status.code(), Some(65));
+}
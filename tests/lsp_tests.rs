@@ -0,0 +1,23 @@
+mod common;
+
+use common::replay;
+
+#[test]
+fn navigation() {
+    replay("lsp", "navigation.txt");
+}
+
+#[test]
+fn diagnostics() {
+    replay("lsp", "diagnostics.txt");
+}
+
+#[test]
+fn symbols() {
+    replay("lsp", "symbols.txt");
+}
+
+#[test]
+fn positions_count_utf16_code_units() {
+    replay("lsp", "unicode.txt");
+}
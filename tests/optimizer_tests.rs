@@ -0,0 +1,45 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use common::{fixture, lox};
+
+#[test]
+fn optimizing_does_not_change_output() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/optimizer");
+    let mut scripts: Vec<_> = fs::read_dir(dir)
+        .expect("scripts exist")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "lox"))
+        .collect();
+    scripts.sort();
+    assert!(!scripts.is_empty());
+
+    for script in &scripts {
+        let script = script.to_str().unwrap();
+        let plain = lox(&[script]);
+        let optimized = lox(&["--optimize", script]);
+        assert!(!plain.stdout.is_empty(), "{} prints nothing", script);
+        assert_eq!(
+            String::from_utf8_lossy(&optimized.stdout),
+            String::from_utf8_lossy(&plain.stdout),
+            "stdout of {}",
+            script
+        );
+        assert_eq!(
+            String::from_utf8_lossy(&optimized.stderr),
+            String::from_utf8_lossy(&plain.stderr),
+            "stderr of {}",
+            script
+        );
+        assert_eq!(optimized.status.code(), plain.status.code(), "exit code of {}", script);
+    }
+}
+
+#[test]
+fn dump_shows_the_tree_before_and_after() {
+    let output = lox(&["--dump-optimized", "tests/optimizer/folding.lox"]);
+    assert_eq!(String::from_utf8_lossy(&output.stderr), fixture("optimizer", "folding.dump"));
+    assert!(output.status.success());
+}
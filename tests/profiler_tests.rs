@@ -0,0 +1,65 @@
+use std::env;
+use std::fs;
+use std::process::{Command, Output};
+
+/// Profiles `tests/profiler/<name>.lox`, returning its output and the
+/// stacks of the folded file, sorted.
+fn profile(name: &str) -> (Output, Vec<String>) {
+    let folded = env::temp_dir().join(format!("lox-profile-{}-{}.folded", name, std::process::id()));
+    let output = Command::new(env!("CARGO_BIN_EXE_lox-in-rust"))
+        .args(["--profile", folded.to_str().unwrap(), &format!("tests/profiler/{}.lox", name)])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .expect("lox runs");
+    assert!(output.status.success());
+
+    let stacks = fs::read_to_string(&folded).expect("folded stacks are written");
+    let _ = fs::remove_file(&folded);
+    let mut frames: Vec<String> = stacks
+        .lines()
+        .filter_map(|line| line.rsplit_once(' '))
+        .map(|(stack, _)| stack.to_string())
+        .collect();
+    frames.sort();
+    (output, frames)
+}
+
+#[test]
+fn line_hits_count_every_statement() {
+    let (output, frames) = profile("hits");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "3\n");
+
+    // `this.count = ...` and `if (true) ...` have lines too.
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let (_, table) = stderr.split_once("Hits\n").expect("report has line hits");
+    let hits: Vec<(usize, usize)> = table
+        .lines()
+        .map(|row| {
+            let mut columns = row.split_whitespace().map(|column| column.parse().unwrap());
+            (columns.next().unwrap(), columns.next().unwrap())
+        })
+        .collect();
+    assert_eq!(hits, [(1, 1), (3, 1), (7, 3), (11, 1), (12, 2), (13, 6), (15, 1)]);
+    assert_eq!(frames, ["<script>", "<script>;Counter.bump", "<script>;Counter.init"]);
+}
+
+#[test]
+fn functions_are_named_by_class_and_line() {
+    // Both initializers are `init`; the lambdas have no name at all.
+    let (output, frames) = profile("classes");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let rows: Vec<&str> = stderr.lines().skip(1).take_while(|row| !row.is_empty()).collect();
+    let mut functions: Vec<&str> = rows.iter().filter_map(|row| row.split("  ").next()).collect();
+    functions.sort();
+    assert_eq!(functions, ["<fn line 13>", "<fn line 14>", "<script>", "Line.init", "Point.init"]);
+    assert_eq!(
+        frames,
+        [
+            "<script>",
+            "<script>;<fn line 13>",
+            "<script>;<fn line 13>;<fn line 14>",
+            "<script>;<fn line 13>;<fn line 14>;Line.init",
+            "<script>;<fn line 13>;<fn line 14>;Line.init;Point.init",
+        ]
+    );
+}
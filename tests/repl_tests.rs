@@ -0,0 +1,112 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdin, Command, Output, Stdio};
+use std::thread;
+use std::time::Duration;
+
+/// Runs the REPL on `input`, without touching the user's history.
+fn repl(input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_lox-in-rust"))
+        .env_remove("HOME")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("lox runs");
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    child.wait_with_output().expect("lox finishes")
+}
+
+#[test]
+fn errors_leave_the_session_running() {
+    let output = repl("var x = 1;\nclock(1);\nprint x + 1;\n");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "2\n");
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "Expected 0 arguments but got 1.\n[line 1]\n");
+    assert!(output.status.success());
+}
+
+#[test]
+fn expressions_echo_and_continue_over_lines() {
+    let output = repl("(1 +\n2)\n\"a\" + \"b\"\n");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "3\nab\n");
+}
+
+#[test]
+fn reset_forgets_definitions_but_keeps_builtins() {
+    let output = repl("var x = 1;\n:reset\nprint x;\nprint math.floor(2.5);\n");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "2\n");
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "Undefined variable 'x'.\n[line 1]\n");
+}
+
+#[test]
+fn lines_that_look_alike_resolve_separately() {
+    let output = repl("{ var a = 1; print a; }\nvar a = 5; { print a;}\n");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n5\n");
+}
+
+/// Starts the REPL with its pipes open, for tests that talk to it.
+fn spawn_repl() -> Child {
+    Command::new(env!("CARGO_BIN_EXE_lox-in-rust"))
+        .env_remove("HOME")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("lox runs")
+}
+
+/// Sends Ctrl-C's signal to `child`.
+fn interrupt(child: &Child) {
+    let status = Command::new("kill").args(["-INT", &child.id().to_string()]).status().expect("kill runs");
+    assert!(status.success());
+}
+
+/// Sends `input`, then waits until the REPL prints `line`.
+fn send_and_wait(stdin: &mut ChildStdin, stdout: &mut impl BufRead, input: &str, line: &str) {
+    stdin.write_all(input.as_bytes()).unwrap();
+    stdin.flush().unwrap();
+    let mut printed = String::new();
+    stdout.read_line(&mut printed).unwrap();
+    assert_eq!(printed, line);
+}
+
+/// Closes stdin and returns what the REPL printed after that, to stdout
+/// and stderr.
+fn finish(child: Child, stdin: ChildStdin, mut stdout: impl Read) -> (String, String) {
+    drop(stdin);
+    let mut rest = String::new();
+    stdout.read_to_string(&mut rest).unwrap();
+    let output = child.wait_with_output().expect("lox finishes");
+    assert!(output.status.success());
+    (rest, String::from_utf8_lossy(&output.stderr).into_owned())
+}
+
+#[test]
+fn ctrl_c_interrupts_the_running_input() {
+    let mut child = spawn_repl();
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+
+    send_and_wait(&mut stdin, &mut stdout, "var i = 0; print \"spinning\"; while (true) i = i + 1;\n", "spinning\n");
+    interrupt(&child);
+    stdin.write_all(b"print \"after\";\n").unwrap();
+
+    let (rest, stderr) = finish(child, stdin, stdout);
+    assert_eq!(rest, "after\n");
+    assert_eq!(stderr, "Interrupted.\n");
+}
+
+#[test]
+fn ctrl_c_at_the_prompt_does_not_cancel_the_next_input() {
+    let mut child = spawn_repl();
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+
+    send_and_wait(&mut stdin, &mut stdout, "print \"ready\";\n", "ready\n");
+    interrupt(&child);
+    thread::sleep(Duration::from_millis(100)); // Let the handler run first
+    stdin.write_all(b"for (var i = 0; i < 3; i = i + 1) print i;\n").unwrap();
+
+    let (rest, stderr) = finish(child, stdin, stdout);
+    assert_eq!(rest, "0\n1\n2\n");
+    assert_eq!(stderr, "");
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use common::lox;
+
+/// Runs `lox test` with `args` and returns its exit code and output.
+fn lox_test(args: &[&str]) -> (i32, String) {
+    let output = lox(&[&["test"], args].concat());
+    let code = output.status.code().expect("lox exits normally");
+    (code, String::from_utf8(output.stdout).expect("output is UTF-8"))
+}
+
+fn expected(name: &str) -> String {
+    common::fixture("test_runner", name)
+}
+
+#[test]
+fn passing_tests_report_ok() {
+    let (code, output) = lox_test(&["tests/test_runner/passing.lox"]);
+    assert_eq!(output, expected("passing.txt"));
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn failures_as_tap() {
+    let (code, output) = lox_test(&["--tap", "tests/test_runner/failing.lox"]);
+    assert_eq!(output, expected("failing.tap"));
+    assert_eq!(code, 1);
+}
+
+#[test]
+fn failures_as_junit() {
+    let (code, output) = lox_test(&["--junit", "tests/test_runner/failing.lox"]);
+    assert_eq!(output, expected("failing.xml"));
+    assert_eq!(code, 1);
+}
+
+#[test]
+fn missing_files_are_a_usage_error() {
+    let (code, _) = lox_test(&["--tap"]);
+    assert_eq!(code, 64);
+}
+
+#[test]
+fn lists_and_maps_compare_by_contents() {
+    let (code, output) = lox_test(&["tests/test_runner/collections.lox"]);
+    assert_eq!(output, expected("collections.txt"));
+    assert_eq!(code, 1);
+}